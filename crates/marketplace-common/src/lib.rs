@@ -0,0 +1,145 @@
+#![allow(unexpected_cfgs)]
+use anchor_lang::prelude::*;
+
+// Accounts defined here are owned by the marketplace program, so the crate
+// shares its program id.
+declare_id!("DQGMnpU1EvMJchnp9qmo8Nfb8nmmwRuk2MT4AkSyiMQu");
+
+/// Seed of the global marketplace PDA
+pub const MARKETPLACE_SEED: &[u8] = b"marketplace";
+
+/// Seed of the marketplace fee treasury PDA
+pub const TREASURY_SEED: &[u8] = b"treasury";
+
+/// Seed of the PDA a registered trading program signs `update_stats` with
+pub const SALE_AUTHORITY_SEED: &[u8] = b"sale_authority";
+
+/// Maximum number of trading programs allowed to report sales
+pub const MAX_REGISTERED_PROGRAMS: usize = 8;
+
+/// Maximum number of SPL mints accepted as listing currency
+pub const MAX_PAYMENT_MINTS: usize = 8;
+
+/// Denominator for every basis point value (100% = 10000)
+pub const BASIS_POINTS_DENOMINATOR: u64 = 10000;
+
+/// Highest platform fee the admin can configure (10%)
+pub const MAX_FEE_BASIS_POINTS: u16 = 1000;
+
+/// Layout version of `MarketplaceState`, bumped whenever fields are appended so
+/// `migrate_marketplace` knows which defaults to fill in (0 = before versioning)
+pub const MARKETPLACE_VERSION: u8 = 1;
+
+/// Default shortest auction the marketplace allows (1 hour)
+pub const DEFAULT_MIN_AUCTION_DURATION: i64 = 3600;
+
+/// Default longest auction the marketplace allows (30 days)
+pub const DEFAULT_MAX_AUCTION_DURATION: i64 = 2592000;
+
+/// Default upper bound on an auction's anti-sniping window (1 hour)
+pub const DEFAULT_MAX_EXTENSION_WINDOW: i64 = 3600;
+
+/// Default upper bound on a single anti-sniping extension (1 hour)
+pub const DEFAULT_MAX_EXTENSION_AMOUNT: i64 = 3600;
+
+#[account]
+#[derive(InitSpace)]
+pub struct MarketplaceState {
+    pub authority: Pubkey,           // 32
+    pub fee_basis_points: u16,       // 2 (e.g., 250 = 2.5%)
+    pub treasury: Pubkey,            // 32
+    pub treasury_bump: u8,           // 1
+    pub is_paused: bool,             // 1
+    pub total_volume: u64,           // 8
+    pub total_sales: u64,            // 8
+    pub bump: u8,                    // 1
+    #[max_len(MAX_REGISTERED_PROGRAMS)]
+    pub registered_programs: Vec<Pubkey>, // 4 + 32 * MAX_REGISTERED_PROGRAMS
+    #[max_len(MAX_PAYMENT_MINTS)]
+    pub accepted_payment_mints: Vec<Pubkey>, // 4 + 32 * MAX_PAYMENT_MINTS
+    pub min_auction_duration: i64,   // 8
+    pub max_auction_duration: i64,   // 8
+    pub max_extension_window: i64,   // 8
+    pub max_extension_amount: i64,   // 8
+    pub version: u8,                 // 1
+}
+
+#[error_code]
+pub enum MarketplaceError {
+    #[msg("Fee basis points cannot exceed 1000 (10%)")]
+    FeeTooHigh,
+    #[msg("Insufficient funds in treasury")]
+    InsufficientFunds,
+    #[msg("Marketplace is currently paused")]
+    MarketplacePaused,
+    #[msg("Math overflow occurred")]
+    MathOverflow,
+    #[msg("Unauthorized access")]
+    Unauthorized,
+    #[msg("Trading program is already registered")]
+    ProgramAlreadyRegistered,
+    #[msg("Trading program is not registered")]
+    ProgramNotRegistered,
+    #[msg("Registered trading program list is full")]
+    TooManyRegisteredPrograms,
+    #[msg("Payment mint is already accepted")]
+    PaymentMintAlreadyAccepted,
+    #[msg("Payment mint is not accepted")]
+    PaymentMintNotAccepted,
+    #[msg("Accepted payment mint list is full")]
+    TooManyPaymentMints,
+    #[msg("Invalid auction duration or extension bounds")]
+    InvalidAuctionBounds,
+    #[msg("Marketplace account already uses the current layout")]
+    AlreadyMigrated,
+}
+
+// Helper functions for other contracts to use
+impl MarketplaceState {
+    pub const INIT_SPACE: usize = 32 + 2 + 32 + 1 + 1 + 8 + 8 + 1
+        + 4 + 32 * MAX_REGISTERED_PROGRAMS
+        + 4 + 32 * MAX_PAYMENT_MINTS
+        + 8 + 8 + 8 + 8
+        + 1; // 638 bytes
+
+    pub fn is_paused(&self) -> bool {
+        self.is_paused
+    }
+
+    pub fn get_fee_basis_points(&self) -> u16 {
+        self.fee_basis_points
+    }
+
+    pub fn get_treasury(&self) -> Pubkey {
+        self.treasury
+    }
+
+    pub fn is_registered_program(&self, program_id: &Pubkey) -> bool {
+        self.registered_programs.contains(program_id)
+    }
+
+    pub fn is_accepted_payment_mint(&self, mint: &Pubkey) -> bool {
+        self.accepted_payment_mints.contains(mint)
+    }
+
+    pub fn calculate_platform_fee(&self, sale_amount: u64) -> Result<u64> {
+        calculate_fee(sale_amount, self.fee_basis_points)
+    }
+}
+
+/// Apply a basis point rate to an amount, rounding down
+pub fn calculate_fee(amount: u64, basis_points: u16) -> Result<u64> {
+    let fee = (amount as u128)
+        .checked_mul(basis_points as u128)
+        .ok_or(MarketplaceError::MathOverflow)?
+        .checked_div(BASIS_POINTS_DENOMINATOR as u128)
+        .ok_or(MarketplaceError::MathOverflow)?;
+
+    u64::try_from(fee).map_err(|_| MarketplaceError::MathOverflow.into())
+}
+
+// Cross-program invocation helper for other contracts
+pub fn check_marketplace_active(marketplace: &MarketplaceState) -> Result<()> {
+    require!(!marketplace.is_paused, MarketplaceError::MarketplacePaused);
+    Ok(())
+}
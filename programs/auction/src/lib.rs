@@ -1,10 +1,18 @@
 #![allow(unexpected_cfgs)]
 #![allow(deprecated)]
+#![allow(clippy::too_many_arguments)]
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer, Mint};
-use anchor_spl::associated_token::AssociatedToken;
+use anchor_lang::solana_program::hash::hashv;
+use anchor_spl::token::{self, CloseAccount, Token, TokenAccount, Transfer, Mint};
+use anchor_spl::associated_token::{self, get_associated_token_address, AssociatedToken, Create};
+use marketplace::program::Marketplace;
+use marketplace::{check_marketplace_active, MarketplaceState, MARKETPLACE_SEED, SALE_AUTHORITY_SEED};
+use royalty::{calculate_creator_royalties, load_metadata, total_royalties, CreatorRoyalty, RoyaltyConfig};
 
-declare_id!("AuctionProgram111111111111111111111111111111");
+declare_id!("DfXY6aSHhYQmoHKtDLRjCrXYsc6dV2NA8aJe4tuv3SFz");
+
+/// Number of most recent bids kept in an English auction's bid history
+pub const MAX_BID_HISTORY: usize = 32;
 
 #[program]
 pub mod auction {
@@ -17,57 +25,577 @@ pub mod auction {
         end_time: i64,
         reserve_price: u64,
         min_bid_increment: u64,
+        buy_now_price: Option<u64>,
+        anti_sniping: AntiSniping,
+        reserve_mode: ReserveMode,
+        payment_mint: Option<Pubkey>,
     ) -> Result<()> {
         // Validate marketplace is active
-        require!(!ctx.accounts.marketplace.is_paused, AuctionError::MarketplacePaused);
-        
-        let clock = Clock::get()?;
+        check_marketplace_active(&ctx.accounts.marketplace)?;
         
         // Validate auction timing
-        require!(start_time >= clock.unix_timestamp, AuctionError::InvalidStartTime);
-        require!(end_time > start_time, AuctionError::InvalidEndTime);
+        validate_auction_window(&ctx.accounts.marketplace, start_time, end_time)?;
         require!(reserve_price > 0, AuctionError::InvalidReservePrice);
         require!(min_bid_increment > 0, AuctionError::InvalidBidIncrement);
-        
-        // Duration must be at least 1 hour and max 30 days
-        let duration = end_time - start_time;
-        require!(duration >= 3600, AuctionError::AuctionTooShort); // 1 hour
-        require!(duration <= 2592000, AuctionError::AuctionTooLong); // 30 days
+        if let Some(buy_now_price) = buy_now_price {
+            require!(buy_now_price >= reserve_price, AuctionError::InvalidBuyNowPrice);
+        }
+
+        // Anti-sniping rules must stay within the marketplace bounds
+        let marketplace = &ctx.accounts.marketplace;
+        require!(
+            anti_sniping.extension_window >= 0
+                && anti_sniping.extension_window <= marketplace.max_extension_window,
+            AuctionError::InvalidExtensionWindow
+        );
+        require!(
+            anti_sniping.extension_amount >= 0
+                && anti_sniping.extension_amount <= marketplace.max_extension_amount,
+            AuctionError::InvalidExtensionAmount
+        );
+        if let Some(max_end_time) = anti_sniping.max_end_time {
+            // Extensions may not stretch the auction past the marketplace's longest duration
+            let max_duration = max_end_time.checked_sub(start_time)
+                .ok_or(AuctionError::MathOverflow)?;
+            require!(
+                max_end_time >= end_time && max_duration <= marketplace.max_auction_duration,
+                AuctionError::InvalidMaxEndTime
+            );
+        }
+        if let ReserveMode::Soft { grace_period } = reserve_mode {
+            require!(grace_period > 0, AuctionError::InvalidGracePeriod);
+        }
+
+        // Transfer NFT to auction escrow
+        open_auction_escrow(ctx.accounts, payment_mint)?;
+
+        // Initialize auction state
+        let auction = &mut ctx.accounts.auction;
+        auction.seller = ctx.accounts.seller.key();
+        auction.mint = ctx.accounts.mint.key();
+        auction.auction_type = AuctionType::English;
+        auction.start_time = start_time;
+        auction.end_time = end_time;
+        auction.reserve_price = reserve_price;
+        auction.min_bid_increment = min_bid_increment;
+        auction.buy_now_price = buy_now_price;
+        auction.anti_sniping = anti_sniping;
+        auction.reserve_mode = reserve_mode;
+        auction.seller_accepted_bid = false;
+        auction.start_price = 0;
+        auction.price_decay = PriceDecay::Linear;
+        auction.reveal_end_time = end_time;
+        auction.sealed_pricing = SealedBidPricing::FirstPrice;
+        auction.payment_mint = payment_mint;
+        auction.highest_bid = 0;
+        auction.second_highest_bid = 0;
+        auction.highest_bidder = None;
+        auction.total_bids = 0;
+        auction.pending_refunds = 0;
+        auction.open_sealed_bids = 0;
+        auction.open_refund_ledgers = 0;
+        auction.bid_history_open = false;
+        auction.is_settled = false;
+        auction.is_canceled = false;
+        auction.bump = ctx.bumps.auction;
+        auction.winner = None;
+
+        // English auctions keep a ring buffer of their most recent bids
+        let auction_key = auction.key();
+        let bid_history = ctx.accounts.bid_history.as_mut()
+            .ok_or(AuctionError::BidHistoryMissing)?;
+        bid_history.auction = auction_key;
+        bid_history.head = 0;
+        bid_history.bids = Vec::new();
+        bid_history.bump = ctx.bumps.bid_history.ok_or(AuctionError::BidHistoryMissing)?;
+        ctx.accounts.auction.bid_history_open = true;
+
+        let auction = &ctx.accounts.auction;
+        emit!(AuctionCreated {
+            auction: auction.key(),
+            seller: auction.seller,
+            mint: auction.mint,
+            start_time,
+            end_time,
+            reserve_price,
+            min_bid_increment,
+            buy_now_price,
+            anti_sniping,
+            reserve_mode,
+            payment_mint,
+        });
+
+        Ok(())
+    }
+
+    /// Create a Dutch auction whose price declines from `start_price` to `floor_price`
+    pub fn create_dutch_auction(
+        ctx: Context<CreateAuction>,
+        start_time: i64,
+        end_time: i64,
+        start_price: u64,
+        floor_price: u64,
+        price_decay: PriceDecay,
+        payment_mint: Option<Pubkey>,
+    ) -> Result<()> {
+        // Validate marketplace is active
+        check_marketplace_active(&ctx.accounts.marketplace)?;
+
+        // Validate auction timing and price curve
+        validate_auction_window(&ctx.accounts.marketplace, start_time, end_time)?;
+        require!(floor_price > 0, AuctionError::InvalidReservePrice);
+        require!(start_price > floor_price, AuctionError::InvalidStartPrice);
+        if let PriceDecay::Stepwise { step_interval } = price_decay {
+            require!(
+                step_interval > 0 && step_interval <= end_time - start_time,
+                AuctionError::InvalidStepInterval
+            );
+        }
 
         // Transfer NFT to auction escrow
-        let transfer_ctx = CpiContext::new(
+        open_auction_escrow(ctx.accounts, payment_mint)?;
+
+        // Initialize auction state, the floor doubles as the reserve price
+        let auction = &mut ctx.accounts.auction;
+        auction.seller = ctx.accounts.seller.key();
+        auction.mint = ctx.accounts.mint.key();
+        auction.auction_type = AuctionType::Dutch;
+        auction.start_time = start_time;
+        auction.end_time = end_time;
+        auction.reserve_price = floor_price;
+        auction.min_bid_increment = 0;
+        auction.buy_now_price = None;
+        auction.anti_sniping = AntiSniping::disabled();
+        auction.reserve_mode = ReserveMode::Hard;
+        auction.seller_accepted_bid = false;
+        auction.start_price = start_price;
+        auction.price_decay = price_decay;
+        auction.reveal_end_time = end_time;
+        auction.sealed_pricing = SealedBidPricing::FirstPrice;
+        auction.payment_mint = payment_mint;
+        auction.highest_bid = 0;
+        auction.second_highest_bid = 0;
+        auction.highest_bidder = None;
+        auction.total_bids = 0;
+        auction.pending_refunds = 0;
+        auction.open_sealed_bids = 0;
+        auction.open_refund_ledgers = 0;
+        auction.bid_history_open = false;
+        auction.is_settled = false;
+        auction.is_canceled = false;
+        auction.bump = ctx.bumps.auction;
+        auction.winner = None;
+
+        emit!(DutchAuctionCreated {
+            auction: auction.key(),
+            seller: auction.seller,
+            mint: auction.mint,
+            start_time,
+            end_time,
+            start_price,
+            floor_price,
+            price_decay,
+            payment_mint,
+        });
+
+        Ok(())
+    }
+
+    /// Buy instantly: at the current price of a Dutch auction, or at the buy-now price of an
+    /// English auction, refunding its highest bidder
+    pub fn buy_now<'info>(ctx: Context<'_, '_, '_, 'info, BuyNow<'info>>) -> Result<()> {
+        let clock = Clock::get()?;
+        check_marketplace_active(&ctx.accounts.marketplace)?;
+
+        // Validate auction state (read-only access)
+        require!(!ctx.accounts.auction.is_settled, AuctionError::AuctionAlreadySettled);
+        require!(!ctx.accounts.auction.is_canceled, AuctionError::AuctionCanceled);
+        require!(clock.unix_timestamp >= ctx.accounts.auction.start_time, AuctionError::AuctionNotStarted);
+        require!(clock.unix_timestamp < ctx.accounts.auction.end_time, AuctionError::AuctionEnded);
+
+        // Store values before mutable access
+        let sale_price = match ctx.accounts.auction.auction_type {
+            AuctionType::Dutch => ctx.accounts.auction.current_dutch_price(clock.unix_timestamp)?,
+            AuctionType::English => {
+                // Buy-now is withdrawn once bidding reaches the buy-now price
+                let buy_now_price = ctx.accounts.auction.buy_now_price
+                    .ok_or(AuctionError::BuyNowNotAvailable)?;
+                require!(ctx.accounts.auction.highest_bid < buy_now_price, AuctionError::BuyNowNotAvailable);
+                buy_now_price
+            }
+            AuctionType::SealedBid => return Err(AuctionError::BuyNowNotAvailable.into()),
+        };
+        let previous_bidder = ctx.accounts.auction.highest_bidder;
+        let previous_bid = ctx.accounts.auction.highest_bid;
+        let auction_bump = ctx.accounts.auction.bump;
+        let mint_key = ctx.accounts.auction.mint;
+        let seller_key = ctx.accounts.auction.seller;
+        let auction_key = ctx.accounts.auction.key();
+        let payment_mint = ctx.accounts.auction.payment_mint;
+
+        let auction_seeds = &[
+            b"auction",
+            mint_key.as_ref(),
+            seller_key.as_ref(),
+            &[auction_bump],
+        ];
+        let signer = &[&auction_seeds[..]];
+
+        // Credit the current highest bidder of an English auction with a refund
+        if let Some(previous_bidder_key) = previous_bidder {
+            if previous_bid > 0 {
+                let previous_bidder_refund = ctx.accounts.previous_bidder_refund.as_mut()
+                    .ok_or(AuctionError::RefundLedgerMissing)?;
+                credit_bid_refund(previous_bidder_refund, auction_key, previous_bidder_key, previous_bid)?;
+                ctx.accounts.auction.track_refund_credit(previous_bid)?;
+            }
+        }
+
+        // Calculate platform fee and creator royalties
+        let platform_fee = ctx.accounts.marketplace.calculate_platform_fee(sale_price)?;
+        let metadata = load_metadata(&ctx.accounts.metadata.to_account_info(), &mint_key)?;
+        let creator_royalties = calculate_creator_royalties(&ctx.accounts.royalty_config, &metadata, sale_price);
+        let royalty_fee = total_royalties(&creator_royalties)?;
+        let seller_proceeds = sale_price.checked_sub(platform_fee)
+            .and_then(|amount| amount.checked_sub(royalty_fee))
+            .ok_or(AuctionError::MathOverflow)?;
+
+        // Transfer platform fee to treasury
+        if platform_fee > 0 {
+            let treasury_destination = payment_destination(
+                &ctx.accounts.treasury,
+                &ctx.accounts.treasury_payment_account,
+                payment_mint,
+            )?;
+            pay_from_buyer(
+                &ctx.accounts.buyer,
+                &ctx.accounts.buyer_payment_account,
+                treasury_destination,
+                payment_mint,
+                &ctx.accounts.system_program,
+                &ctx.accounts.token_program,
+                platform_fee,
+            )?;
+        }
+
+        // Pay creator royalties to the accounts passed in remaining accounts
+        // (creator wallets for lamport auctions, their payment mint ATAs otherwise)
+        for creator in &creator_royalties {
+            if creator.amount > 0 {
+                let creator_destination = match payment_mint {
+                    None => creator.address,
+                    Some(payment_mint) => get_associated_token_address(&creator.address, &payment_mint),
+                };
+                let creator_account = ctx.remaining_accounts
+                    .iter()
+                    .find(|acc| acc.key() == creator_destination)
+                    .ok_or(AuctionError::CreatorAccountNotFound)?;
+
+                pay_from_buyer(
+                    &ctx.accounts.buyer,
+                    &ctx.accounts.buyer_payment_account,
+                    creator_account.clone(),
+                    payment_mint,
+                    &ctx.accounts.system_program,
+                    &ctx.accounts.token_program,
+                    creator.amount,
+                )?;
+            }
+        }
+
+        // Transfer proceeds to seller
+        let seller_destination = payment_destination(
+            &ctx.accounts.seller,
+            &ctx.accounts.seller_payment_account,
+            payment_mint,
+        )?;
+        pay_from_buyer(
+            &ctx.accounts.buyer,
+            &ctx.accounts.buyer_payment_account,
+            seller_destination,
+            payment_mint,
+            &ctx.accounts.system_program,
+            &ctx.accounts.token_program,
+            seller_proceeds,
+        )?;
+
+        // Transfer NFT to buyer
+        let nft_transfer_ctx = CpiContext::new_with_signer(
             ctx.accounts.token_program.to_account_info(),
             Transfer {
-                from: ctx.accounts.seller_token_account.to_account_info(),
-                to: ctx.accounts.auction_token_account.to_account_info(),
-                authority: ctx.accounts.seller.to_account_info(),
+                from: ctx.accounts.auction_token_account.to_account_info(),
+                to: ctx.accounts.buyer_token_account.to_account_info(),
+                authority: ctx.accounts.auction.to_account_info(),
             },
+            signer,
+        );
+        token::transfer(nft_transfer_ctx, 1)?;
+
+        // Update auction state
+        let auction = &mut ctx.accounts.auction;
+        auction.highest_bid = sale_price;
+        auction.highest_bidder = Some(ctx.accounts.buyer.key());
+        auction.is_settled = true;
+        auction.winner = Some(ctx.accounts.buyer.key());
+
+        // Update marketplace stats via CPI, signed by our sale authority
+        let sale_authority_seeds = &[SALE_AUTHORITY_SEED, &[ctx.bumps.sale_authority]];
+        let sale_authority_signer = &[&sale_authority_seeds[..]];
+        let cpi_accounts = marketplace::cpi::accounts::UpdateStats {
+            marketplace: ctx.accounts.marketplace.to_account_info(),
+            sale_authority: ctx.accounts.sale_authority.to_account_info(),
+            caller_program: ctx.accounts.auction_program.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.marketplace_program.to_account_info(),
+            cpi_accounts,
+            sale_authority_signer,
         );
-        token::transfer(transfer_ctx, 1)?;
+        // Lifetime volume is tracked in lamports, so token sales only count towards sales
+        let volume = if payment_mint.is_none() { sale_price } else { 0 };
+        marketplace::cpi::update_stats(cpi_ctx, volume)?;
+
+        emit!(AuctionSettled {
+            auction: auction_key,
+            seller: seller_key,
+            winner: ctx.accounts.buyer.key(),
+            final_price: sale_price,
+            payment_mint,
+            platform_fee,
+            royalty_fee,
+            seller_proceeds,
+            creators: creator_royalties,
+        });
+
+        Ok(())
+    }
+
+    /// Create a sealed-bid auction: bids are committed until `end_time` and revealed until `reveal_end_time`
+    pub fn create_sealed_auction(
+        ctx: Context<CreateAuction>,
+        start_time: i64,
+        end_time: i64,
+        reveal_end_time: i64,
+        reserve_price: u64,
+        sealed_pricing: SealedBidPricing,
+        payment_mint: Option<Pubkey>,
+    ) -> Result<()> {
+        // Validate marketplace is active
+        check_marketplace_active(&ctx.accounts.marketplace)?;
+
+        // Validate auction timing, the reveal phase follows the commit phase
+        validate_auction_window(&ctx.accounts.marketplace, start_time, end_time)?;
+        require!(reveal_end_time > end_time, AuctionError::InvalidRevealEndTime);
+        require!(reserve_price > 0, AuctionError::InvalidReservePrice);
+
+        // Transfer NFT to auction escrow
+        open_auction_escrow(ctx.accounts, payment_mint)?;
 
         // Initialize auction state
         let auction = &mut ctx.accounts.auction;
         auction.seller = ctx.accounts.seller.key();
         auction.mint = ctx.accounts.mint.key();
+        auction.auction_type = AuctionType::SealedBid;
         auction.start_time = start_time;
         auction.end_time = end_time;
         auction.reserve_price = reserve_price;
-        auction.min_bid_increment = min_bid_increment;
+        auction.min_bid_increment = 0;
+        auction.buy_now_price = None;
+        auction.anti_sniping = AntiSniping::disabled();
+        auction.reserve_mode = ReserveMode::Hard;
+        auction.seller_accepted_bid = false;
+        auction.start_price = 0;
+        auction.price_decay = PriceDecay::Linear;
+        auction.reveal_end_time = reveal_end_time;
+        auction.sealed_pricing = sealed_pricing;
+        auction.payment_mint = payment_mint;
         auction.highest_bid = 0;
+        auction.second_highest_bid = 0;
         auction.highest_bidder = None;
         auction.total_bids = 0;
+        auction.pending_refunds = 0;
+        auction.open_sealed_bids = 0;
+        auction.open_refund_ledgers = 0;
+        auction.bid_history_open = false;
         auction.is_settled = false;
         auction.is_canceled = false;
         auction.bump = ctx.bumps.auction;
+        auction.winner = None;
 
-        emit!(AuctionCreated {
+        emit!(SealedAuctionCreated {
             auction: auction.key(),
             seller: auction.seller,
             mint: auction.mint,
             start_time,
             end_time,
+            reveal_end_time,
             reserve_price,
-            min_bid_increment,
+            sealed_pricing,
+            payment_mint,
+        });
+
+        Ok(())
+    }
+
+    /// Commit a sealed bid as `hash(bidder || amount || salt)`, escrowing collateral that covers the bid
+    pub fn commit_bid(ctx: Context<CommitBid>, commitment: [u8; 32], collateral: u64) -> Result<()> {
+        let clock = Clock::get()?;
+
+        // Validate auction state (read-only access)
+        require!(ctx.accounts.auction.auction_type == AuctionType::SealedBid, AuctionError::NotSealedBidAuction);
+        require!(!ctx.accounts.auction.is_settled, AuctionError::AuctionAlreadySettled);
+        require!(!ctx.accounts.auction.is_canceled, AuctionError::AuctionCanceled);
+        require!(clock.unix_timestamp >= ctx.accounts.auction.start_time, AuctionError::AuctionNotStarted);
+        require!(clock.unix_timestamp < ctx.accounts.auction.end_time, AuctionError::AuctionEnded);
+        require!(collateral >= ctx.accounts.auction.reserve_price, AuctionError::BidBelowReserve);
+
+        // Transfer collateral into escrow
+        match ctx.accounts.auction.payment_mint {
+            None => {
+                let collateral_transfer_ctx = CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.bidder.to_account_info(),
+                        to: ctx.accounts.auction.to_account_info(),
+                    },
+                );
+                anchor_lang::system_program::transfer(collateral_transfer_ctx, collateral)?;
+            }
+            Some(payment_mint) => {
+                let bidder_payment_account = ctx.accounts.bidder_payment_account.as_ref()
+                    .ok_or(AuctionError::PaymentAccountMissing)?;
+                require_keys_eq!(bidder_payment_account.mint, payment_mint, AuctionError::InvalidPaymentAccount);
+                let auction_payment_account = auction_escrow_account(
+                    &ctx.accounts.auction,
+                    &ctx.accounts.auction_payment_account,
+                )?;
+
+                let collateral_transfer_ctx = CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: bidder_payment_account.to_account_info(),
+                        to: auction_payment_account.to_account_info(),
+                        authority: ctx.accounts.bidder.to_account_info(),
+                    },
+                );
+                token::transfer(collateral_transfer_ctx, collateral)?;
+            }
+        }
+
+        let sealed_bid = &mut ctx.accounts.sealed_bid;
+        sealed_bid.auction = ctx.accounts.auction.key();
+        sealed_bid.bidder = ctx.accounts.bidder.key();
+        sealed_bid.commitment = commitment;
+        sealed_bid.collateral = collateral;
+        sealed_bid.revealed_amount = None;
+        sealed_bid.bump = ctx.bumps.sealed_bid;
+
+        let auction = &mut ctx.accounts.auction;
+        auction.total_bids = auction.total_bids.checked_add(1)
+            .ok_or(AuctionError::MathOverflow)?;
+        auction.open_sealed_bids = auction.open_sealed_bids.checked_add(1)
+            .ok_or(AuctionError::MathOverflow)?;
+
+        emit!(SealedBidCommitted {
+            auction: auction.key(),
+            bidder: sealed_bid.bidder,
+            collateral,
+        });
+
+        Ok(())
+    }
+
+    /// Reveal a committed sealed bid during the reveal phase
+    pub fn reveal_bid(ctx: Context<RevealBid>, amount: u64, salt: [u8; 32]) -> Result<()> {
+        let clock = Clock::get()?;
+
+        // Validate auction state (read-only access)
+        require!(ctx.accounts.auction.auction_type == AuctionType::SealedBid, AuctionError::NotSealedBidAuction);
+        require!(clock.unix_timestamp >= ctx.accounts.auction.end_time, AuctionError::RevealNotStarted);
+        require!(clock.unix_timestamp < ctx.accounts.auction.reveal_end_time, AuctionError::RevealEnded);
+        require!(ctx.accounts.sealed_bid.revealed_amount.is_none(), AuctionError::BidAlreadyRevealed);
+
+        // The commitment binds the bidder, so it cannot be replayed by someone else
+        let bidder_key = ctx.accounts.bidder.key();
+        let computed = hashv(&[bidder_key.as_ref(), &amount.to_le_bytes(), &salt]);
+        require!(computed.to_bytes() == ctx.accounts.sealed_bid.commitment, AuctionError::InvalidReveal);
+        require!(amount <= ctx.accounts.sealed_bid.collateral, AuctionError::BidExceedsCollateral);
+
+        ctx.accounts.sealed_bid.revealed_amount = Some(amount);
+
+        // Only bids at or above reserve compete; ties go to the earlier reveal
+        let auction = &mut ctx.accounts.auction;
+        if amount >= auction.reserve_price {
+            if amount > auction.highest_bid {
+                auction.second_highest_bid = auction.highest_bid;
+                auction.highest_bid = amount;
+                auction.highest_bidder = Some(bidder_key);
+            } else if amount > auction.second_highest_bid {
+                auction.second_highest_bid = amount;
+            }
+        }
+
+        emit!(SealedBidRevealed {
+            auction: auction.key(),
+            bidder: bidder_key,
+            amount,
+            is_leading: auction.highest_bidder == Some(bidder_key),
+        });
+
+        Ok(())
+    }
+
+    /// Return sealed-bid collateral after the reveal phase, less the clearing price for the winner
+    pub fn refund_sealed_bid(ctx: Context<RefundSealedBid>) -> Result<()> {
+        let clock = Clock::get()?;
+        let auction = &ctx.accounts.auction;
+        require!(auction.auction_type == AuctionType::SealedBid, AuctionError::NotSealedBidAuction);
+        require!(clock.unix_timestamp >= auction.reveal_end_time, AuctionError::AuctionNotEnded);
+
+        // The winner is only refunded the excess once the sale has been settled
+        let bidder_key = ctx.accounts.sealed_bid.bidder;
+        let collateral = ctx.accounts.sealed_bid.collateral;
+        let is_winner = auction.highest_bidder == Some(bidder_key)
+            && auction.highest_bid >= auction.reserve_price;
+        let refund_amount = if is_winner {
+            require!(auction.is_settled, AuctionError::AuctionNotSettled);
+            collateral.checked_sub(auction.clearing_price())
+                .ok_or(AuctionError::MathOverflow)?
+        } else {
+            collateral
+        };
+
+        if refund_amount > 0 {
+            let auction_seeds = &[
+                b"auction",
+                auction.mint.as_ref(),
+                auction.seller.as_ref(),
+                &[auction.bump],
+            ];
+            let signer = &[&auction_seeds[..]];
+            let refund_destination = payment_destination(
+                &ctx.accounts.bidder,
+                &ctx.accounts.bidder_payment_account,
+                auction.payment_mint,
+            )?;
+            pay_from_auction(
+                auction,
+                &ctx.accounts.auction_payment_account,
+                refund_destination,
+                &ctx.accounts.token_program,
+                signer,
+                refund_amount,
+            )?;
+        }
+
+        let auction = &mut ctx.accounts.auction;
+        auction.open_sealed_bids = auction.open_sealed_bids.checked_sub(1)
+            .ok_or(AuctionError::MathOverflow)?;
+
+        emit!(SealedBidRefunded {
+            auction: auction.key(),
+            bidder: bidder_key,
+            amount: refund_amount,
         });
 
         Ok(())
@@ -78,15 +606,21 @@ pub mod auction {
         let clock = Clock::get()?;
         
         // Validate auction state (read-only access)
+        require!(ctx.accounts.auction.auction_type == AuctionType::English, AuctionError::NotEnglishAuction);
         require!(!ctx.accounts.auction.is_settled, AuctionError::AuctionAlreadySettled);
         require!(!ctx.accounts.auction.is_canceled, AuctionError::AuctionCanceled);
         require!(clock.unix_timestamp >= ctx.accounts.auction.start_time, AuctionError::AuctionNotStarted);
         require!(clock.unix_timestamp < ctx.accounts.auction.end_time, AuctionError::AuctionEnded);
-        require!(bid_amount >= ctx.accounts.auction.reserve_price, AuctionError::BidBelowReserve);
+        // Soft reserve auctions accept bids below reserve, opening at the minimum increment
+        let opening_bid = match ctx.accounts.auction.reserve_mode {
+            ReserveMode::Hard => ctx.accounts.auction.reserve_price,
+            ReserveMode::Soft { .. } => ctx.accounts.auction.min_bid_increment,
+        };
+        require!(bid_amount >= opening_bid, AuctionError::BidBelowReserve);
         
         // Validate bid amount
         let required_bid = if ctx.accounts.auction.highest_bid == 0 {
-            ctx.accounts.auction.reserve_price
+            opening_bid
         } else {
             ctx.accounts.auction.highest_bid.checked_add(ctx.accounts.auction.min_bid_increment)
                 .ok_or(AuctionError::MathOverflow)?
@@ -96,50 +630,72 @@ pub mod auction {
         // Store values before mutable access
         let previous_bidder = ctx.accounts.auction.highest_bidder;
         let previous_bid = ctx.accounts.auction.highest_bid;
-        let auction_bump = ctx.accounts.auction.bump;
-        let mint_key = ctx.accounts.auction.mint;
-        let seller_key = ctx.accounts.auction.seller;
+        let payment_mint = ctx.accounts.auction.payment_mint;
+        let auction_key = ctx.accounts.auction.key();
+        let bidder_key = ctx.accounts.bidder.key();
 
-        // Refund previous highest bidder
+        // Set up the bidder's refund ledger on their first bid
+        let bidder_refund = &mut ctx.accounts.bidder_refund;
+        if bidder_refund.bidder == Pubkey::default() {
+            bidder_refund.auction = auction_key;
+            bidder_refund.bidder = bidder_key;
+            bidder_refund.refundable = 0;
+            bidder_refund.total_credited = 0;
+            bidder_refund.total_withdrawn = 0;
+            bidder_refund.bump = ctx.bumps.bidder_refund;
+            ctx.accounts.auction.open_refund_ledgers = ctx.accounts.auction.open_refund_ledgers
+                .checked_add(1)
+                .ok_or(AuctionError::MathOverflow)?;
+        }
+
+        // Credit the outbid amount to the previous bidder's refund ledger, the funds stay in
+        // escrow until they call `withdraw_refund`
         if let Some(previous_bidder_key) = previous_bidder {
             if previous_bid > 0 {
-                // Transfer previous bid back to previous bidder
-                let auction_seeds = &[
-                    b"auction",
-                    mint_key.as_ref(),
-                    seller_key.as_ref(),
-                    &[auction_bump],
-                ];
-                let signer = &[&auction_seeds[..]];
-
-                let refund_ctx = CpiContext::new_with_signer(
+                let previous_bidder_refund = if previous_bidder_key == bidder_key {
+                    &mut ctx.accounts.bidder_refund
+                } else {
+                    ctx.accounts.previous_bidder_refund.as_mut()
+                        .ok_or(AuctionError::RefundLedgerMissing)?
+                };
+                credit_bid_refund(previous_bidder_refund, auction_key, previous_bidder_key, previous_bid)?;
+                ctx.accounts.auction.track_refund_credit(previous_bid)?;
+            }
+        }
+
+        // Transfer new bid into escrow
+        match payment_mint {
+            None => {
+                let bid_transfer_ctx = CpiContext::new(
                     ctx.accounts.system_program.to_account_info(),
                     anchor_lang::system_program::Transfer {
-                        from: ctx.accounts.auction.to_account_info(),
-                        to: ctx.accounts.previous_bidder.to_account_info(),
+                        from: ctx.accounts.bidder.to_account_info(),
+                        to: ctx.accounts.auction.to_account_info(),
                     },
-                    signer,
                 );
-                anchor_lang::system_program::transfer(refund_ctx, previous_bid)?;
+                anchor_lang::system_program::transfer(bid_transfer_ctx, bid_amount)?;
+            }
+            Some(payment_mint) => {
+                let bidder_payment_account = ctx.accounts.bidder_payment_account.as_ref()
+                    .ok_or(AuctionError::PaymentAccountMissing)?;
+                require_keys_eq!(bidder_payment_account.mint, payment_mint, AuctionError::InvalidPaymentAccount);
+                let auction_payment_account = auction_escrow_account(
+                    &ctx.accounts.auction,
+                    &ctx.accounts.auction_payment_account,
+                )?;
 
-                emit!(BidRefunded {
-                    auction: ctx.accounts.auction.key(),
-                    bidder: previous_bidder_key,
-                    amount: previous_bid,
-                });
+                let bid_transfer_ctx = CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: bidder_payment_account.to_account_info(),
+                        to: auction_payment_account.to_account_info(),
+                        authority: ctx.accounts.bidder.to_account_info(),
+                    },
+                );
+                token::transfer(bid_transfer_ctx, bid_amount)?;
             }
         }
 
-        // Transfer new bid to auction account
-        let bid_transfer_ctx = CpiContext::new(
-            ctx.accounts.system_program.to_account_info(),
-            anchor_lang::system_program::Transfer {
-                from: ctx.accounts.bidder.to_account_info(),
-                to: ctx.accounts.auction.to_account_info(),
-            },
-        );
-        anchor_lang::system_program::transfer(bid_transfer_ctx, bid_amount)?;
-
         // Now get mutable reference to update auction state
         let auction = &mut ctx.accounts.auction;
         
@@ -149,12 +705,22 @@ pub mod auction {
         auction.total_bids = auction.total_bids.checked_add(1)
             .ok_or(AuctionError::MathOverflow)?;
 
-        // Extend auction if bid placed in last 10 minutes
+        // Extend auction if bid placed within the extension window, never past the hard cap
         let time_remaining = auction.end_time - clock.unix_timestamp;
-        if time_remaining <= 600 { // 10 minutes
-            auction.end_time = clock.unix_timestamp + 600; // Extend by 10 minutes
+        if time_remaining <= auction.anti_sniping.extension_window {
+            let mut extended_end_time = clock.unix_timestamp + auction.anti_sniping.extension_amount;
+            if let Some(max_end_time) = auction.anti_sniping.max_end_time {
+                extended_end_time = extended_end_time.min(max_end_time);
+            }
+            auction.end_time = auction.end_time.max(extended_end_time);
         }
 
+        ctx.accounts.bid_history.record(BidRecord {
+            bidder: bidder_key,
+            amount: bid_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
         emit!(BidPlaced {
             auction: auction.key(),
             bidder: ctx.accounts.bidder.key(),
@@ -165,26 +731,165 @@ pub mod auction {
         Ok(())
     }
 
-    /// Claim auction (settle) - can be called by winner or seller
-    pub fn claim_auction(ctx: Context<ClaimAuction>) -> Result<()> {
-        let clock = Clock::get()?;
-        
-        // Validate auction can be settled (read-only access)
-        require!(!ctx.accounts.auction.is_settled, AuctionError::AuctionAlreadySettled);
-        require!(!ctx.accounts.auction.is_canceled, AuctionError::AuctionCanceled);
-        require!(clock.unix_timestamp >= ctx.accounts.auction.end_time, AuctionError::AuctionNotEnded);
-        
-        // Store values before mutable access
-        let highest_bid = ctx.accounts.auction.highest_bid;
-        let reserve_price = ctx.accounts.auction.reserve_price;
-        let highest_bidder = ctx.accounts.auction.highest_bidder;
-        let auction_bump = ctx.accounts.auction.bump;
-        let mint_key = ctx.accounts.auction.mint;
-        let seller_key = ctx.accounts.auction.seller;
-        let auction_key = ctx.accounts.auction.key();
+    /// Return the recorded bids of an English auction, oldest first
+    pub fn get_bid_history(ctx: Context<GetBidHistory>) -> Result<Vec<BidRecord>> {
+        Ok(ctx.accounts.bid_history.ordered())
+    }
+
+    /// Close the bid history of a finished auction and return its rent to the seller
+    pub fn close_bid_history(ctx: Context<CloseBidHistory>) -> Result<()> {
+        let auction = &mut ctx.accounts.auction;
+        require!(auction.is_settled || auction.is_canceled, AuctionError::AuctionNotSettled);
+        auction.bid_history_open = false;
+
+        emit!(BidHistoryClosed {
+            auction: auction.key(),
+            seller: auction.seller,
+            recorded_bids: ctx.accounts.bid_history.bids.len() as u32,
+        });
+
+        Ok(())
+    }
+
+    /// Pay out everything credited to a bidder's refund ledger from the auction escrow and close
+    /// the ledger (permissionless, the refund always goes to the ledger's bidder)
+    ///
+    /// A bidder holding the top bid of a running auction keeps their ledger, since the next
+    /// outbid has to credit it. Once the auction is over, emptied ledgers such as the winner's
+    /// can be closed with nothing to pay out
+    pub fn withdraw_refund(ctx: Context<WithdrawRefund>) -> Result<()> {
+        let amount = ctx.accounts.bid_refund.refundable;
+        let auction = &ctx.accounts.auction;
+        let is_finished = auction.is_settled || auction.is_canceled;
+        require!(amount > 0 || is_finished, AuctionError::NothingToRefund);
+        let holds_live_bid = !is_finished
+            && auction.highest_bidder == Some(ctx.accounts.bidder.key());
+
+        if amount > 0 {
+            let auction_seeds = &[
+                b"auction",
+                auction.mint.as_ref(),
+                auction.seller.as_ref(),
+                &[auction.bump],
+            ];
+            let signer = &[&auction_seeds[..]];
+
+            let refund_destination = payment_destination(
+                &ctx.accounts.bidder.to_account_info(),
+                &ctx.accounts.bidder_payment_account,
+                auction.payment_mint,
+            )?;
+            pay_from_auction(
+                auction,
+                &ctx.accounts.auction_payment_account,
+                refund_destination,
+                &ctx.accounts.token_program,
+                signer,
+                amount,
+            )?;
+        }
+
+        let auction = &mut ctx.accounts.auction;
+        auction.pending_refunds = auction.pending_refunds.checked_sub(amount)
+            .ok_or(AuctionError::MathOverflow)?;
+
+        let bid_refund = &mut ctx.accounts.bid_refund;
+        bid_refund.refundable = 0;
+        bid_refund.total_withdrawn = bid_refund.total_withdrawn.checked_add(amount)
+            .ok_or(AuctionError::MathOverflow)?;
+
+        emit!(BidRefundWithdrawn {
+            auction: bid_refund.auction,
+            bidder: bid_refund.bidder,
+            amount,
+            total_withdrawn: bid_refund.total_withdrawn,
+        });
+
+        // Anyone else's ledger is recreated by `place_bid` if they bid again
+        if !holds_live_bid {
+            let auction = &mut ctx.accounts.auction;
+            auction.open_refund_ledgers = auction.open_refund_ledgers.checked_sub(1)
+                .ok_or(AuctionError::MathOverflow)?;
+            ctx.accounts.bid_refund.close(ctx.accounts.bidder.to_account_info())?;
+        }
+
+        Ok(())
+    }
+
+    /// Accept a top bid below a soft reserve during the post-auction grace period (only seller)
+    pub fn accept_highest_bid(ctx: Context<AcceptHighestBid>) -> Result<()> {
+        let clock = Clock::get()?;
+        let auction = &ctx.accounts.auction;
+
+        let grace_period = match auction.reserve_mode {
+            ReserveMode::Soft { grace_period } => grace_period,
+            ReserveMode::Hard => return Err(AuctionError::NotSoftReserveAuction.into()),
+        };
+        require!(!auction.is_settled, AuctionError::AuctionAlreadySettled);
+        require!(!auction.is_canceled, AuctionError::AuctionCanceled);
+        require!(clock.unix_timestamp >= auction.end_time, AuctionError::AuctionNotEnded);
+        require!(
+            clock.unix_timestamp < auction.end_time + grace_period,
+            AuctionError::GracePeriodEnded
+        );
+        require!(
+            auction.highest_bid > 0 && auction.highest_bid < auction.reserve_price,
+            AuctionError::NoBidToAccept
+        );
+
+        let auction = &mut ctx.accounts.auction;
+        auction.seller_accepted_bid = true;
+
+        emit!(HighestBidAccepted {
+            auction: auction.key(),
+            seller: auction.seller,
+            bidder: auction.highest_bidder.unwrap(),
+            amount: auction.highest_bid,
+            reserve_price: auction.reserve_price,
+        });
+
+        Ok(())
+    }
+
+    /// Claim auction (settle) - can be called by winner or seller
+    pub fn claim_auction<'info>(ctx: Context<'_, '_, '_, 'info, ClaimAuction<'info>>) -> Result<()> {
+        let clock = Clock::get()?;
+        
+        // Validate auction can be settled (read-only access)
+        require!(!ctx.accounts.auction.is_settled, AuctionError::AuctionAlreadySettled);
+        require!(!ctx.accounts.auction.is_canceled, AuctionError::AuctionCanceled);
+        require!(clock.unix_timestamp >= ctx.accounts.auction.settlement_time(), AuctionError::AuctionNotEnded);
+        
+        // Store values before mutable access
+        let auction_type = ctx.accounts.auction.auction_type;
+        let highest_bid = ctx.accounts.auction.highest_bid;
+        let reserve_price = ctx.accounts.auction.reserve_price;
+        let highest_bidder = ctx.accounts.auction.highest_bidder;
+        let auction_bump = ctx.accounts.auction.bump;
+        let mint_key = ctx.accounts.auction.mint;
+        let seller_key = ctx.accounts.auction.seller;
+        let auction_key = ctx.accounts.auction.key();
+        let payment_mint = ctx.accounts.auction.payment_mint;
+
+        // Bids are refunded to, and the NFT delivered to, the highest bidder only
+        if let Some(highest_bidder_key) = highest_bidder {
+            require_keys_eq!(ctx.accounts.winner.key(), highest_bidder_key, AuctionError::InvalidWinner);
+        }
+
+        // A soft reserve that was missed leaves the seller a grace period to accept the top bid,
+        // after which the bid is refunded
+        let reserve_met = highest_bid >= reserve_price || ctx.accounts.auction.seller_accepted_bid;
+        if let ReserveMode::Soft { grace_period } = ctx.accounts.auction.reserve_mode {
+            require!(
+                reserve_met
+                    || highest_bid == 0
+                    || clock.unix_timestamp >= ctx.accounts.auction.end_time + grace_period,
+                AuctionError::GracePeriodActive
+            );
+        }
 
         // Check if reserve price was met
-        if highest_bid < reserve_price {
+        if !reserve_met {
             // Reserve not met - return NFT to seller
             let auction_seeds = &[
                 b"auction",
@@ -205,18 +910,13 @@ pub mod auction {
             );
             token::transfer(nft_transfer_ctx, 1)?;
 
-            // Refund highest bidder if any
-            if highest_bid > 0 {
-                if let Some(_highest_bidder) = highest_bidder {
-                    let refund_ctx = CpiContext::new_with_signer(
-                        ctx.accounts.system_program.to_account_info(),
-                        anchor_lang::system_program::Transfer {
-                            from: ctx.accounts.auction.to_account_info(),
-                            to: ctx.accounts.winner.to_account_info(),
-                        },
-                        signer,
-                    );
-                    anchor_lang::system_program::transfer(refund_ctx, highest_bid)?;
+            // Refund highest bidder if any, sealed-bid collateral is returned by `refund_sealed_bid`
+            if highest_bid > 0 && auction_type != AuctionType::SealedBid {
+                if let Some(highest_bidder_key) = highest_bidder {
+                    let winner_refund = ctx.accounts.winner_refund.as_mut()
+                        .ok_or(AuctionError::RefundLedgerMissing)?;
+                    credit_bid_refund(winner_refund, auction_key, highest_bidder_key, highest_bid)?;
+                    ctx.accounts.auction.track_refund_credit(highest_bid)?;
                 }
             }
 
@@ -231,11 +931,15 @@ pub mod auction {
             });
         } else {
             // Reserve met - complete the sale
-            let sale_price = highest_bid;
+            let sale_price = ctx.accounts.auction.clearing_price();
             
-            // Calculate platform fee
+            // Calculate platform fee and creator royalties
             let platform_fee = ctx.accounts.marketplace.calculate_platform_fee(sale_price)?;
+            let metadata = load_metadata(&ctx.accounts.metadata.to_account_info(), &mint_key)?;
+            let creator_royalties = calculate_creator_royalties(&ctx.accounts.royalty_config, &metadata, sale_price);
+            let royalty_fee = total_royalties(&creator_royalties)?;
             let seller_proceeds = sale_price.checked_sub(platform_fee)
+                .and_then(|amount| amount.checked_sub(royalty_fee))
                 .ok_or(AuctionError::MathOverflow)?;
 
             let auction_seeds = &[
@@ -248,27 +952,59 @@ pub mod auction {
 
             // Transfer platform fee to treasury
             if platform_fee > 0 {
-                let fee_transfer_ctx = CpiContext::new_with_signer(
-                    ctx.accounts.system_program.to_account_info(),
-                    anchor_lang::system_program::Transfer {
-                        from: ctx.accounts.auction.to_account_info(),
-                        to: ctx.accounts.treasury.to_account_info(),
-                    },
+                let treasury_destination = payment_destination(
+                    &ctx.accounts.treasury,
+                    &ctx.accounts.treasury_payment_account,
+                    payment_mint,
+                )?;
+                pay_from_auction(
+                    &ctx.accounts.auction,
+                    &ctx.accounts.auction_payment_account,
+                    treasury_destination,
+                    &ctx.accounts.token_program,
                     signer,
-                );
-                anchor_lang::system_program::transfer(fee_transfer_ctx, platform_fee)?;
+                    platform_fee,
+                )?;
+            }
+
+            // Pay creator royalties to the accounts passed in remaining accounts
+            // (creator wallets for lamport auctions, their payment mint ATAs otherwise)
+            for creator in &creator_royalties {
+                if creator.amount > 0 {
+                    let creator_destination = match payment_mint {
+                        None => creator.address,
+                        Some(payment_mint) => get_associated_token_address(&creator.address, &payment_mint),
+                    };
+                    let creator_account = ctx.remaining_accounts
+                        .iter()
+                        .find(|acc| acc.key() == creator_destination)
+                        .ok_or(AuctionError::CreatorAccountNotFound)?;
+
+                    pay_from_auction(
+                        &ctx.accounts.auction,
+                        &ctx.accounts.auction_payment_account,
+                        creator_account.clone(),
+                        &ctx.accounts.token_program,
+                        signer,
+                        creator.amount,
+                    )?;
+                }
             }
 
             // Transfer proceeds to seller
-            let seller_transfer_ctx = CpiContext::new_with_signer(
-                ctx.accounts.system_program.to_account_info(),
-                anchor_lang::system_program::Transfer {
-                    from: ctx.accounts.auction.to_account_info(),
-                    to: ctx.accounts.seller.to_account_info(),
-                },
+            let seller_destination = payment_destination(
+                &ctx.accounts.seller,
+                &ctx.accounts.seller_payment_account,
+                payment_mint,
+            )?;
+            pay_from_auction(
+                &ctx.accounts.auction,
+                &ctx.accounts.auction_payment_account,
+                seller_destination,
+                &ctx.accounts.token_program,
                 signer,
-            );
-            anchor_lang::system_program::transfer(seller_transfer_ctx, seller_proceeds)?;
+                seller_proceeds,
+            )?;
 
             // Transfer NFT to winner
             let nft_transfer_ctx = CpiContext::new_with_signer(
@@ -284,13 +1020,35 @@ pub mod auction {
 
             // Update auction state
             ctx.accounts.auction.is_settled = true;
+            ctx.accounts.auction.winner = Some(ctx.accounts.winner.key());
+
+            // Update marketplace stats via CPI, signed by our sale authority
+            let sale_authority_seeds = &[SALE_AUTHORITY_SEED, &[ctx.bumps.sale_authority]];
+            let sale_authority_signer = &[&sale_authority_seeds[..]];
+            let cpi_accounts = marketplace::cpi::accounts::UpdateStats {
+                marketplace: ctx.accounts.marketplace.to_account_info(),
+                sale_authority: ctx.accounts.sale_authority.to_account_info(),
+                caller_program: ctx.accounts.auction_program.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.marketplace_program.to_account_info(),
+                cpi_accounts,
+                sale_authority_signer,
+            );
+            // Lifetime volume is tracked in lamports, so token sales only count towards sales
+            let volume = if payment_mint.is_none() { sale_price } else { 0 };
+            marketplace::cpi::update_stats(cpi_ctx, volume)?;
 
             emit!(AuctionSettled {
                 auction: auction_key,
                 seller: seller_key,
                 winner: highest_bidder.unwrap(),
                 final_price: sale_price,
+                payment_mint,
                 platform_fee,
+                royalty_fee,
+                seller_proceeds,
+                creators: creator_royalties,
             });
         }
 
@@ -342,6 +1100,74 @@ pub mod auction {
         Ok(())
     }
 
+    /// Close a settled or canceled auction once every bidder has been paid back, returning the
+    /// rent of the auction, its bid history and its empty token accounts to the seller
+    ///
+    /// Closing frees the auction PDA so the seller can auction the same mint again. English
+    /// auctions close their bid history along with it
+    pub fn close_auction(ctx: Context<CloseAuction>) -> Result<()> {
+        let auction = &ctx.accounts.auction;
+        require!(auction.is_settled || auction.is_canceled, AuctionError::AuctionNotSettled);
+        require!(auction.pending_refunds == 0, AuctionError::RefundsOutstanding);
+        require!(auction.open_sealed_bids == 0, AuctionError::RefundsOutstanding);
+        // Ledgers can only be closed through their auction, so none may outlive it
+        require!(auction.open_refund_ledgers == 0, AuctionError::RefundLedgersOpen);
+        // A bid history left open would block recreating the auction under the same PDA
+        require!(
+            !auction.bid_history_open || ctx.accounts.bid_history.is_some(),
+            AuctionError::BidHistoryMissing
+        );
+
+        let auction_seeds = &[
+            b"auction",
+            auction.mint.as_ref(),
+            auction.seller.as_ref(),
+            &[auction.bump],
+        ];
+        let signer = &[&auction_seeds[..]];
+
+        let close_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.auction_token_account.to_account_info(),
+                destination: ctx.accounts.seller.to_account_info(),
+                authority: auction.to_account_info(),
+            },
+            signer,
+        );
+        token::close_account(close_ctx)?;
+
+        // SPL-denominated auctions also escrowed bids in a payment mint ATA
+        if let Some(payment_mint) = auction.payment_mint {
+            let auction_payment_account = ctx.accounts.auction_payment_account.as_ref()
+                .ok_or(AuctionError::PaymentAccountMissing)?;
+            require_keys_eq!(
+                auction_payment_account.key(),
+                get_associated_token_address(&auction.key(), &payment_mint),
+                AuctionError::InvalidPaymentAccount
+            );
+
+            let close_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                CloseAccount {
+                    account: auction_payment_account.to_account_info(),
+                    destination: ctx.accounts.seller.to_account_info(),
+                    authority: auction.to_account_info(),
+                },
+                signer,
+            );
+            token::close_account(close_ctx)?;
+        }
+
+        emit!(AuctionClosed {
+            auction: auction.key(),
+            seller: auction.seller,
+            mint: auction.mint,
+        });
+
+        Ok(())
+    }
+
     /// Emergency refund for stuck bids (admin only)
     pub fn emergency_refund(ctx: Context<EmergencyRefund>) -> Result<()> {
         // Only marketplace admin can call this
@@ -349,32 +1175,21 @@ pub mod auction {
             ctx.accounts.marketplace.authority == ctx.accounts.admin.key(),
             AuctionError::Unauthorized
         );
+        // Only English auctions hold a single escrowed highest bid
+        require!(ctx.accounts.auction.auction_type == AuctionType::English, AuctionError::NotEnglishAuction);
+        // Token bids sit in the auction's ATA, which this lamport refund cannot reach
+        require!(ctx.accounts.auction.payment_mint.is_none(), AuctionError::TokenAuctionRefund);
 
         // Store values before using in transfer
-        let auction_bump = ctx.accounts.auction.bump;
-        let mint_key = ctx.accounts.auction.mint;
-        let seller_key = ctx.accounts.auction.seller;
         let highest_bid = ctx.accounts.auction.highest_bid;
         let auction_key = ctx.accounts.auction.key();
 
         // Refund the stuck bid
-        let auction_seeds = &[
-            b"auction",
-            mint_key.as_ref(),
-            seller_key.as_ref(),
-            &[auction_bump],
-        ];
-        let signer = &[&auction_seeds[..]];
-
-        let refund_ctx = CpiContext::new_with_signer(
-            ctx.accounts.system_program.to_account_info(),
-            anchor_lang::system_program::Transfer {
-                from: ctx.accounts.auction.to_account_info(),
-                to: ctx.accounts.refund_recipient.to_account_info(),
-            },
-            signer,
-        );
-        anchor_lang::system_program::transfer(refund_ctx, highest_bid)?;
+        move_lamports(
+            &ctx.accounts.auction.to_account_info(),
+            &ctx.accounts.refund_recipient.to_account_info(),
+            highest_bid,
+        )?;
 
         emit!(EmergencyRefundIssued {
             auction: auction_key,
@@ -411,27 +1226,375 @@ pub struct CreateAuction<'info> {
     )]
     pub seller_token_account: Account<'info, TokenAccount>,
     
+    // Anyone can create the auction PDA's ATA ahead of a relist, so an existing empty one is reused
+    #[account(
+        init_if_needed,
+        payer = seller,
+        associated_token::mint = mint,
+        associated_token::authority = auction
+    )]
+    pub auction_token_account: Account<'info, TokenAccount>,
+    
+    /// CHECK: Metadata account for the NFT - using AccountInfo instead of Account
+    pub metadata: AccountInfo<'info>,
+    
+    /// Bidding currency for SPL-denominated auctions
+    pub payment_mint: Option<Account<'info, Mint>>,
+    
+    /// CHECK: Auction's payment mint ATA, created by the associated token program
+    #[account(mut)]
+    pub auction_payment_account: Option<UncheckedAccount<'info>>,
+    
+    /// Bid history ring buffer, required for English auctions
     #[account(
         init,
         payer = seller,
+        space = 8 + BidHistory::INIT_SPACE,
+        seeds = [b"bid_history", auction.key().as_ref()],
+        bump
+    )]
+    pub bid_history: Option<Account<'info, BidHistory>>,
+    
+    #[account(
+        seeds = [MARKETPLACE_SEED],
+        bump = marketplace.bump,
+        seeds::program = marketplace::ID
+    )]
+    pub marketplace: Account<'info, MarketplaceState>,
+    
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct PlaceBid<'info> {
+    #[account(
+        mut,
+        seeds = [b"auction", auction.mint.as_ref(), auction.seller.as_ref()],
+        bump = auction.bump
+    )]
+    pub auction: Account<'info, AuctionState>,
+    
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    
+    #[account(
+        init_if_needed,
+        payer = bidder,
+        space = 8 + BidRefundLedger::INIT_SPACE,
+        seeds = [b"bid_refund", auction.key().as_ref(), bidder.key().as_ref()],
+        bump
+    )]
+    pub bidder_refund: Account<'info, BidRefundLedger>,
+    
+    /// Previous highest bidder's refund ledger, credited with the outbid amount
+    #[account(mut)]
+    pub previous_bidder_refund: Option<Account<'info, BidRefundLedger>>,
+    
+    #[account(
+        mut,
+        seeds = [b"bid_history", auction.key().as_ref()],
+        bump = bid_history.bump,
+        has_one = auction
+    )]
+    pub bid_history: Account<'info, BidHistory>,
+    
+    /// Bidder's payment mint token account for SPL-denominated auctions
+    #[account(mut, token::authority = bidder)]
+    pub bidder_payment_account: Option<Account<'info, TokenAccount>>,
+    
+    /// Auction's payment mint ATA holding the escrowed bid
+    #[account(mut)]
+    pub auction_payment_account: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawRefund<'info> {
+    #[account(
+        mut,
+        seeds = [b"auction", auction.mint.as_ref(), auction.seller.as_ref()],
+        bump = auction.bump
+    )]
+    pub auction: Account<'info, AuctionState>,
+    
+    #[account(
+        mut,
+        seeds = [b"bid_refund", auction.key().as_ref(), bidder.key().as_ref()],
+        bump = bid_refund.bump,
+        has_one = auction,
+        has_one = bidder
+    )]
+    pub bid_refund: Account<'info, BidRefundLedger>,
+    
+    /// CHECK: Bidder receiving the refund and ledger rent, anyone can trigger the withdrawal
+    #[account(mut)]
+    pub bidder: AccountInfo<'info>,
+    
+    /// Bidder's payment mint ATA for SPL-denominated auctions
+    #[account(mut)]
+    pub bidder_payment_account: Option<Account<'info, TokenAccount>>,
+    
+    /// Auction's payment mint ATA holding the escrowed bids
+    #[account(mut)]
+    pub auction_payment_account: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptHighestBid<'info> {
+    #[account(
+        mut,
+        seeds = [b"auction", auction.mint.as_ref(), auction.seller.as_ref()],
+        bump = auction.bump,
+        has_one = seller
+    )]
+    pub auction: Account<'info, AuctionState>,
+    
+    pub seller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimAuction<'info> {
+    #[account(
+        mut,
+        seeds = [b"auction", auction.mint.as_ref(), auction.seller.as_ref()],
+        bump = auction.bump
+    )]
+    pub auction: Account<'info, AuctionState>,
+    
+    /// CHECK: Either seller or winner can claim
+    #[account(mut)]
+    pub claimer: Signer<'info>,
+    
+    /// CHECK: Seller account for payment
+    #[account(
+        mut,
+        constraint = seller.key() == auction.seller
+    )]
+    pub seller: AccountInfo<'info>,
+    
+    /// CHECK: Winner account (highest bidder)
+    #[account(mut)]
+    pub winner: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        associated_token::mint = auction.mint,
+        associated_token::authority = auction
+    )]
+    pub auction_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        associated_token::mint = auction.mint,
+        associated_token::authority = seller
+    )]
+    pub seller_token_account: Account<'info, TokenAccount>,
+    
+    /// NFT mint account
+    pub mint: Account<'info, Mint>,
+    
+    /// CHECK: Metadata account for the NFT, deserialized by `load_metadata`
+    pub metadata: UncheckedAccount<'info>,
+    
+    #[account(
+        seeds = [b"royalty_config"],
+        bump = royalty_config.bump,
+        seeds::program = royalty::ID
+    )]
+    pub royalty_config: Account<'info, RoyaltyConfig>,
+
+    #[account(
+        init_if_needed,
+        payer = claimer,
         associated_token::mint = mint,
+        associated_token::authority = winner,
+        constraint = mint.key() == auction.mint
+    )]
+    pub winner_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        seeds = [MARKETPLACE_SEED],
+        bump = marketplace.bump,
+        seeds::program = marketplace::ID
+    )]
+    pub marketplace: Account<'info, MarketplaceState>,
+    
+    /// CHECK: Treasury account from marketplace
+    #[account(
+        mut,
+        constraint = treasury.key() == marketplace.treasury
+    )]
+    pub treasury: AccountInfo<'info>,
+    
+    /// Auction's payment mint ATA holding the escrowed bid
+    #[account(mut)]
+    pub auction_payment_account: Option<Account<'info, TokenAccount>>,
+    
+    /// Seller's payment mint ATA for SPL-denominated auctions
+    #[account(mut)]
+    pub seller_payment_account: Option<Account<'info, TokenAccount>>,
+    
+    /// Winner's refund ledger, credited with the top bid when the reserve is not met
+    #[account(mut)]
+    pub winner_refund: Option<Account<'info, BidRefundLedger>>,
+    
+    /// Treasury's payment mint ATA for SPL-denominated auctions
+    #[account(mut)]
+    pub treasury_payment_account: Option<Account<'info, TokenAccount>>,
+    
+    /// CHECK: PDA signer proving the stats update comes from this program
+    #[account(
+        seeds = [SALE_AUTHORITY_SEED],
+        bump
+    )]
+    pub sale_authority: UncheckedAccount<'info>,
+    
+    pub auction_program: Program<'info, crate::program::Auction>,
+    pub marketplace_program: Program<'info, Marketplace>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct BuyNow<'info> {
+    #[account(
+        mut,
+        seeds = [b"auction", auction.mint.as_ref(), auction.seller.as_ref()],
+        bump = auction.bump
+    )]
+    pub auction: Account<'info, AuctionState>,
+    
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    /// CHECK: Seller account for payment
+    #[account(
+        mut,
+        constraint = seller.key() == auction.seller
+    )]
+    pub seller: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        associated_token::mint = auction.mint,
         associated_token::authority = auction
     )]
-    pub auction_token_account: Account<'info, TokenAccount>,
+    pub auction_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = mint,
+        associated_token::authority = buyer
+    )]
+    pub buyer_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        constraint = mint.key() == auction.mint
+    )]
+    pub mint: Account<'info, Mint>,
+    
+    /// CHECK: Metadata account for the NFT, deserialized by `load_metadata`
+    pub metadata: UncheckedAccount<'info>,
+    
+    #[account(
+        seeds = [b"royalty_config"],
+        bump = royalty_config.bump,
+        seeds::program = royalty::ID
+    )]
+    pub royalty_config: Account<'info, RoyaltyConfig>,
+    
+    #[account(
+        mut,
+        seeds = [MARKETPLACE_SEED],
+        bump = marketplace.bump,
+        seeds::program = marketplace::ID
+    )]
+    pub marketplace: Account<'info, MarketplaceState>,
+    
+    /// CHECK: Treasury account from marketplace
+    #[account(
+        mut,
+        constraint = treasury.key() == marketplace.treasury
+    )]
+    pub treasury: AccountInfo<'info>,
+    
+    /// Buyer's payment mint token account for SPL-denominated auctions
+    #[account(mut, token::authority = buyer)]
+    pub buyer_payment_account: Option<Account<'info, TokenAccount>>,
+    
+    /// Seller's payment mint ATA for SPL-denominated auctions
+    #[account(mut)]
+    pub seller_payment_account: Option<Account<'info, TokenAccount>>,
+    
+    /// Treasury's payment mint ATA for SPL-denominated auctions
+    #[account(mut)]
+    pub treasury_payment_account: Option<Account<'info, TokenAccount>>,
+    
+    /// Refund ledger of an English auction's highest bidder, credited on buy-now
+    #[account(mut)]
+    pub previous_bidder_refund: Option<Account<'info, BidRefundLedger>>,
+    
+    /// CHECK: PDA signer proving the stats update comes from this program
+    #[account(
+        seeds = [SALE_AUTHORITY_SEED],
+        bump
+    )]
+    pub sale_authority: UncheckedAccount<'info>,
+    
+    pub auction_program: Program<'info, crate::program::Auction>,
+    pub marketplace_program: Program<'info, Marketplace>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CommitBid<'info> {
+    #[account(
+        mut,
+        seeds = [b"auction", auction.mint.as_ref(), auction.seller.as_ref()],
+        bump = auction.bump
+    )]
+    pub auction: Account<'info, AuctionState>,
+    
+    #[account(
+        init,
+        payer = bidder,
+        space = 8 + SealedBidState::INIT_SPACE,
+        seeds = [b"sealed_bid", auction.key().as_ref(), bidder.key().as_ref()],
+        bump
+    )]
+    pub sealed_bid: Account<'info, SealedBidState>,
     
-    /// CHECK: Metadata account for the NFT - using AccountInfo instead of Account
-    pub metadata: AccountInfo<'info>,
+    #[account(mut)]
+    pub bidder: Signer<'info>,
     
-    pub marketplace: Account<'info, MarketplaceState>,
+    /// Bidder's payment mint token account for SPL-denominated auctions
+    #[account(mut, token::authority = bidder)]
+    pub bidder_payment_account: Option<Account<'info, TokenAccount>>,
+    
+    /// Auction's payment mint ATA holding the escrowed collateral
+    #[account(mut)]
+    pub auction_payment_account: Option<Account<'info, TokenAccount>>,
     
     pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
     pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-pub struct PlaceBid<'info> {
+pub struct RevealBid<'info> {
     #[account(
         mut,
         seeds = [b"auction", auction.mint.as_ref(), auction.seller.as_ref()],
@@ -439,18 +1602,20 @@ pub struct PlaceBid<'info> {
     )]
     pub auction: Account<'info, AuctionState>,
     
-    #[account(mut)]
-    pub bidder: Signer<'info>,
-    
-    /// CHECK: Previous highest bidder for refunds
-    #[account(mut)]
-    pub previous_bidder: AccountInfo<'info>,
+    #[account(
+        mut,
+        seeds = [b"sealed_bid", auction.key().as_ref(), bidder.key().as_ref()],
+        bump = sealed_bid.bump,
+        has_one = auction,
+        has_one = bidder
+    )]
+    pub sealed_bid: Account<'info, SealedBidState>,
     
-    pub system_program: Program<'info, System>,
+    pub bidder: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct ClaimAuction<'info> {
+pub struct RefundSealedBid<'info> {
     #[account(
         mut,
         seeds = [b"auction", auction.mint.as_ref(), auction.seller.as_ref()],
@@ -458,20 +1623,44 @@ pub struct ClaimAuction<'info> {
     )]
     pub auction: Account<'info, AuctionState>,
     
-    /// CHECK: Either seller or winner can claim
+    #[account(
+        mut,
+        seeds = [b"sealed_bid", auction.key().as_ref(), bidder.key().as_ref()],
+        bump = sealed_bid.bump,
+        has_one = auction,
+        has_one = bidder,
+        close = bidder
+    )]
+    pub sealed_bid: Account<'info, SealedBidState>,
+    
+    /// CHECK: Bidder receiving the collateral and rent, anyone can trigger the refund
     #[account(mut)]
-    pub claimer: Signer<'info>,
+    pub bidder: AccountInfo<'info>,
     
-    /// CHECK: Seller account for payment
+    /// Bidder's payment mint ATA for SPL-denominated auctions
+    #[account(mut)]
+    pub bidder_payment_account: Option<Account<'info, TokenAccount>>,
+    
+    /// Auction's payment mint ATA holding the escrowed collateral
+    #[account(mut)]
+    pub auction_payment_account: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelAuction<'info> {
     #[account(
         mut,
-        constraint = seller.key() == auction.seller
+        seeds = [b"auction", auction.mint.as_ref(), auction.seller.as_ref()],
+        bump = auction.bump,
+        has_one = seller
     )]
-    pub seller: AccountInfo<'info>,
+    pub auction: Account<'info, AuctionState>,
     
-    /// CHECK: Winner account (highest bidder)
     #[account(mut)]
-    pub winner: AccountInfo<'info>,
+    pub seller: Signer<'info>,
     
     #[account(
         mut,
@@ -487,40 +1676,49 @@ pub struct ClaimAuction<'info> {
     )]
     pub seller_token_account: Account<'info, TokenAccount>,
     
-    /// NFT mint account
-    pub mint: Account<'info, Mint>,
+    pub token_program: Program<'info, Token>,
+}
 
+#[derive(Accounts)]
+pub struct GetBidHistory<'info> {
     #[account(
-        init_if_needed,
-        payer = claimer,
-        associated_token::mint = mint,
-        associated_token::authority = winner,
-        constraint = mint.key() == auction.mint
+        seeds = [b"bid_history", bid_history.auction.as_ref()],
+        bump = bid_history.bump
     )]
-    pub winner_token_account: Account<'info, TokenAccount>,
-    
-    pub marketplace: Account<'info, MarketplaceState>,
+    pub bid_history: Account<'info, BidHistory>,
+}
+
+#[derive(Accounts)]
+pub struct CloseBidHistory<'info> {
+    #[account(
+        mut,
+        seeds = [b"auction", auction.mint.as_ref(), auction.seller.as_ref()],
+        bump = auction.bump,
+        has_one = seller
+    )]
+    pub auction: Account<'info, AuctionState>,
     
-    /// CHECK: Treasury account from marketplace
     #[account(
         mut,
-        constraint = treasury.key() == marketplace.treasury
+        seeds = [b"bid_history", auction.key().as_ref()],
+        bump = bid_history.bump,
+        has_one = auction,
+        close = seller
     )]
-    pub treasury: AccountInfo<'info>,
+    pub bid_history: Account<'info, BidHistory>,
     
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
+    #[account(mut)]
+    pub seller: Signer<'info>,
 }
 
 #[derive(Accounts)]
-pub struct CancelAuction<'info> {
+pub struct CloseAuction<'info> {
     #[account(
         mut,
         seeds = [b"auction", auction.mint.as_ref(), auction.seller.as_ref()],
         bump = auction.bump,
-        has_one = seller
+        has_one = seller,
+        close = seller
     )]
     pub auction: Account<'info, AuctionState>,
     
@@ -534,12 +1732,20 @@ pub struct CancelAuction<'info> {
     )]
     pub auction_token_account: Account<'info, TokenAccount>,
     
+    /// Auction's payment mint ATA for SPL-denominated auctions
+    #[account(mut)]
+    pub auction_payment_account: Option<Account<'info, TokenAccount>>,
+    
+    /// English auctions' bid history, closed along with the auction unless
+    /// `close_bid_history` already closed it
     #[account(
         mut,
-        associated_token::mint = auction.mint,
-        associated_token::authority = seller
+        seeds = [b"bid_history", auction.key().as_ref()],
+        bump = bid_history.bump,
+        has_one = auction,
+        close = seller
     )]
-    pub seller_token_account: Account<'info, TokenAccount>,
+    pub bid_history: Option<Account<'info, BidHistory>>,
     
     pub token_program: Program<'info, Token>,
 }
@@ -559,6 +1765,11 @@ pub struct EmergencyRefund<'info> {
     #[account(mut)]
     pub refund_recipient: AccountInfo<'info>,
     
+    #[account(
+        seeds = [MARKETPLACE_SEED],
+        bump = marketplace.bump,
+        seeds::program = marketplace::ID
+    )]
     pub marketplace: Account<'info, MarketplaceState>,
     pub system_program: Program<'info, System>,
 }
@@ -568,47 +1779,198 @@ pub struct EmergencyRefund<'info> {
 pub struct AuctionState {
     pub seller: Pubkey,              // 32
     pub mint: Pubkey,                // 32
+    pub auction_type: AuctionType,   // 1
     pub start_time: i64,             // 8
     pub end_time: i64,               // 8
-    pub reserve_price: u64,          // 8
+    pub reserve_price: u64,          // 8 (floor price for Dutch auctions)
     pub min_bid_increment: u64,      // 8
+    pub buy_now_price: Option<u64>,  // 1 + 8 (English auctions only)
+    pub anti_sniping: AntiSniping,   // 8 + 8 + 1 + 8 (English auctions only)
+    pub reserve_mode: ReserveMode,   // 1 + 8 (English auctions only)
+    pub seller_accepted_bid: bool,   // 1 (soft reserve accepted by the seller)
+    pub start_price: u64,            // 8 (Dutch auctions only)
+    pub price_decay: PriceDecay,     // 1 + 8 (Dutch auctions only)
+    pub reveal_end_time: i64,        // 8 (sealed-bid auctions only)
+    pub sealed_pricing: SealedBidPricing, // 1 (sealed-bid auctions only)
+    pub payment_mint: Option<Pubkey>, // 1 + 32 (None = lamports)
     pub highest_bid: u64,            // 8
+    pub second_highest_bid: u64,     // 8 (sealed-bid auctions only)
     pub highest_bidder: Option<Pubkey>, // 1 + 32
     pub total_bids: u64,             // 8
+    pub pending_refunds: u64,        // 8 (credited to refund ledgers, not yet withdrawn)
+    pub open_sealed_bids: u32,       // 4 (sealed bids not yet refunded)
+    pub open_refund_ledgers: u32,    // 4 (bid refund ledgers not yet closed)
+    pub bid_history_open: bool,      // 1 (English auctions until their bid history is closed)
     pub is_settled: bool,            // 1
     pub is_canceled: bool,           // 1
     pub bump: u8,                    // 1
+    pub winner: Option<Pubkey>,      // 1 + 32 (set only when the NFT was sold)
 }
 
 impl AuctionState {
-    pub const INIT_SPACE: usize = 32 + 32 + 8 + 8 + 8 + 8 + 8 + 1 + 32 + 8 + 1 + 1 + 1; // 148 bytes
+    pub const INIT_SPACE: usize = 32 + 32 + 1 + 8 + 8 + 8 + 8 + 1 + 8 + 8 + 8 + 1 + 8 + 1 + 8 + 1 + 8 + 1 + 8 + 8 + 1 + 1 + 32 + 8 + 8 + 1 + 32 + 8 + 8 + 4 + 4 + 1 + 1 + 1 + 1 + 1 + 32; // 310 bytes
+
+    /// Account for an amount credited to a bidder's refund ledger
+    pub fn track_refund_credit(&mut self, amount: u64) -> Result<()> {
+        self.pending_refunds = self.pending_refunds.checked_add(amount)
+            .ok_or(AuctionError::MathOverflow)?;
+        Ok(())
+    }
+
+    /// When the auction can be settled, sealed-bid auctions settle after the reveal phase
+    pub fn settlement_time(&self) -> i64 {
+        match self.auction_type {
+            AuctionType::SealedBid => self.reveal_end_time,
+            _ => self.end_time,
+        }
+    }
+
+    /// Price the winner pays, second-price sealed-bid auctions never clear below reserve
+    pub fn clearing_price(&self) -> u64 {
+        match (self.auction_type, self.sealed_pricing) {
+            (AuctionType::SealedBid, SealedBidPricing::SecondPrice) => {
+                self.second_highest_bid.max(self.reserve_price)
+            }
+            _ => self.highest_bid,
+        }
+    }
+
+    /// Current Dutch auction price, declining from `start_price` to the floor over the auction window
+    pub fn current_dutch_price(&self, now: i64) -> Result<u64> {
+        let duration = self.end_time - self.start_time;
+        let elapsed = (now - self.start_time).clamp(0, duration);
+
+        // Stepwise decay only drops at the end of each full step
+        let (progress, total) = match self.price_decay {
+            PriceDecay::Linear => (elapsed, duration),
+            PriceDecay::Stepwise { step_interval } => (elapsed / step_interval, duration / step_interval),
+        };
+
+        let price_range = self.start_price.checked_sub(self.reserve_price)
+            .ok_or(AuctionError::MathOverflow)?;
+        let decay = (price_range as u128)
+            .checked_mul(progress as u128)
+            .ok_or(AuctionError::MathOverflow)?
+            .checked_div(total as u128)
+            .ok_or(AuctionError::MathOverflow)?;
+
+        Ok(self.start_price - decay as u64)
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum AuctionType {
+    English,
+    Dutch,
+    SealedBid,
+}
+
+/// Bids placed within `extension_window` seconds of the end push it to `now + extension_amount`,
+/// capped at `max_end_time` when set
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct AntiSniping {
+    pub extension_window: i64,
+    pub extension_amount: i64,
+    pub max_end_time: Option<i64>,
+}
+
+impl AntiSniping {
+    pub fn disabled() -> Self {
+        Self {
+            extension_window: 0,
+            extension_amount: 0,
+            max_end_time: None,
+        }
+    }
+}
+
+/// A hard reserve rejects bids below it, a soft reserve accepts them and lets the seller
+/// take the top bid within `grace_period` seconds of the end
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum ReserveMode {
+    Hard,
+    Soft { grace_period: i64 },
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum SealedBidPricing {
+    FirstPrice,
+    SecondPrice,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub struct BidRecord {
+    pub bidder: Pubkey,
+    pub amount: u64,
+    pub timestamp: i64,
 }
 
-// Define MarketplaceState here since it's referenced by your auction program
+/// Ring buffer of the last `MAX_BID_HISTORY` bids on an English auction
 #[account]
 #[derive(InitSpace)]
-pub struct MarketplaceState {
-    pub authority: Pubkey,           // 32
-    pub treasury: Pubkey,            // 32
-    pub platform_fee_bps: u16,       // 2
-    pub is_paused: bool,             // 1
-    pub total_volume: u64,           // 8
-    pub total_sales: u64,            // 8
+pub struct BidHistory {
+    pub auction: Pubkey,             // 32
+    pub head: u16,                   // 2 (oldest entry once the buffer is full)
+    #[max_len(MAX_BID_HISTORY)]
+    pub bids: Vec<BidRecord>,        // 4 + 48 * MAX_BID_HISTORY
     pub bump: u8,                    // 1
 }
 
-impl MarketplaceState {
-    pub const INIT_SPACE: usize = 32 + 32 + 2 + 1 + 8 + 8 + 1; // 84 bytes
-    
-    pub fn calculate_platform_fee(&self, sale_price: u64) -> Result<u64> {
-        let fee = (sale_price as u128)
-            .checked_mul(self.platform_fee_bps as u128)
-            .ok_or(AuctionError::MathOverflow)?
-            .checked_div(10000)
-            .ok_or(AuctionError::MathOverflow)?;
-        
-        Ok(fee as u64)
+impl BidHistory {
+    pub const INIT_SPACE: usize = 32 + 2 + 4 + 48 * MAX_BID_HISTORY + 1; // 1575 bytes
+
+    /// Append a bid, overwriting the oldest one when the buffer is full
+    pub fn record(&mut self, bid: BidRecord) {
+        if self.bids.len() < MAX_BID_HISTORY {
+            self.bids.push(bid);
+        } else {
+            self.bids[self.head as usize] = bid;
+            self.head = ((self.head as usize + 1) % MAX_BID_HISTORY) as u16;
+        }
     }
+
+    /// Recorded bids, oldest first
+    pub fn ordered(&self) -> Vec<BidRecord> {
+        let (newer, older) = self.bids.split_at(self.head as usize);
+        older.iter().chain(newer).copied().collect()
+    }
+}
+
+/// Per-bidder record of outbid amounts owed from an auction's escrow
+#[account]
+#[derive(InitSpace)]
+pub struct BidRefundLedger {
+    pub auction: Pubkey,             // 32
+    pub bidder: Pubkey,              // 32
+    pub refundable: u64,             // 8
+    pub total_credited: u64,         // 8
+    pub total_withdrawn: u64,        // 8
+    pub bump: u8,                    // 1
+}
+
+impl BidRefundLedger {
+    pub const INIT_SPACE: usize = 32 + 32 + 8 + 8 + 8 + 1; // 89 bytes
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct SealedBidState {
+    pub auction: Pubkey,             // 32
+    pub bidder: Pubkey,              // 32
+    pub commitment: [u8; 32],        // 32
+    pub collateral: u64,             // 8
+    pub revealed_amount: Option<u64>, // 1 + 8
+    pub bump: u8,                    // 1
+}
+
+impl SealedBidState {
+    pub const INIT_SPACE: usize = 32 + 32 + 32 + 8 + 1 + 8 + 1; // 114 bytes
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum PriceDecay {
+    Linear,
+    Stepwise { step_interval: i64 },
 }
 
 #[event]
@@ -620,6 +1982,58 @@ pub struct AuctionCreated {
     pub end_time: i64,
     pub reserve_price: u64,
     pub min_bid_increment: u64,
+    pub buy_now_price: Option<u64>,
+    pub anti_sniping: AntiSniping,
+    pub reserve_mode: ReserveMode,
+    pub payment_mint: Option<Pubkey>,
+}
+
+#[event]
+pub struct DutchAuctionCreated {
+    pub auction: Pubkey,
+    pub seller: Pubkey,
+    pub mint: Pubkey,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub start_price: u64,
+    pub floor_price: u64,
+    pub price_decay: PriceDecay,
+    pub payment_mint: Option<Pubkey>,
+}
+
+#[event]
+pub struct SealedAuctionCreated {
+    pub auction: Pubkey,
+    pub seller: Pubkey,
+    pub mint: Pubkey,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub reveal_end_time: i64,
+    pub reserve_price: u64,
+    pub sealed_pricing: SealedBidPricing,
+    pub payment_mint: Option<Pubkey>,
+}
+
+#[event]
+pub struct SealedBidCommitted {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub collateral: u64,
+}
+
+#[event]
+pub struct SealedBidRevealed {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub amount: u64,
+    pub is_leading: bool,
+}
+
+#[event]
+pub struct SealedBidRefunded {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub amount: u64,
 }
 
 #[event]
@@ -631,10 +2045,19 @@ pub struct BidPlaced {
 }
 
 #[event]
-pub struct BidRefunded {
+pub struct BidRefundCredited {
     pub auction: Pubkey,
     pub bidder: Pubkey,
     pub amount: u64,
+    pub refundable: u64,
+}
+
+#[event]
+pub struct BidRefundWithdrawn {
+    pub auction: Pubkey,
+    pub bidder: Pubkey,
+    pub amount: u64,
+    pub total_withdrawn: u64,
 }
 
 #[event]
@@ -643,7 +2066,20 @@ pub struct AuctionSettled {
     pub seller: Pubkey,
     pub winner: Pubkey,
     pub final_price: u64,
+    pub payment_mint: Option<Pubkey>,
     pub platform_fee: u64,
+    pub royalty_fee: u64,
+    pub seller_proceeds: u64,
+    pub creators: Vec<CreatorRoyalty>,
+}
+
+#[event]
+pub struct HighestBidAccepted {
+    pub auction: Pubkey,
+    pub seller: Pubkey,
+    pub bidder: Pubkey,
+    pub amount: u64,
+    pub reserve_price: u64,
 }
 
 #[event]
@@ -661,6 +2097,20 @@ pub struct AuctionCanceled {
     pub mint: Pubkey,
 }
 
+#[event]
+pub struct BidHistoryClosed {
+    pub auction: Pubkey,
+    pub seller: Pubkey,
+    pub recorded_bids: u32,
+}
+
+#[event]
+pub struct AuctionClosed {
+    pub auction: Pubkey,
+    pub seller: Pubkey,
+    pub mint: Pubkey,
+}
+
 #[event]
 pub struct EmergencyRefundIssued {
     pub auction: Pubkey,
@@ -679,9 +2129,9 @@ pub enum AuctionError {
     InvalidReservePrice,
     #[msg("Invalid bid increment")]
     InvalidBidIncrement,
-    #[msg("Auction too short (minimum 1 hour)")]
+    #[msg("Auction shorter than the marketplace minimum duration")]
     AuctionTooShort,
-    #[msg("Auction too long (maximum 30 days)")]
+    #[msg("Auction longer than the marketplace maximum duration")]
     AuctionTooLong,
     #[msg("Auction not started")]
     AuctionNotStarted,
@@ -707,4 +2157,268 @@ pub enum AuctionError {
     MathOverflow,
     #[msg("Unauthorized access")]
     Unauthorized,
+    #[msg("Creator account not found")]
+    CreatorAccountNotFound,
+    #[msg("Payment mint is not accepted by the marketplace")]
+    PaymentMintNotAccepted,
+    #[msg("Payment token account is required for SPL-denominated auctions")]
+    PaymentAccountMissing,
+    #[msg("Payment token account does not match the auction's payment mint")]
+    InvalidPaymentAccount,
+    #[msg("Previous bidder does not match the auction's highest bidder")]
+    InvalidPreviousBidder,
+    #[msg("Winner does not match the auction's highest bidder")]
+    InvalidWinner,
+    #[msg("Emergency refunds only cover lamport-denominated auctions")]
+    TokenAuctionRefund,
+    #[msg("Start price must be above the floor price")]
+    InvalidStartPrice,
+    #[msg("Price step interval must fit within the auction duration")]
+    InvalidStepInterval,
+    #[msg("Auction is not an English auction")]
+    NotEnglishAuction,
+    #[msg("Auction is not a Dutch auction")]
+    NotDutchAuction,
+    #[msg("Auction is not a sealed-bid auction")]
+    NotSealedBidAuction,
+    #[msg("Reveal phase must end after the commit phase")]
+    InvalidRevealEndTime,
+    #[msg("Reveal phase has not started")]
+    RevealNotStarted,
+    #[msg("Reveal phase has ended")]
+    RevealEnded,
+    #[msg("Bid already revealed")]
+    BidAlreadyRevealed,
+    #[msg("Revealed bid does not match the commitment")]
+    InvalidReveal,
+    #[msg("Revealed bid exceeds the escrowed collateral")]
+    BidExceedsCollateral,
+    #[msg("Auction has not been settled")]
+    AuctionNotSettled,
+    #[msg("Buy-now price cannot be below the reserve price")]
+    InvalidBuyNowPrice,
+    #[msg("Buy-now is not available for this auction")]
+    BuyNowNotAvailable,
+    #[msg("Extension window exceeds the marketplace bound")]
+    InvalidExtensionWindow,
+    #[msg("Extension amount exceeds the marketplace bound")]
+    InvalidExtensionAmount,
+    #[msg("Maximum end time must fall between the end time and the longest allowed duration")]
+    InvalidMaxEndTime,
+    #[msg("Refund ledger of the outbid bidder is required")]
+    RefundLedgerMissing,
+    #[msg("Refund ledger does not belong to the outbid bidder")]
+    InvalidRefundLedger,
+    #[msg("No refund to withdraw")]
+    NothingToRefund,
+    #[msg("Bid history account is required for English auctions")]
+    BidHistoryMissing,
+    #[msg("Soft reserve grace period must be positive")]
+    InvalidGracePeriod,
+    #[msg("Auction does not have a soft reserve")]
+    NotSoftReserveAuction,
+    #[msg("Seller grace period has ended")]
+    GracePeriodEnded,
+    #[msg("Seller grace period is still running")]
+    GracePeriodActive,
+    #[msg("No bid below reserve to accept")]
+    NoBidToAccept,
+    #[msg("Bidders still have refunds outstanding")]
+    RefundsOutstanding,
+    #[msg("Bidder refund ledgers are still open")]
+    RefundLedgersOpen,
+}
+
+// Record an outbid amount on a bidder's refund ledger
+fn credit_bid_refund(
+    ledger: &mut BidRefundLedger,
+    auction: Pubkey,
+    bidder: Pubkey,
+    amount: u64,
+) -> Result<()> {
+    require_keys_eq!(ledger.auction, auction, AuctionError::InvalidRefundLedger);
+    require_keys_eq!(ledger.bidder, bidder, AuctionError::InvalidRefundLedger);
+
+    ledger.refundable = ledger.refundable.checked_add(amount)
+        .ok_or(AuctionError::MathOverflow)?;
+    ledger.total_credited = ledger.total_credited.checked_add(amount)
+        .ok_or(AuctionError::MathOverflow)?;
+
+    emit!(BidRefundCredited {
+        auction,
+        bidder,
+        amount,
+        refundable: ledger.refundable,
+    });
+
+    Ok(())
+}
+
+// Duration must stay within the bounds the marketplace admin configured
+fn validate_auction_window(marketplace: &MarketplaceState, start_time: i64, end_time: i64) -> Result<()> {
+    let clock = Clock::get()?;
+    require!(start_time >= clock.unix_timestamp, AuctionError::InvalidStartTime);
+    require!(end_time > start_time, AuctionError::InvalidEndTime);
+
+    let duration = end_time - start_time;
+    require!(duration >= marketplace.min_auction_duration, AuctionError::AuctionTooShort);
+    require!(duration <= marketplace.max_auction_duration, AuctionError::AuctionTooLong);
+    Ok(())
+}
+
+// Move the NFT into the auction escrow and, for SPL-denominated auctions, create the
+// auction's payment mint ATA that escrows bids
+fn open_auction_escrow(accounts: &CreateAuction, payment_mint: Option<Pubkey>) -> Result<()> {
+    let transfer_ctx = CpiContext::new(
+        accounts.token_program.to_account_info(),
+        Transfer {
+            from: accounts.seller_token_account.to_account_info(),
+            to: accounts.auction_token_account.to_account_info(),
+            authority: accounts.seller.to_account_info(),
+        },
+    );
+    token::transfer(transfer_ctx, 1)?;
+
+    if let Some(payment_mint) = payment_mint {
+        require!(
+            accounts.marketplace.is_accepted_payment_mint(&payment_mint),
+            AuctionError::PaymentMintNotAccepted
+        );
+        let payment_mint_account = accounts.payment_mint.as_ref()
+            .ok_or(AuctionError::PaymentAccountMissing)?;
+        let auction_payment_account = accounts.auction_payment_account.as_ref()
+            .ok_or(AuctionError::PaymentAccountMissing)?;
+        require_keys_eq!(payment_mint_account.key(), payment_mint, AuctionError::InvalidPaymentAccount);
+
+        let create_ctx = CpiContext::new(
+            accounts.associated_token_program.to_account_info(),
+            Create {
+                payer: accounts.seller.to_account_info(),
+                associated_token: auction_payment_account.to_account_info(),
+                authority: accounts.auction.to_account_info(),
+                mint: payment_mint_account.to_account_info(),
+                system_program: accounts.system_program.to_account_info(),
+                token_program: accounts.token_program.to_account_info(),
+            },
+        );
+        associated_token::create_idempotent(create_ctx)?;
+    }
+
+    Ok(())
+}
+
+/// Resolve where a payout to `wallet` lands: the wallet itself for lamport
+/// auctions, or its payment mint ATA for SPL-denominated ones
+fn payment_destination<'info>(
+    wallet: &AccountInfo<'info>,
+    token_account: &Option<Account<'info, TokenAccount>>,
+    payment_mint: Option<Pubkey>,
+) -> Result<AccountInfo<'info>> {
+    match payment_mint {
+        None => Ok(wallet.clone()),
+        Some(payment_mint) => {
+            let token_account = token_account.as_ref()
+                .ok_or(AuctionError::PaymentAccountMissing)?;
+            require_keys_eq!(
+                token_account.key(),
+                get_associated_token_address(wallet.key, &payment_mint),
+                AuctionError::InvalidPaymentAccount
+            );
+            Ok(token_account.to_account_info())
+        }
+    }
+}
+
+/// The auction's payment mint ATA that escrows SPL bids
+fn auction_escrow_account<'a, 'info>(
+    auction: &Account<'info, AuctionState>,
+    auction_payment_account: &'a Option<Account<'info, TokenAccount>>,
+) -> Result<&'a Account<'info, TokenAccount>> {
+    let payment_mint = auction.payment_mint.ok_or(AuctionError::InvalidPaymentAccount)?;
+    let auction_payment_account = auction_payment_account.as_ref()
+        .ok_or(AuctionError::PaymentAccountMissing)?;
+    require_keys_eq!(
+        auction_payment_account.key(),
+        get_associated_token_address(&auction.key(), &payment_mint),
+        AuctionError::InvalidPaymentAccount
+    );
+    Ok(auction_payment_account)
+}
+
+/// Pay `amount` straight from a buyer in the auction's currency
+fn pay_from_buyer<'info>(
+    buyer: &Signer<'info>,
+    buyer_payment_account: &Option<Account<'info, TokenAccount>>,
+    destination: AccountInfo<'info>,
+    payment_mint: Option<Pubkey>,
+    system_program: &Program<'info, System>,
+    token_program: &Program<'info, Token>,
+    amount: u64,
+) -> Result<()> {
+    match payment_mint {
+        None => {
+            let transfer_ctx = CpiContext::new(
+                system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: buyer.to_account_info(),
+                    to: destination,
+                },
+            );
+            anchor_lang::system_program::transfer(transfer_ctx, amount)
+        }
+        Some(payment_mint) => {
+            let buyer_payment_account = buyer_payment_account.as_ref()
+                .ok_or(AuctionError::PaymentAccountMissing)?;
+            require_keys_eq!(buyer_payment_account.mint, payment_mint, AuctionError::InvalidPaymentAccount);
+
+            let transfer_ctx = CpiContext::new(
+                token_program.to_account_info(),
+                Transfer {
+                    from: buyer_payment_account.to_account_info(),
+                    to: destination,
+                    authority: buyer.to_account_info(),
+                },
+            );
+            token::transfer(transfer_ctx, amount)
+        }
+    }
+}
+
+/// Move lamports out of a program-owned account
+fn move_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
+    let remaining = from.lamports().checked_sub(amount)
+        .ok_or(AuctionError::MathOverflow)?;
+    let received = to.lamports().checked_add(amount)
+        .ok_or(AuctionError::MathOverflow)?;
+    **from.lamports.borrow_mut() = remaining;
+    **to.lamports.borrow_mut() = received;
+    Ok(())
+}
+
+/// Pay `amount` out of the escrowed bid in the auction's currency, signed by the auction PDA
+fn pay_from_auction<'info>(
+    auction: &Account<'info, AuctionState>,
+    auction_payment_account: &Option<Account<'info, TokenAccount>>,
+    destination: AccountInfo<'info>,
+    token_program: &Program<'info, Token>,
+    signer: &[&[&[u8]]],
+    amount: u64,
+) -> Result<()> {
+    match auction.payment_mint {
+        // The auction PDA carries data, so the system program cannot debit it
+        None => move_lamports(&auction.to_account_info(), &destination, amount),
+        Some(_) => {
+            let auction_payment_account = auction_escrow_account(auction, auction_payment_account)?;
+            let transfer_ctx = CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                Transfer {
+                    from: auction_payment_account.to_account_info(),
+                    to: destination,
+                    authority: auction.to_account_info(),
+                },
+                signer,
+            );
+            token::transfer(transfer_ctx, amount)
+        }
+    }
 }
\ No newline at end of file
@@ -1,10 +1,26 @@
 #![allow(unexpected_cfgs)]
 #![allow(deprecated)]
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer, Mint};
-use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, CloseAccount, Token, TokenAccount, Transfer, Mint};
+use anchor_spl::associated_token::{get_associated_token_address, AssociatedToken};
+use auction::AuctionState;
+use listing::ListingState;
+use marketplace::program::Marketplace;
+use marketplace::{MarketplaceState, MARKETPLACE_SEED, SALE_AUTHORITY_SEED};
 
-declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+declare_id!("7DcvjbnmYF4fZxNgoPKPAeRAUMictHjj1dKawgy8VGUk");
+
+/// Maximum number of NFTs each party can put into a swap
+pub const MAX_SWAP_ITEMS: usize = 4;
+
+/// Maximum number of NFTs a single depositor can put into an escrow
+pub const MAX_DEPOSITOR_NFTS: usize = 4;
+
+/// Maximum number of release conditions on one escrow
+pub const MAX_RELEASE_CONDITIONS: usize = 4;
+
+/// Maximum number of named signers that can approve a release
+pub const MAX_APPROVERS: usize = 5;
 
 #[program]
 pub mod escrow {
@@ -16,17 +32,30 @@ pub mod escrow {
         escrow_type: EscrowType,
         duration: Option<i64>,
     ) -> Result<()> {
+        // Direct sales need their buyer and price fixed up front
+        require!(escrow_type != EscrowType::DirectSale, EscrowError::DirectSaleTermsRequired);
+
         let escrow = &mut ctx.accounts.escrow;
         let clock = Clock::get()?;
 
         escrow.authority = ctx.accounts.authority.key();
+        escrow.buyer = None;
+        escrow.arbiter = None;
         escrow.escrow_type = escrow_type;
         escrow.created_at = clock.unix_timestamp;
         escrow.expires_at = duration.map(|d| clock.unix_timestamp + d);
         escrow.nft_mint = None;
+        escrow.nft_count = 0;
         escrow.sol_amount = 0;
+        escrow.depositor_count = 0;
+        escrow.price = 0;
         escrow.is_released = false;
         escrow.is_emergency_withdrawn = false;
+        escrow.is_disputed = false;
+        escrow.release_logic = ConditionLogic::All;
+        escrow.release_conditions = Vec::new();
+        escrow.approvers = Vec::new();
+        escrow.approvals = 0;
         escrow.bump = ctx.bumps.escrow;
 
         emit!(EscrowCreated {
@@ -40,14 +69,78 @@ pub mod escrow {
         Ok(())
     }
 
+    /// Create a `DirectSale` escrow where the authority sells to a designated buyer at a fixed price
+    pub fn create_direct_sale(
+        ctx: Context<CreateEscrow>,
+        buyer: Pubkey,
+        price: u64,
+        duration: Option<i64>,
+    ) -> Result<()> {
+        require!(price > 0, EscrowError::InvalidAmount);
+        require!(buyer != ctx.accounts.authority.key(), EscrowError::InvalidCounterparty);
+
+        let escrow = &mut ctx.accounts.escrow;
+        let clock = Clock::get()?;
+
+        escrow.authority = ctx.accounts.authority.key();
+        escrow.buyer = Some(buyer);
+        escrow.arbiter = None;
+        escrow.escrow_type = EscrowType::DirectSale;
+        escrow.created_at = clock.unix_timestamp;
+        escrow.expires_at = duration.map(|d| clock.unix_timestamp + d);
+        escrow.nft_mint = None;
+        escrow.nft_count = 0;
+        escrow.sol_amount = 0;
+        escrow.depositor_count = 0;
+        escrow.price = price;
+        escrow.is_released = false;
+        escrow.is_emergency_withdrawn = false;
+        escrow.is_disputed = false;
+        escrow.release_logic = ConditionLogic::All;
+        escrow.release_conditions = Vec::new();
+        escrow.approvers = Vec::new();
+        escrow.approvals = 0;
+        escrow.bump = ctx.bumps.escrow;
+
+        emit!(EscrowCreated {
+            escrow: escrow.key(),
+            authority: escrow.authority,
+            escrow_type: EscrowType::DirectSale,
+            created_at: escrow.created_at,
+            expires_at: escrow.expires_at,
+        });
+
+        emit!(DirectSaleCreated {
+            escrow: escrow.key(),
+            seller: escrow.authority,
+            buyer,
+            price,
+        });
+
+        Ok(())
+    }
+
     /// Deposit NFT into escrow
+    ///
+    /// Escrows hold any number of NFTs from any number of depositors, recorded per depositor;
+    /// direct sales hold exactly the seller's one NFT
     pub fn deposit_nft(ctx: Context<DepositNft>) -> Result<()> {
         let escrow = &mut ctx.accounts.escrow;
         
         // Validate escrow state
         require!(!escrow.is_released, EscrowError::EscrowAlreadyReleased);
         require!(!escrow.is_emergency_withdrawn, EscrowError::EscrowEmergencyWithdrawn);
-        require!(escrow.nft_mint.is_none(), EscrowError::NftAlreadyDeposited);
+        require!(escrow.escrow_type != EscrowType::Swap, EscrowError::SwapEscrow);
+        require!(escrow.escrow_type != EscrowType::Vesting, EscrowError::VestingEscrow);
+
+        // Only the seller puts the NFT into a direct sale
+        if escrow.escrow_type == EscrowType::DirectSale {
+            require!(escrow.nft_mint.is_none(), EscrowError::NftAlreadyDeposited);
+            require!(
+                ctx.accounts.depositor.key() == escrow.authority,
+                EscrowError::Unauthorized
+            );
+        }
         
         // Check expiry
         if let Some(expires_at) = escrow.expires_at {
@@ -66,8 +159,25 @@ pub mod escrow {
         );
         token::transfer(transfer_ctx, 1)?;
 
-        // Update escrow state
-        escrow.nft_mint = Some(ctx.accounts.mint.key());
+        // Record the NFT against its depositor so it can be returned to them
+        let mint = ctx.accounts.mint.key();
+        let deposit_record = &mut ctx.accounts.deposit_record;
+        if deposit_record.escrow == Pubkey::default() {
+            deposit_record.escrow = escrow.key();
+            deposit_record.depositor = ctx.accounts.depositor.key();
+            deposit_record.bump = ctx.bumps.deposit_record;
+            escrow.depositor_count = escrow.depositor_count.checked_add(1)
+                .ok_or(EscrowError::MathOverflow)?;
+        }
+        require!(deposit_record.mints.len() < MAX_DEPOSITOR_NFTS, EscrowError::TooManyDepositorNfts);
+        deposit_record.mints.push(mint);
+
+        // Update escrow state; the first NFT is the one single-asset flows release
+        if escrow.nft_mint.is_none() {
+            escrow.nft_mint = Some(mint);
+        }
+        escrow.nft_count = escrow.nft_count.checked_add(1)
+            .ok_or(EscrowError::MathOverflow)?;
 
         emit!(NftDeposited {
             escrow: escrow.key(),
@@ -86,7 +196,20 @@ pub mod escrow {
             
             require!(!escrow.is_released, EscrowError::EscrowAlreadyReleased);
             require!(!escrow.is_emergency_withdrawn, EscrowError::EscrowEmergencyWithdrawn);
+            require!(escrow.escrow_type != EscrowType::Swap, EscrowError::SwapEscrow);
+            require!(escrow.escrow_type != EscrowType::Vesting, EscrowError::VestingEscrow);
             require!(amount > 0, EscrowError::InvalidAmount);
+
+            // Only the designated buyer pays into a direct sale, and never beyond the price
+            if escrow.escrow_type == EscrowType::DirectSale {
+                require!(
+                    Some(ctx.accounts.depositor.key()) == escrow.buyer,
+                    EscrowError::Unauthorized
+                );
+                let total = escrow.sol_amount.checked_add(amount)
+                    .ok_or(EscrowError::MathOverflow)?;
+                require!(total <= escrow.price, EscrowError::ExceedsPrice);
+            }
             
             // Check expiry
             if let Some(expires_at) = escrow.expires_at {
@@ -107,10 +230,25 @@ pub mod escrow {
         );
         anchor_lang::system_program::transfer(transfer_ctx, amount)?;
 
+        // Record who the lamports belong to so they can be refunded on expiry
+        let deposit_record = &mut ctx.accounts.deposit_record;
+        let is_new_depositor = deposit_record.escrow == Pubkey::default();
+        if is_new_depositor {
+            deposit_record.escrow = escrow_key;
+            deposit_record.depositor = depositor_key;
+            deposit_record.bump = ctx.bumps.deposit_record;
+        }
+        deposit_record.sol_amount = deposit_record.sol_amount.checked_add(amount)
+            .ok_or(EscrowError::MathOverflow)?;
+
         // Update escrow state after transfer
         let escrow = &mut ctx.accounts.escrow;
         escrow.sol_amount = current_sol.checked_add(amount)
             .ok_or(EscrowError::MathOverflow)?;
+        if is_new_depositor {
+            escrow.depositor_count = escrow.depositor_count.checked_add(1)
+                .ok_or(EscrowError::MathOverflow)?;
+        }
 
         emit!(SolDeposited {
             escrow: escrow_key,
@@ -123,19 +261,57 @@ pub mod escrow {
     }
 
     /// Release assets from escrow (requires authority or multi-sig)
-    pub fn release_assets(ctx: Context<ReleaseAssets>) -> Result<()> {
+    ///
+    /// Swap escrows exchange both parties' deposits atomically; see `release_swap`.
+    ///
+    /// Remaining accounts: one listing or auction account per settlement condition, in
+    /// condition order, then `[escrow_token_account, recipient_token_account]` per NFT beyond
+    /// the first
+    pub fn release_assets<'info>(ctx: Context<'_, '_, 'info, 'info, ReleaseAssets<'info>>) -> Result<()> {
+        if ctx.accounts.escrow.escrow_type == EscrowType::Swap {
+            return release_swap(ctx);
+        }
+        // Vesting escrows only pay out through `claim_vested` and `revoke_vesting`
+        require!(
+            ctx.accounts.escrow.escrow_type != EscrowType::Vesting,
+            EscrowError::VestingEscrow
+        );
+
+        // The escrow's release conditions must hold on top of the caller checks below
+        let condition_accounts = ctx.accounts.escrow.settlement_condition_count();
+        require!(
+            ctx.remaining_accounts.len() >= condition_accounts,
+            EscrowError::ConditionAccountMissing
+        );
+        let (condition_accounts, nft_accounts) = ctx.remaining_accounts.split_at(condition_accounts);
+        require!(
+            ctx.accounts.escrow.release_conditions_met(Clock::get()?.unix_timestamp, condition_accounts)?,
+            EscrowError::ReleaseConditionsNotMet
+        );
+
         // Validate escrow state and extract needed values
         let (nft_mint, sol_amount, authority, created_at, bump, escrow_key, authority_key, recipient_owner) = {
             let escrow = &ctx.accounts.escrow;
             
             require!(!escrow.is_released, EscrowError::EscrowAlreadyReleased);
             require!(!escrow.is_emergency_withdrawn, EscrowError::EscrowEmergencyWithdrawn);
+            require!(!escrow.is_disputed, EscrowError::EscrowDisputed);
             
-            // Authority check
-            require!(
-                ctx.accounts.authority.key() == escrow.authority,
-                EscrowError::Unauthorized
-            );
+            if escrow.escrow_type == EscrowType::DirectSale {
+                check_direct_sale_release(
+                    escrow,
+                    &ctx.accounts.authority.key(),
+                    ctx.accounts.escrow_token_account.as_deref(),
+                    ctx.accounts.recipient_token_account.as_deref(),
+                    &ctx.accounts.sol_recipient.key(),
+                )?;
+            } else {
+                // Authority check
+                require!(
+                    ctx.accounts.authority.key() == escrow.authority,
+                    EscrowError::Unauthorized
+                );
+            }
 
             (
                 escrow.nft_mint,
@@ -145,7 +321,7 @@ pub mod escrow {
                 escrow.bump,
                 escrow.key(),
                 ctx.accounts.authority.key(),
-                ctx.accounts.recipient_token_account.owner,
+                ctx.accounts.recipient_token_account.as_ref().map(|account| account.owner),
             )
         };
 
@@ -159,29 +335,53 @@ pub mod escrow {
 
         // Release NFT if present
         if nft_mint.is_some() {
+            let escrow_token_account = ctx.accounts.escrow_token_account.as_ref()
+                .ok_or(EscrowError::TokenAccountMissing)?;
+            let recipient_token_account = ctx.accounts.recipient_token_account.as_ref()
+                .ok_or(EscrowError::TokenAccountMissing)?;
+
             let nft_transfer_ctx = CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
                 Transfer {
-                    from: ctx.accounts.escrow_token_account.to_account_info(),
-                    to: ctx.accounts.recipient_token_account.to_account_info(),
+                    from: escrow_token_account.to_account_info(),
+                    to: recipient_token_account.to_account_info(),
                     authority: ctx.accounts.escrow.to_account_info(),
                 },
                 signer,
             );
             token::transfer(nft_transfer_ctx, 1)?;
+
+            // Any further NFTs follow the first to the same owner
+            transfer_additional_nfts(
+                &ctx.accounts.escrow,
+                &ctx.accounts.token_program,
+                nft_accounts,
+                &recipient_token_account.owner,
+            )?;
         }
 
         // Release SOL if present
         if sol_amount > 0 {
-            let sol_transfer_ctx = CpiContext::new_with_signer(
-                ctx.accounts.system_program.to_account_info(),
-                anchor_lang::system_program::Transfer {
-                    from: ctx.accounts.escrow.to_account_info(),
-                    to: ctx.accounts.sol_recipient.to_account_info(),
-                },
-                signer,
+            // The escrow PDA carries data, so the system program cannot debit it
+            **ctx.accounts.escrow.to_account_info().lamports.borrow_mut() -= sol_amount;
+            **ctx.accounts.sol_recipient.to_account_info().lamports.borrow_mut() += sol_amount;
+        }
+
+        // An NFT released together with SOL is a settled sale
+        if nft_mint.is_some() && sol_amount > 0 {
+            let sale_authority_seeds = &[SALE_AUTHORITY_SEED, &[ctx.bumps.sale_authority]];
+            let sale_authority_signer = &[&sale_authority_seeds[..]];
+            let cpi_accounts = marketplace::cpi::accounts::UpdateStats {
+                marketplace: ctx.accounts.marketplace.to_account_info(),
+                sale_authority: ctx.accounts.sale_authority.to_account_info(),
+                caller_program: ctx.accounts.escrow_program.to_account_info(),
+            };
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.marketplace_program.to_account_info(),
+                cpi_accounts,
+                sale_authority_signer,
             );
-            anchor_lang::system_program::transfer(sol_transfer_ctx, sol_amount)?;
+            marketplace::cpi::update_stats(cpi_ctx, sol_amount)?;
         }
 
         // Update escrow state after transfers
@@ -192,8 +392,9 @@ pub mod escrow {
             escrow: escrow_key,
             authority: authority_key,
             nft_mint,
+            nft_count: escrow.nft_count,
             sol_amount,
-            nft_recipient: recipient_owner,
+            nft_recipient: recipient_owner.unwrap_or(ctx.accounts.sol_recipient.key()),
             sol_recipient: ctx.accounts.sol_recipient.key(),
         });
 
@@ -201,7 +402,9 @@ pub mod escrow {
     }
 
     /// Emergency withdraw (admin only, for stuck assets)
-    pub fn emergency_withdraw(ctx: Context<EmergencyWithdraw>) -> Result<()> {
+    ///
+    /// Remaining accounts: `[escrow_token_account, recovery_token_account]` per NFT beyond the first
+    pub fn emergency_withdraw<'info>(ctx: Context<'_, '_, 'info, 'info, EmergencyWithdraw<'info>>) -> Result<()> {
         // Validate escrow state and extract needed values
         let (nft_mint, sol_amount, authority, created_at, bump, escrow_key, admin_key) = {
             let escrow = &ctx.accounts.escrow;
@@ -246,19 +449,20 @@ pub mod escrow {
                 signer,
             );
             token::transfer(nft_transfer_ctx, 1)?;
+
+            transfer_additional_nfts(
+                &ctx.accounts.escrow,
+                &ctx.accounts.token_program,
+                ctx.remaining_accounts,
+                &ctx.accounts.recovery_token_account.owner,
+            )?;
         }
 
         // Emergency withdraw SOL if present
         if sol_amount > 0 {
-            let sol_transfer_ctx = CpiContext::new_with_signer(
-                ctx.accounts.system_program.to_account_info(),
-                anchor_lang::system_program::Transfer {
-                    from: ctx.accounts.escrow.to_account_info(),
-                    to: ctx.accounts.recovery_sol_account.to_account_info(),
-                },
-                signer,
-            );
-            anchor_lang::system_program::transfer(sol_transfer_ctx, sol_amount)?;
+            // The escrow PDA carries data, so the system program cannot debit it
+            **ctx.accounts.escrow.to_account_info().lamports.borrow_mut() -= sol_amount;
+            **ctx.accounts.recovery_sol_account.to_account_info().lamports.borrow_mut() += sol_amount;
         }
 
         // Update escrow state after transfers
@@ -269,6 +473,7 @@ pub mod escrow {
             escrow: escrow_key,
             admin: admin_key,
             nft_mint,
+            nft_count: escrow.nft_count,
             sol_amount,
             recovery_account: ctx.accounts.recovery_sol_account.key(),
         });
@@ -276,181 +481,1740 @@ pub mod escrow {
         Ok(())
     }
 
-    /// Get escrow status
-    pub fn get_escrow_status(ctx: Context<GetEscrowStatus>) -> Result<EscrowStatus> {
-        let escrow = &ctx.accounts.escrow;
-        let clock = Clock::get()?;
-        
-        let status = if escrow.is_released {
-            EscrowStatus::Released
-        } else if escrow.is_emergency_withdrawn {
-            EscrowStatus::EmergencyWithdrawn
-        } else if let Some(expires_at) = escrow.expires_at {
-            if clock.unix_timestamp >= expires_at {
-                EscrowStatus::Expired
-            } else {
-                EscrowStatus::Active
+    /// Program when the escrow may be released; only possible while the escrow is still empty
+    ///
+    /// An empty condition list removes every condition
+    pub fn set_release_conditions(
+        ctx: Context<SetReleaseConditions>,
+        logic: ConditionLogic,
+        conditions: Vec<ReleaseCondition>,
+        approvers: Vec<Pubkey>,
+    ) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        require!(!escrow.is_released, EscrowError::EscrowAlreadyReleased);
+        require!(!escrow.is_emergency_withdrawn, EscrowError::EscrowEmergencyWithdrawn);
+        require!(escrow.escrow_type != EscrowType::Swap, EscrowError::SwapEscrow);
+        require!(
+            escrow.nft_count == 0 && escrow.sol_amount == 0,
+            EscrowError::EscrowNotEmpty
+        );
+        require!(conditions.len() <= MAX_RELEASE_CONDITIONS, EscrowError::InvalidReleaseConditions);
+        require!(approvers.len() <= MAX_APPROVERS, EscrowError::InvalidReleaseConditions);
+        for (index, approver) in approvers.iter().enumerate() {
+            require!(!approvers[..index].contains(approver), EscrowError::InvalidReleaseConditions);
+        }
+        for condition in &conditions {
+            if let ReleaseCondition::Approvals { threshold } = condition {
+                require!(
+                    *threshold > 0 && *threshold as usize <= approvers.len(),
+                    EscrowError::InvalidReleaseConditions
+                );
             }
-        } else {
-            EscrowStatus::Active
-        };
+        }
 
-        Ok(status)
-    }
-}
+        escrow.release_logic = logic;
+        escrow.release_conditions = conditions;
+        escrow.approvers = approvers;
+        escrow.approvals = 0;
 
-#[derive(Accounts)]
-pub struct CreateEscrow<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + EscrowState::INIT_SPACE,
-        seeds = [b"escrow", authority.key().as_ref(), &Clock::get()?.unix_timestamp.to_le_bytes()],
-        bump
-    )]
-    pub escrow: Account<'info, EscrowState>,
-    
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
-}
+        emit!(ReleaseConditionsSet {
+            escrow: escrow.key(),
+            logic,
+            conditions: escrow.release_conditions.clone(),
+            approvers: escrow.approvers.clone(),
+        });
 
-// Fixed: Added #[derive(Accounts)] macro
-#[derive(Accounts)]
-pub struct DepositNft<'info> {
-    #[account(
-        mut,
-        seeds = [b"escrow", escrow.authority.as_ref(), &escrow.created_at.to_le_bytes()],
-        bump = escrow.bump
-    )]
-    pub escrow: Account<'info, EscrowState>,
-    
-    // Removed has_one constraint since depositor might be different from authority
-    pub authority: Signer<'info>,
-    
-    #[account(mut)]
-    pub depositor: Signer<'info>,
-    
-    pub mint: Account<'info, Mint>,
-    
-    #[account(
-        mut,
-        associated_token::mint = mint,
-        associated_token::authority = depositor,
-        constraint = depositor_token_account.amount == 1 @ EscrowError::InvalidAmount
-    )]
-    pub depositor_token_account: Account<'info, TokenAccount>,
-    
-    #[account(
-        init_if_needed,
-        payer = depositor,
-        associated_token::mint = mint,
-        associated_token::authority = escrow
-    )]
-    pub escrow_token_account: Account<'info, TokenAccount>,
-    
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>,
-}
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct DepositSol<'info> {
-    #[account(
-        mut,
-        seeds = [b"escrow", escrow.authority.as_ref(), &escrow.created_at.to_le_bytes()],
-        bump = escrow.bump
-    )]
-    pub escrow: Account<'info, EscrowState>,
-    
-    pub authority: Signer<'info>,
-    
-    #[account(mut)]
-    pub depositor: Signer<'info>,
-    
-    pub system_program: Program<'info, System>,
-}
+    /// Record a named approver's sign-off towards an `Approvals` release condition
+    pub fn approve_release(ctx: Context<ApproveRelease>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        require!(!escrow.is_released, EscrowError::EscrowAlreadyReleased);
+        require!(!escrow.is_emergency_withdrawn, EscrowError::EscrowEmergencyWithdrawn);
 
-#[derive(Accounts)]
-pub struct ReleaseAssets<'info> {
-    #[account(
-        mut,
-        seeds = [b"escrow", escrow.authority.as_ref(), &escrow.created_at.to_le_bytes()],
-        bump = escrow.bump
-    )]
-    pub escrow: Account<'info, EscrowState>,
-    
-    pub authority: Signer<'info>,
-    
-    #[account(mut)]
-    pub escrow_token_account: Account<'info, TokenAccount>,
-    
-    #[account(mut)]
-    pub recipient_token_account: Account<'info, TokenAccount>,
-    
-    /// CHECK: SOL recipient account
-    #[account(mut)]
-    pub sol_recipient: AccountInfo<'info>,
-    
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-}
+        let approver = ctx.accounts.approver.key();
+        let index = escrow.approvers.iter()
+            .position(|named| *named == approver)
+            .ok_or(EscrowError::NotApprover)?;
+        escrow.approvals |= 1 << index;
 
-#[derive(Accounts)]
-pub struct EmergencyWithdraw<'info> {
-    #[account(
-        mut,
-        seeds = [b"escrow", escrow.authority.as_ref(), &escrow.created_at.to_le_bytes()],
-        bump = escrow.bump
-    )]
-    pub escrow: Account<'info, EscrowState>,
-    
-    pub admin: Signer<'info>,
-    
-    pub marketplace: Account<'info, marketplace::MarketplaceState>,
-    
-    #[account(mut)]
-    pub escrow_token_account: Account<'info, TokenAccount>,
-    
-    #[account(mut)]
-    pub recovery_token_account: Account<'info, TokenAccount>,
-    
-    /// CHECK: Recovery SOL account
-    #[account(mut)]
-    pub recovery_sol_account: AccountInfo<'info>,
-    
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-}
+        emit!(ReleaseApproved {
+            escrow: escrow.key(),
+            approver,
+            approvals: escrow.approvals.count_ones() as u8,
+        });
 
-#[derive(Accounts)]
-pub struct GetEscrowStatus<'info> {
-    #[account(
-        seeds = [b"escrow", escrow.authority.as_ref(), &escrow.created_at.to_le_bytes()],
-        bump = escrow.bump
-    )]
-    pub escrow: Account<'info, EscrowState>,
-}
+        Ok(())
+    }
 
-#[account]
-#[derive(InitSpace)]
-pub struct EscrowState {
-    pub authority: Pubkey,              // 32
-    pub escrow_type: EscrowType,        // 1 + size
-    pub created_at: i64,                // 8
-    pub expires_at: Option<i64>,        // 1 + 8
-    pub nft_mint: Option<Pubkey>,       // 1 + 32
-    pub sol_amount: u64,                // 8
-    pub is_released: bool,              // 1
-    pub is_emergency_withdrawn: bool,   // 1
-    pub bump: u8,                       // 1
-}
+    /// Appoint the arbiter who settles disputes; only possible on a direct sale while the escrow
+    /// is still empty
+    pub fn set_arbiter(ctx: Context<SetArbiter>, arbiter: Option<Pubkey>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        require!(escrow.escrow_type == EscrowType::DirectSale, EscrowError::NotDirectSale);
+        require!(!escrow.is_released, EscrowError::EscrowAlreadyReleased);
+        require!(!escrow.is_emergency_withdrawn, EscrowError::EscrowEmergencyWithdrawn);
+        require!(
+            escrow.nft_count == 0 && escrow.sol_amount == 0,
+            EscrowError::EscrowNotEmpty
+        );
+        if let Some(arbiter) = arbiter {
+            require!(
+                arbiter != escrow.authority && Some(arbiter) != escrow.buyer,
+                EscrowError::InvalidArbiter
+            );
+        }
 
-impl EscrowState {
-    pub const INIT_SPACE: usize = 32 + 1 + 1 + 8 + 1 + 8 + 1 + 32 + 8 + 1 + 1 + 1; // 95 bytes
-}
+        escrow.arbiter = arbiter;
+
+        emit!(ArbiterSet {
+            escrow: escrow.key(),
+            arbiter,
+        });
+
+        Ok(())
+    }
+
+    /// Freeze release until the arbiter resolves the escrow (seller or buyer)
+    ///
+    /// Only direct sales name the two parties a dispute is settled between, so other escrow
+    /// types cannot be disputed
+    pub fn raise_dispute(ctx: Context<RaiseDispute>) -> Result<()> {
+        let escrow = &mut ctx.accounts.escrow;
+        require!(escrow.escrow_type == EscrowType::DirectSale, EscrowError::NotDirectSale);
+        require!(!escrow.is_released, EscrowError::EscrowAlreadyReleased);
+        require!(!escrow.is_emergency_withdrawn, EscrowError::EscrowEmergencyWithdrawn);
+        require!(!escrow.is_disputed, EscrowError::EscrowDisputed);
+        require!(escrow.arbiter.is_some(), EscrowError::NoArbiter);
+
+        let buyer = escrow.buyer.ok_or(EscrowError::NotDirectSale)?;
+        let party = ctx.accounts.party.key();
+        require!(party == escrow.authority || party == buyer, EscrowError::Unauthorized);
+
+        escrow.is_disputed = true;
+
+        emit!(DisputeRaised {
+            escrow: escrow.key(),
+            raised_by: party,
+        });
+
+        Ok(())
+    }
+
+    /// Arbiter settles a dispute: the seller receives `seller_sol_amount`, the buyer the rest
+    /// of the escrowed SOL, and the NFTs go to the chosen party
+    ///
+    /// Remaining accounts: `[escrow_token_account, recipient_token_account]` per NFT beyond the first
+    pub fn resolve_dispute<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ResolveDispute<'info>>,
+        seller_sol_amount: u64,
+        nft_recipient: DisputeParty,
+    ) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.is_disputed, EscrowError::NoDispute);
+        require!(!escrow.is_released, EscrowError::EscrowAlreadyReleased);
+        require!(!escrow.is_emergency_withdrawn, EscrowError::EscrowEmergencyWithdrawn);
+        require!(seller_sol_amount <= escrow.sol_amount, EscrowError::InvalidAmount);
+
+        let buyer_sol_amount = escrow.sol_amount - seller_sol_amount;
+        let nft_owner = match nft_recipient {
+            DisputeParty::Seller => escrow.authority,
+            DisputeParty::Buyer => ctx.accounts.buyer.key(),
+        };
+
+        // Route the NFT to the party the arbiter picked
+        if let Some(nft_mint) = escrow.nft_mint {
+            let escrow_token_account = ctx.accounts.escrow_token_account.as_ref()
+                .ok_or(EscrowError::TokenAccountMissing)?;
+            let recipient_token_account = ctx.accounts.recipient_token_account.as_ref()
+                .ok_or(EscrowError::TokenAccountMissing)?;
+            require!(
+                escrow_token_account.owner == escrow.key() && escrow_token_account.mint == nft_mint,
+                EscrowError::InvalidRecipient
+            );
+            require!(
+                recipient_token_account.owner == nft_owner && recipient_token_account.mint == nft_mint,
+                EscrowError::InvalidRecipient
+            );
+
+            let created_at = escrow.created_at.to_le_bytes();
+            let escrow_seeds = &[
+                b"escrow",
+                escrow.authority.as_ref(),
+                &created_at,
+                &[escrow.bump],
+            ];
+            let signer = &[&escrow_seeds[..]];
+
+            let nft_transfer_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: escrow_token_account.to_account_info(),
+                    to: recipient_token_account.to_account_info(),
+                    authority: escrow.to_account_info(),
+                },
+                signer,
+            );
+            token::transfer(nft_transfer_ctx, 1)?;
+
+            transfer_additional_nfts(
+                escrow,
+                &ctx.accounts.token_program,
+                ctx.remaining_accounts,
+                &nft_owner,
+            )?;
+        }
+
+        // Split the escrowed SOL between the parties
+        let escrow_info = ctx.accounts.escrow.to_account_info();
+        if seller_sol_amount > 0 {
+            move_lamports(&escrow_info, &ctx.accounts.seller, seller_sol_amount)?;
+        }
+        if buyer_sol_amount > 0 {
+            move_lamports(&escrow_info, &ctx.accounts.buyer, buyer_sol_amount)?;
+        }
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.sol_amount = 0;
+        escrow.is_disputed = false;
+        escrow.is_released = true;
+
+        emit!(DisputeResolved {
+            escrow: escrow.key(),
+            arbiter: ctx.accounts.arbiter.key(),
+            nft_mint: escrow.nft_mint,
+            nft_count: escrow.nft_count,
+            nft_recipient: nft_owner,
+            seller_sol_amount,
+            buyer_sol_amount,
+        });
+
+        Ok(())
+    }
+
+    /// Return every deposit of an expired escrow to its depositor and close it (permissionless)
+    ///
+    /// Remaining accounts, per deposit record: `[deposit_record, depositor]` followed by
+    /// `[escrow_token_account, depositor_token_account]` for each NFT in the record
+    pub fn refund_expired<'info>(ctx: Context<'_, '_, 'info, 'info, RefundExpired<'info>>) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(!escrow.is_released, EscrowError::EscrowAlreadyReleased);
+        require!(!escrow.is_emergency_withdrawn, EscrowError::EscrowEmergencyWithdrawn);
+        require!(!escrow.is_disputed, EscrowError::EscrowDisputed);
+        require!(escrow.escrow_type != EscrowType::Swap, EscrowError::SwapEscrow);
+        require!(escrow.escrow_type != EscrowType::Vesting, EscrowError::VestingEscrow);
+
+        let expires_at = escrow.expires_at.ok_or(EscrowError::EscrowNotExpired)?;
+        let clock = Clock::get()?;
+        require!(clock.unix_timestamp >= expires_at, EscrowError::EscrowNotExpired);
+
+        let escrow_key = escrow.key();
+        let created_at = escrow.created_at.to_le_bytes();
+        let escrow_seeds = &[
+            b"escrow",
+            escrow.authority.as_ref(),
+            &created_at,
+            &[escrow.bump],
+        ];
+        let signer = &[&escrow_seeds[..]];
+
+        let escrow_info = ctx.accounts.escrow.to_account_info();
+        let mut accounts = ctx.remaining_accounts;
+        let mut sol_refunded: u64 = 0;
+        let mut nfts_returned: u32 = 0;
+        let mut depositors = Vec::with_capacity(escrow.depositor_count as usize);
+        for _ in 0..escrow.depositor_count {
+            require!(accounts.len() >= 2, EscrowError::InvalidDepositAccounts);
+            let (record_info, depositor) = (&accounts[0], &accounts[1]);
+            let record = Account::<DepositRecord>::try_from(record_info)?;
+            require_keys_eq!(record.escrow, escrow_key, EscrowError::InvalidDepositAccounts);
+            require_keys_eq!(record.depositor, depositor.key(), EscrowError::InvalidDepositAccounts);
+            require!(
+                depositor.is_writable && !depositors.contains(&record.depositor),
+                EscrowError::InvalidDepositAccounts
+            );
+
+            let nft_accounts_len = record.mints.len() * 2;
+            require!(accounts.len() >= 2 + nft_accounts_len, EscrowError::InvalidDepositAccounts);
+            let nft_accounts = &accounts[2..2 + nft_accounts_len];
+
+            // Return each NFT, and the rent of its escrow token account, to its depositor
+            for (mint, pair) in record.mints.iter().zip(nft_accounts.chunks(2)) {
+                let (escrow_token_account, depositor_token_account) = (&pair[0], &pair[1]);
+                require_keys_eq!(
+                    escrow_token_account.key(),
+                    get_associated_token_address(&escrow_key, mint),
+                    EscrowError::InvalidDepositAccounts
+                );
+                require_keys_eq!(
+                    depositor_token_account.key(),
+                    get_associated_token_address(&record.depositor, mint),
+                    EscrowError::InvalidDepositAccounts
+                );
+
+                let nft_transfer_ctx = CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: escrow_token_account.clone(),
+                        to: depositor_token_account.clone(),
+                        authority: escrow_info.clone(),
+                    },
+                    signer,
+                );
+                token::transfer(nft_transfer_ctx, 1)?;
+
+                let close_ctx = CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    CloseAccount {
+                        account: escrow_token_account.clone(),
+                        destination: depositor.clone(),
+                        authority: escrow_info.clone(),
+                    },
+                    signer,
+                );
+                token::close_account(close_ctx)?;
+            }
+
+            if record.sol_amount > 0 {
+                move_lamports(&escrow_info, depositor, record.sol_amount)?;
+            }
+            sol_refunded = sol_refunded.checked_add(record.sol_amount)
+                .ok_or(EscrowError::MathOverflow)?;
+            nfts_returned = nfts_returned.checked_add(record.mints.len() as u32)
+                .ok_or(EscrowError::MathOverflow)?;
+            depositors.push(record.depositor);
+
+            // The depositor paid the record's rent, so it goes back to them
+            record.close(depositor.clone())?;
+            accounts = &accounts[2 + nft_accounts_len..];
+        }
+        require!(
+            accounts.is_empty()
+                && sol_refunded == escrow.sol_amount
+                && nfts_returned == escrow.nft_count,
+            EscrowError::InvalidDepositAccounts
+        );
+
+        emit!(ExpiredEscrowRefunded {
+            escrow: escrow_key,
+            nfts_returned,
+            sol_refunded,
+            depositors,
+        });
+
+        Ok(())
+    }
+
+    /// Fund a `Vesting` escrow with SOL that unlocks to the beneficiary over time
+    pub fn create_vesting_schedule(
+        ctx: Context<CreateVestingSchedule>,
+        beneficiary: Pubkey,
+        total_amount: u64,
+        kind: VestingKind,
+        start_time: i64,
+        cliff_time: i64,
+        end_time: i64,
+    ) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.escrow_type == EscrowType::Vesting, EscrowError::NotVestingEscrow);
+        require!(!escrow.is_released, EscrowError::EscrowAlreadyReleased);
+        require!(!escrow.is_emergency_withdrawn, EscrowError::EscrowEmergencyWithdrawn);
+        require!(total_amount > 0, EscrowError::InvalidAmount);
+        require!(
+            start_time <= cliff_time && cliff_time <= end_time && start_time < end_time,
+            EscrowError::InvalidVestingSchedule
+        );
+
+        // Lock the full amount in the escrow PDA up front
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.authority.to_account_info(),
+                to: ctx.accounts.escrow.to_account_info(),
+            },
+        );
+        anchor_lang::system_program::transfer(transfer_ctx, total_amount)?;
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.sol_amount = escrow.sol_amount.checked_add(total_amount)
+            .ok_or(EscrowError::MathOverflow)?;
+
+        let schedule = &mut ctx.accounts.schedule;
+        schedule.escrow = escrow.key();
+        schedule.creator = escrow.authority;
+        schedule.beneficiary = beneficiary;
+        schedule.kind = kind;
+        schedule.total_amount = total_amount;
+        schedule.claimed_amount = 0;
+        schedule.start_time = start_time;
+        schedule.cliff_time = cliff_time;
+        schedule.end_time = end_time;
+        schedule.is_revoked = false;
+        schedule.bump = ctx.bumps.schedule;
+
+        emit!(VestingScheduleCreated {
+            escrow: schedule.escrow,
+            schedule: schedule.key(),
+            creator: schedule.creator,
+            beneficiary,
+            kind,
+            total_amount,
+            start_time,
+            cliff_time,
+            end_time,
+        });
+
+        Ok(())
+    }
+
+    /// Pay the beneficiary everything vested so far (permissionless)
+    pub fn claim_vested(ctx: Context<ClaimVested>) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(!escrow.is_released, EscrowError::EscrowAlreadyReleased);
+        require!(!escrow.is_emergency_withdrawn, EscrowError::EscrowEmergencyWithdrawn);
+
+        let clock = Clock::get()?;
+        let schedule = &ctx.accounts.schedule;
+        let claimable = schedule.vested_amount(clock.unix_timestamp)?
+            .checked_sub(schedule.claimed_amount)
+            .ok_or(EscrowError::MathOverflow)?;
+        require!(claimable > 0, EscrowError::NothingVested);
+
+        move_lamports(&ctx.accounts.escrow.to_account_info(), &ctx.accounts.beneficiary, claimable)?;
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.sol_amount = escrow.sol_amount.checked_sub(claimable)
+            .ok_or(EscrowError::MathOverflow)?;
+
+        let schedule = &mut ctx.accounts.schedule;
+        schedule.claimed_amount = schedule.claimed_amount.checked_add(claimable)
+            .ok_or(EscrowError::MathOverflow)?;
+
+        // A fully claimed schedule leaves nothing in the escrow
+        if schedule.claimed_amount == schedule.total_amount {
+            ctx.accounts.escrow.is_released = true;
+        }
+
+        emit!(VestedClaimed {
+            escrow: schedule.escrow,
+            schedule: schedule.key(),
+            beneficiary: schedule.beneficiary,
+            amount: claimable,
+            total_claimed: schedule.claimed_amount,
+        });
+
+        Ok(())
+    }
+
+    /// Stop a schedule: what has vested stays claimable, the rest returns to the creator
+    pub fn revoke_vesting(ctx: Context<RevokeVesting>) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(!escrow.is_released, EscrowError::EscrowAlreadyReleased);
+        require!(!escrow.is_emergency_withdrawn, EscrowError::EscrowEmergencyWithdrawn);
+
+        let clock = Clock::get()?;
+        let schedule = &ctx.accounts.schedule;
+        require!(!schedule.is_revoked, EscrowError::VestingRevoked);
+
+        let vested = schedule.vested_amount(clock.unix_timestamp)?;
+        let unvested = schedule.total_amount.checked_sub(vested)
+            .ok_or(EscrowError::MathOverflow)?;
+
+        if unvested > 0 {
+            move_lamports(&ctx.accounts.escrow.to_account_info(), &ctx.accounts.creator, unvested)?;
+        }
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.sol_amount = escrow.sol_amount.checked_sub(unvested)
+            .ok_or(EscrowError::MathOverflow)?;
+
+        // Freezing the total at the vested amount keeps it claimable
+        let schedule = &mut ctx.accounts.schedule;
+        schedule.total_amount = vested;
+        schedule.is_revoked = true;
+        if schedule.claimed_amount == schedule.total_amount {
+            ctx.accounts.escrow.is_released = true;
+        }
+
+        emit!(VestingRevoked {
+            escrow: schedule.escrow,
+            schedule: schedule.key(),
+            creator: schedule.creator,
+            vested_amount: vested,
+            returned_amount: unvested,
+        });
+
+        Ok(())
+    }
+
+    /// Close a finished escrow, returning every rent deposit to whoever paid it (permissionless)
+    ///
+    /// Remaining accounts, per deposit record: `[deposit_record, depositor]` followed by the
+    /// escrow token account of each NFT in the record. Swaps instead pass the initiator and
+    /// counterparty wallets, then the escrow token account of each deposited NFT, initiator
+    /// deposits first
+    pub fn close_escrow<'info>(ctx: Context<'_, '_, 'info, 'info, CloseEscrow<'info>>) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        let swap_canceled = ctx.accounts.swap.as_ref().is_some_and(|swap| swap.is_canceled);
+        require!(
+            escrow.is_released || escrow.is_emergency_withdrawn || swap_canceled,
+            EscrowError::EscrowNotFinished
+        );
+        if escrow.escrow_type == EscrowType::Swap {
+            require!(ctx.accounts.swap.is_some(), EscrowError::SwapAccountMissing);
+        }
+        if escrow.escrow_type == EscrowType::Vesting {
+            require!(ctx.accounts.schedule.is_some(), EscrowError::VestingScheduleMissing);
+        }
+
+        let escrow_key = escrow.key();
+        let created_at = escrow.created_at.to_le_bytes();
+        let escrow_seeds = &[
+            b"escrow",
+            escrow.authority.as_ref(),
+            &created_at,
+            &[escrow.bump],
+        ];
+        let signer = &[&escrow_seeds[..]];
+
+        // Every emptied escrow token account goes back to the wallet that created it
+        let escrow_info = ctx.accounts.escrow.to_account_info();
+        let close_token_account = |token_account: &AccountInfo<'info>, mint: &Pubkey, payer: &AccountInfo<'info>| {
+            require_keys_eq!(
+                token_account.key(),
+                get_associated_token_address(&escrow_key, mint),
+                EscrowError::InvalidDepositAccounts
+            );
+            let close_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                CloseAccount {
+                    account: token_account.clone(),
+                    destination: payer.clone(),
+                    authority: escrow_info.clone(),
+                },
+                signer,
+            );
+            token::close_account(close_ctx)
+        };
+
+        let mut accounts = ctx.remaining_accounts;
+        let mut token_accounts_closed: u32 = 0;
+        if let Some(swap) = ctx.accounts.swap.as_ref() {
+            require!(accounts.len() >= 2, EscrowError::InvalidSwapAccounts);
+            let (initiator, counterparty) = (&accounts[0], &accounts[1]);
+            require_keys_eq!(initiator.key(), swap.initiator, EscrowError::InvalidSwapAccounts);
+            require_keys_eq!(counterparty.key(), swap.counterparty, EscrowError::InvalidSwapAccounts);
+            require!(initiator.is_writable && counterparty.is_writable, EscrowError::InvalidSwapAccounts);
+            accounts = &accounts[2..];
+
+            let deposits: Vec<(&Pubkey, &AccountInfo<'info>)> = swap.initiator_mints.iter()
+                .map(|mint| (mint, initiator))
+                .chain(swap.counterparty_mints.iter().map(|mint| (mint, counterparty)))
+                .collect();
+            require!(accounts.len() >= deposits.len(), EscrowError::InvalidSwapAccounts);
+            for ((mint, payer), token_account) in deposits.iter().zip(accounts.iter()) {
+                close_token_account(token_account, mint, payer)?;
+            }
+            token_accounts_closed = deposits.len() as u32;
+            accounts = &accounts[deposits.len()..];
+        }
+
+        for _ in 0..escrow.depositor_count {
+            require!(accounts.len() >= 2, EscrowError::InvalidDepositAccounts);
+            let (record_info, depositor) = (&accounts[0], &accounts[1]);
+            let record = Account::<DepositRecord>::try_from(record_info)?;
+            require_keys_eq!(record.escrow, escrow_key, EscrowError::InvalidDepositAccounts);
+            require_keys_eq!(record.depositor, depositor.key(), EscrowError::InvalidDepositAccounts);
+            require!(depositor.is_writable, EscrowError::InvalidDepositAccounts);
+
+            let token_accounts = &accounts[2..];
+            require!(token_accounts.len() >= record.mints.len(), EscrowError::InvalidDepositAccounts);
+            for (mint, token_account) in record.mints.iter().zip(token_accounts.iter()) {
+                close_token_account(token_account, mint, depositor)?;
+            }
+            token_accounts_closed = token_accounts_closed.checked_add(record.mints.len() as u32)
+                .ok_or(EscrowError::MathOverflow)?;
+
+            accounts = &accounts[2 + record.mints.len()..];
+            record.close(depositor.clone())?;
+        }
+        require!(accounts.is_empty(), EscrowError::InvalidDepositAccounts);
+
+        emit!(EscrowClosed {
+            escrow: escrow_key,
+            authority: escrow.authority,
+            depositors: escrow.depositor_count,
+            token_accounts_closed,
+        });
+
+        Ok(())
+    }
+
+    /// Get escrow status
+    pub fn get_escrow_status(ctx: Context<GetEscrowStatus>) -> Result<EscrowStatus> {
+        let escrow = &ctx.accounts.escrow;
+        let clock = Clock::get()?;
+        
+        let status = if escrow.is_released {
+            EscrowStatus::Released
+        } else if escrow.is_emergency_withdrawn {
+            EscrowStatus::EmergencyWithdrawn
+        } else if escrow.is_disputed {
+            EscrowStatus::Disputed
+        } else if let Some(expires_at) = escrow.expires_at {
+            if clock.unix_timestamp >= expires_at {
+                EscrowStatus::Expired
+            } else {
+                EscrowStatus::Active
+            }
+        } else {
+            EscrowStatus::Active
+        };
+
+        Ok(status)
+    }
+
+    /// Open a two-party swap on a `Swap` escrow, with the escrow authority as initiator
+    pub fn create_swap(ctx: Context<CreateSwap>, counterparty: Pubkey) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        require!(escrow.escrow_type == EscrowType::Swap, EscrowError::NotSwapEscrow);
+        require!(!escrow.is_released, EscrowError::EscrowAlreadyReleased);
+        require!(!escrow.is_emergency_withdrawn, EscrowError::EscrowEmergencyWithdrawn);
+        require!(counterparty != escrow.authority, EscrowError::InvalidCounterparty);
+
+        let swap = &mut ctx.accounts.swap;
+        swap.escrow = escrow.key();
+        swap.initiator = escrow.authority;
+        swap.counterparty = counterparty;
+        swap.initiator_mints = Vec::new();
+        swap.counterparty_mints = Vec::new();
+        swap.initiator_sol = 0;
+        swap.counterparty_sol = 0;
+        swap.initiator_accepted = false;
+        swap.counterparty_accepted = false;
+        swap.is_canceled = false;
+        swap.bump = ctx.bumps.swap;
+
+        emit!(SwapCreated {
+            escrow: swap.escrow,
+            swap: swap.key(),
+            initiator: swap.initiator,
+            counterparty,
+        });
+
+        Ok(())
+    }
+
+    /// Deposit one of the party's NFTs into a swap
+    pub fn deposit_swap_nft(ctx: Context<DepositSwapNft>) -> Result<()> {
+        check_swap_open(&ctx.accounts.escrow, &ctx.accounts.swap)?;
+
+        let party = ctx.accounts.party.key();
+        let mint = ctx.accounts.mint.key();
+        let swap = &mut ctx.accounts.swap;
+        let is_initiator = swap.is_initiator(&party)?;
+        require!(
+            !swap.initiator_mints.contains(&mint) && !swap.counterparty_mints.contains(&mint),
+            EscrowError::NftAlreadyDeposited
+        );
+
+        let side_mints = if is_initiator {
+            &mut swap.initiator_mints
+        } else {
+            &mut swap.counterparty_mints
+        };
+        require!(side_mints.len() < MAX_SWAP_ITEMS, EscrowError::TooManySwapItems);
+        side_mints.push(mint);
+
+        // Changing the terms voids any acceptance already given
+        swap.initiator_accepted = false;
+        swap.counterparty_accepted = false;
+
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.party_token_account.to_account_info(),
+                to: ctx.accounts.escrow_token_account.to_account_info(),
+                authority: ctx.accounts.party.to_account_info(),
+            },
+        );
+        token::transfer(transfer_ctx, 1)?;
+
+        emit!(NftDeposited {
+            escrow: ctx.accounts.escrow.key(),
+            mint,
+            depositor: party,
+        });
+
+        Ok(())
+    }
+
+    /// Deposit lamports into the party's side of a swap
+    pub fn deposit_swap_sol(ctx: Context<DepositSwapSol>, amount: u64) -> Result<()> {
+        check_swap_open(&ctx.accounts.escrow, &ctx.accounts.swap)?;
+        require!(amount > 0, EscrowError::InvalidAmount);
+
+        let party = ctx.accounts.party.key();
+        let swap = &mut ctx.accounts.swap;
+        if swap.is_initiator(&party)? {
+            swap.initiator_sol = swap.initiator_sol.checked_add(amount)
+                .ok_or(EscrowError::MathOverflow)?;
+        } else {
+            swap.counterparty_sol = swap.counterparty_sol.checked_add(amount)
+                .ok_or(EscrowError::MathOverflow)?;
+        }
+
+        // Changing the terms voids any acceptance already given
+        swap.initiator_accepted = false;
+        swap.counterparty_accepted = false;
+
+        let transfer_ctx = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.party.to_account_info(),
+                to: ctx.accounts.escrow.to_account_info(),
+            },
+        );
+        anchor_lang::system_program::transfer(transfer_ctx, amount)?;
+
+        // The escrow's balance covers both sides so emergency recovery sees all of it
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.sol_amount = escrow.sol_amount.checked_add(amount)
+            .ok_or(EscrowError::MathOverflow)?;
+
+        emit!(SolDeposited {
+            escrow: escrow.key(),
+            depositor: party,
+            amount,
+            total_sol: escrow.sol_amount,
+        });
+
+        Ok(())
+    }
+
+    /// Accept the swap as it currently stands
+    pub fn accept_swap(ctx: Context<AcceptSwap>) -> Result<()> {
+        check_swap_open(&ctx.accounts.escrow, &ctx.accounts.swap)?;
+
+        let party = ctx.accounts.party.key();
+        let swap = &mut ctx.accounts.swap;
+        require!(
+            (!swap.initiator_mints.is_empty() || swap.initiator_sol > 0)
+                && (!swap.counterparty_mints.is_empty() || swap.counterparty_sol > 0),
+            EscrowError::EmptySwapSide
+        );
+
+        if swap.is_initiator(&party)? {
+            swap.initiator_accepted = true;
+        } else {
+            swap.counterparty_accepted = true;
+        }
+
+        emit!(SwapAccepted {
+            escrow: swap.escrow,
+            swap: swap.key(),
+            party,
+            fully_accepted: swap.is_fully_accepted(),
+        });
+
+        Ok(())
+    }
+
+    /// Cancel a swap before both parties accept, returning every deposit to its owner
+    ///
+    /// Remaining accounts: `[escrow_token_account, owner_token_account]` per deposited NFT,
+    /// initiator deposits first, each side in deposit order
+    pub fn cancel_swap<'info>(ctx: Context<'_, '_, 'info, 'info, CancelSwap<'info>>) -> Result<()> {
+        let escrow = &ctx.accounts.escrow;
+        let swap = &ctx.accounts.swap;
+        require!(!escrow.is_released, EscrowError::EscrowAlreadyReleased);
+        require!(!escrow.is_emergency_withdrawn, EscrowError::EscrowEmergencyWithdrawn);
+        require!(!swap.is_canceled, EscrowError::SwapCanceled);
+        require!(!swap.is_fully_accepted(), EscrowError::SwapAlreadyAccepted);
+        swap.is_initiator(&ctx.accounts.party.key())?;
+
+        // Every NFT goes back to the side that deposited it
+        let returns: Vec<(Pubkey, Pubkey)> = swap.initiator_mints.iter()
+            .map(|mint| (*mint, swap.initiator))
+            .chain(swap.counterparty_mints.iter().map(|mint| (*mint, swap.counterparty)))
+            .collect();
+        transfer_swap_nfts(
+            escrow,
+            &ctx.accounts.token_program,
+            ctx.remaining_accounts,
+            &returns,
+        )?;
+
+        let escrow_info = ctx.accounts.escrow.to_account_info();
+        if swap.initiator_sol > 0 {
+            move_lamports(&escrow_info, &ctx.accounts.initiator, swap.initiator_sol)?;
+        }
+        if swap.counterparty_sol > 0 {
+            move_lamports(&escrow_info, &ctx.accounts.counterparty, swap.counterparty_sol)?;
+        }
+
+        let escrow = &mut ctx.accounts.escrow;
+        escrow.sol_amount = 0;
+        let swap = &mut ctx.accounts.swap;
+        swap.is_canceled = true;
+
+        emit!(SwapCanceled {
+            escrow: swap.escrow,
+            swap: swap.key(),
+            canceled_by: ctx.accounts.party.key(),
+        });
+
+        Ok(())
+    }
+}
+
+/// Exchange both sides of a fully accepted swap in one instruction
+///
+/// Remaining accounts: the initiator and counterparty wallets, then
+/// `[escrow_token_account, recipient_token_account]` per deposited NFT, initiator deposits
+/// first, each side in deposit order
+fn release_swap<'info>(ctx: Context<'_, '_, 'info, 'info, ReleaseAssets<'info>>) -> Result<()> {
+    let escrow = &ctx.accounts.escrow;
+    let swap = ctx.accounts.swap.as_ref().ok_or(EscrowError::SwapAccountMissing)?;
+    require!(!escrow.is_released, EscrowError::EscrowAlreadyReleased);
+    require!(!escrow.is_emergency_withdrawn, EscrowError::EscrowEmergencyWithdrawn);
+    require!(!swap.is_canceled, EscrowError::SwapCanceled);
+    require!(swap.is_fully_accepted(), EscrowError::SwapNotAccepted);
+    swap.is_initiator(&ctx.accounts.authority.key())?;
+
+    require!(ctx.remaining_accounts.len() >= 2, EscrowError::InvalidSwapAccounts);
+    let (wallets, items) = ctx.remaining_accounts.split_at(2);
+    let (initiator, counterparty) = (&wallets[0], &wallets[1]);
+    require_keys_eq!(initiator.key(), swap.initiator, EscrowError::InvalidSwapAccounts);
+    require_keys_eq!(counterparty.key(), swap.counterparty, EscrowError::InvalidSwapAccounts);
+    require!(initiator.is_writable && counterparty.is_writable, EscrowError::InvalidSwapAccounts);
+
+    // Each side's NFTs go to the other party
+    let deliveries: Vec<(Pubkey, Pubkey)> = swap.initiator_mints.iter()
+        .map(|mint| (*mint, swap.counterparty))
+        .chain(swap.counterparty_mints.iter().map(|mint| (*mint, swap.initiator)))
+        .collect();
+    transfer_swap_nfts(escrow, &ctx.accounts.token_program, items, &deliveries)?;
+
+    let escrow_info = ctx.accounts.escrow.to_account_info();
+    if swap.initiator_sol > 0 {
+        move_lamports(&escrow_info, counterparty, swap.initiator_sol)?;
+    }
+    if swap.counterparty_sol > 0 {
+        move_lamports(&escrow_info, initiator, swap.counterparty_sol)?;
+    }
+
+    emit!(SwapReleased {
+        escrow: escrow.key(),
+        swap: swap.key(),
+        initiator: swap.initiator,
+        counterparty: swap.counterparty,
+        initiator_mints: swap.initiator_mints.clone(),
+        counterparty_mints: swap.counterparty_mints.clone(),
+        initiator_sol: swap.initiator_sol,
+        counterparty_sol: swap.counterparty_sol,
+    });
+
+    let escrow = &mut ctx.accounts.escrow;
+    escrow.sol_amount = 0;
+    escrow.is_released = true;
+
+    Ok(())
+}
+
+/// Move escrowed swap NFTs to their recipients' ATAs, signed by the escrow PDA
+fn transfer_swap_nfts<'info>(
+    escrow: &Account<'info, EscrowState>,
+    token_program: &Program<'info, Token>,
+    accounts: &[AccountInfo<'info>],
+    transfers: &[(Pubkey, Pubkey)],
+) -> Result<()> {
+    require!(accounts.len() == transfers.len() * 2, EscrowError::InvalidSwapAccounts);
+
+    let escrow_key = escrow.key();
+    let created_at = escrow.created_at.to_le_bytes();
+    let escrow_seeds = &[
+        b"escrow",
+        escrow.authority.as_ref(),
+        &created_at,
+        &[escrow.bump],
+    ];
+    let signer = &[&escrow_seeds[..]];
+
+    for ((mint, recipient), pair) in transfers.iter().zip(accounts.chunks(2)) {
+        let (escrow_token_account, recipient_token_account) = (&pair[0], &pair[1]);
+        require_keys_eq!(
+            escrow_token_account.key(),
+            get_associated_token_address(&escrow_key, mint),
+            EscrowError::InvalidSwapAccounts
+        );
+        require_keys_eq!(
+            recipient_token_account.key(),
+            get_associated_token_address(recipient, mint),
+            EscrowError::InvalidSwapAccounts
+        );
+
+        let nft_transfer_ctx = CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            Transfer {
+                from: escrow_token_account.clone(),
+                to: recipient_token_account.clone(),
+                authority: escrow.to_account_info(),
+            },
+            signer,
+        );
+        token::transfer(nft_transfer_ctx, 1)?;
+    }
+
+    Ok(())
+}
+
+/// Move every escrowed NFT beyond `escrow.nft_mint` to `recipient`, signed by the escrow PDA
+///
+/// `accounts` holds `[escrow_token_account, recipient_token_account]` pairs; each escrow token
+/// account must still hold a distinct NFT so that none is left behind
+fn transfer_additional_nfts<'info>(
+    escrow: &Account<'info, EscrowState>,
+    token_program: &Program<'info, Token>,
+    accounts: &'info [AccountInfo<'info>],
+    recipient: &Pubkey,
+) -> Result<()> {
+    let additional = escrow.nft_count.saturating_sub(1) as usize;
+    require!(accounts.len() == additional * 2, EscrowError::InvalidDepositAccounts);
+
+    let escrow_key = escrow.key();
+    let created_at = escrow.created_at.to_le_bytes();
+    let escrow_seeds = &[
+        b"escrow",
+        escrow.authority.as_ref(),
+        &created_at,
+        &[escrow.bump],
+    ];
+    let signer = &[&escrow_seeds[..]];
+
+    let mut moved: Vec<Pubkey> = Vec::with_capacity(additional);
+    for pair in accounts.chunks(2) {
+        let escrow_token_account = Account::<TokenAccount>::try_from(&pair[0])?;
+        let recipient_token_account = Account::<TokenAccount>::try_from(&pair[1])?;
+        let mint = escrow_token_account.mint;
+        require!(
+            escrow.nft_mint != Some(mint) && !moved.contains(&mint),
+            EscrowError::InvalidDepositAccounts
+        );
+        require!(
+            escrow_token_account.key() == get_associated_token_address(&escrow_key, &mint)
+                && escrow_token_account.amount == 1,
+            EscrowError::InvalidDepositAccounts
+        );
+        require!(
+            recipient_token_account.owner == *recipient && recipient_token_account.mint == mint,
+            EscrowError::InvalidRecipient
+        );
+
+        let nft_transfer_ctx = CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            Transfer {
+                from: pair[0].clone(),
+                to: pair[1].clone(),
+                authority: escrow.to_account_info(),
+            },
+            signer,
+        );
+        token::transfer(nft_transfer_ctx, 1)?;
+        moved.push(mint);
+    }
+
+    Ok(())
+}
+
+/// A direct sale releases only once both sides are in, and only along the recorded routes:
+/// the NFT to the buyer and the payment to the seller
+fn check_direct_sale_release(
+    escrow: &Account<EscrowState>,
+    signer: &Pubkey,
+    escrow_token_account: Option<&TokenAccount>,
+    recipient_token_account: Option<&TokenAccount>,
+    sol_recipient: &Pubkey,
+) -> Result<()> {
+    let buyer = escrow.buyer.ok_or(EscrowError::DirectSaleTermsRequired)?;
+    require!(
+        *signer == escrow.authority || *signer == buyer,
+        EscrowError::Unauthorized
+    );
+
+    let nft_mint = escrow.nft_mint.ok_or(EscrowError::DirectSaleIncomplete)?;
+    require!(escrow.sol_amount == escrow.price, EscrowError::DirectSaleIncomplete);
+
+    let escrow_token_account = escrow_token_account.ok_or(EscrowError::TokenAccountMissing)?;
+    let recipient_token_account = recipient_token_account.ok_or(EscrowError::TokenAccountMissing)?;
+    require!(
+        escrow_token_account.owner == escrow.key() && escrow_token_account.mint == nft_mint,
+        EscrowError::InvalidRecipient
+    );
+    require!(
+        recipient_token_account.owner == buyer && recipient_token_account.mint == nft_mint,
+        EscrowError::InvalidRecipient
+    );
+    require_keys_eq!(*sol_recipient, escrow.authority, EscrowError::InvalidRecipient);
+
+    Ok(())
+}
+
+/// Move lamports out of the program-owned escrow account
+fn move_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
+    let remaining = from.lamports().checked_sub(amount)
+        .ok_or(EscrowError::MathOverflow)?;
+    let received = to.lamports().checked_add(amount)
+        .ok_or(EscrowError::MathOverflow)?;
+    **from.lamports.borrow_mut() = remaining;
+    **to.lamports.borrow_mut() = received;
+    Ok(())
+}
+
+/// Swaps only take deposits and acceptances while nothing has been settled
+fn check_swap_open(escrow: &EscrowState, swap: &SwapState) -> Result<()> {
+    require!(!escrow.is_released, EscrowError::EscrowAlreadyReleased);
+    require!(!escrow.is_emergency_withdrawn, EscrowError::EscrowEmergencyWithdrawn);
+    require!(!swap.is_canceled, EscrowError::SwapCanceled);
+
+    if let Some(expires_at) = escrow.expires_at {
+        let clock = Clock::get()?;
+        require!(clock.unix_timestamp < expires_at, EscrowError::EscrowExpired);
+    }
+
+    Ok(())
+}
+
+#[derive(Accounts)]
+pub struct CreateEscrow<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + EscrowState::INIT_SPACE,
+        seeds = [b"escrow", authority.key().as_ref(), &Clock::get()?.unix_timestamp.to_le_bytes()],
+        bump
+    )]
+    pub escrow: Account<'info, EscrowState>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+// Fixed: Added #[derive(Accounts)] macro
+#[derive(Accounts)]
+pub struct DepositNft<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.authority.as_ref(), &escrow.created_at.to_le_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, EscrowState>,
+    
+    // Removed has_one constraint since depositor might be different from authority
+    pub authority: Signer<'info>,
+    
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = depositor,
+        constraint = depositor_token_account.amount == 1 @ EscrowError::InvalidAmount
+    )]
+    pub depositor_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        init_if_needed,
+        payer = depositor,
+        associated_token::mint = mint,
+        associated_token::authority = escrow
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        init_if_needed,
+        payer = depositor,
+        space = 8 + DepositRecord::INIT_SPACE,
+        seeds = [b"deposit", escrow.key().as_ref(), depositor.key().as_ref()],
+        bump
+    )]
+    pub deposit_record: Account<'info, DepositRecord>,
+    
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositSol<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.authority.as_ref(), &escrow.created_at.to_le_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, EscrowState>,
+    
+    pub authority: Signer<'info>,
+    
+    #[account(mut)]
+    pub depositor: Signer<'info>,
+    
+    #[account(
+        init_if_needed,
+        payer = depositor,
+        space = 8 + DepositRecord::INIT_SPACE,
+        seeds = [b"deposit", escrow.key().as_ref(), depositor.key().as_ref()],
+        bump
+    )]
+    pub deposit_record: Account<'info, DepositRecord>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ReleaseAssets<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.authority.as_ref(), &escrow.created_at.to_le_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, EscrowState>,
+    
+    pub authority: Signer<'info>,
+    
+    // Required when an NFT was deposited; swaps pass their NFT accounts as remaining accounts
+    #[account(mut)]
+    pub escrow_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub recipient_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        seeds = [b"swap", escrow.key().as_ref()],
+        bump = swap.bump
+    )]
+    pub swap: Option<Account<'info, SwapState>>,
+    
+    /// CHECK: SOL recipient account
+    #[account(mut)]
+    pub sol_recipient: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [MARKETPLACE_SEED],
+        bump = marketplace.bump,
+        seeds::program = marketplace::ID
+    )]
+    pub marketplace: Account<'info, MarketplaceState>,
+    
+    /// CHECK: PDA signer proving the stats update comes from this program
+    #[account(
+        seeds = [SALE_AUTHORITY_SEED],
+        bump
+    )]
+    pub sale_authority: UncheckedAccount<'info>,
+    
+    pub escrow_program: Program<'info, crate::program::Escrow>,
+    pub marketplace_program: Program<'info, Marketplace>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct EmergencyWithdraw<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.authority.as_ref(), &escrow.created_at.to_le_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, EscrowState>,
+    
+    pub admin: Signer<'info>,
+    
+    #[account(
+        seeds = [MARKETPLACE_SEED],
+        bump = marketplace.bump,
+        seeds::program = marketplace::ID
+    )]
+    pub marketplace: Account<'info, MarketplaceState>,
+    
+    #[account(mut)]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+    
+    #[account(mut)]
+    pub recovery_token_account: Account<'info, TokenAccount>,
+    
+    /// CHECK: Recovery SOL account
+    #[account(mut)]
+    pub recovery_sol_account: AccountInfo<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetArbiter<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.authority.as_ref(), &escrow.created_at.to_le_bytes()],
+        bump = escrow.bump,
+        has_one = authority
+    )]
+    pub escrow: Account<'info, EscrowState>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetReleaseConditions<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.authority.as_ref(), &escrow.created_at.to_le_bytes()],
+        bump = escrow.bump,
+        has_one = authority
+    )]
+    pub escrow: Account<'info, EscrowState>,
+    
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ApproveRelease<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.authority.as_ref(), &escrow.created_at.to_le_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, EscrowState>,
+    
+    pub approver: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RaiseDispute<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.authority.as_ref(), &escrow.created_at.to_le_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, EscrowState>,
+    
+    pub party: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ResolveDispute<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.authority.as_ref(), &escrow.created_at.to_le_bytes()],
+        bump = escrow.bump,
+        constraint = escrow.arbiter == Some(arbiter.key()) @ EscrowError::Unauthorized
+    )]
+    pub escrow: Account<'info, EscrowState>,
+    
+    pub arbiter: Signer<'info>,
+    
+    /// CHECK: Seller (escrow authority) receiving its share of the SOL
+    #[account(
+        mut,
+        constraint = seller.key() == escrow.authority @ EscrowError::InvalidRecipient
+    )]
+    pub seller: AccountInfo<'info>,
+    
+    /// CHECK: Designated buyer receiving its share of the SOL
+    #[account(
+        mut,
+        constraint = escrow.buyer == Some(buyer.key()) @ EscrowError::InvalidRecipient
+    )]
+    pub buyer: AccountInfo<'info>,
+    
+    // Required when an NFT was deposited
+    #[account(mut)]
+    pub escrow_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(mut)]
+    pub recipient_token_account: Option<Account<'info, TokenAccount>>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RefundExpired<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.authority.as_ref(), &escrow.created_at.to_le_bytes()],
+        bump = escrow.bump,
+        close = authority
+    )]
+    pub escrow: Account<'info, EscrowState>,
+    
+    /// CHECK: Escrow creator receiving the escrow account's rent
+    #[account(
+        mut,
+        constraint = authority.key() == escrow.authority @ EscrowError::InvalidRecipient
+    )]
+    pub authority: AccountInfo<'info>,
+    
+    // Anyone can trigger the refund once the escrow has expired
+    pub caller: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct CreateVestingSchedule<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.authority.as_ref(), &escrow.created_at.to_le_bytes()],
+        bump = escrow.bump,
+        has_one = authority
+    )]
+    pub escrow: Account<'info, EscrowState>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + VestingSchedule::INIT_SPACE,
+        seeds = [b"vesting", escrow.key().as_ref()],
+        bump
+    )]
+    pub schedule: Account<'info, VestingSchedule>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimVested<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.authority.as_ref(), &escrow.created_at.to_le_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, EscrowState>,
+    
+    #[account(
+        mut,
+        seeds = [b"vesting", escrow.key().as_ref()],
+        bump = schedule.bump,
+        has_one = beneficiary
+    )]
+    pub schedule: Account<'info, VestingSchedule>,
+    
+    /// CHECK: Beneficiary receiving the vested SOL, validated against the schedule
+    #[account(mut)]
+    pub beneficiary: AccountInfo<'info>,
+    
+    // Anyone can push vested SOL to the beneficiary
+    pub caller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeVesting<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.authority.as_ref(), &escrow.created_at.to_le_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, EscrowState>,
+    
+    #[account(
+        mut,
+        seeds = [b"vesting", escrow.key().as_ref()],
+        bump = schedule.bump,
+        has_one = creator
+    )]
+    pub schedule: Account<'info, VestingSchedule>,
+    
+    #[account(mut)]
+    pub creator: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseEscrow<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.authority.as_ref(), &escrow.created_at.to_le_bytes()],
+        bump = escrow.bump,
+        has_one = authority,
+        close = authority
+    )]
+    pub escrow: Account<'info, EscrowState>,
+    
+    /// CHECK: Escrow creator receiving the rent of the escrow, swap and schedule accounts
+    #[account(mut)]
+    pub authority: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        seeds = [b"swap", escrow.key().as_ref()],
+        bump = swap.bump,
+        close = authority
+    )]
+    pub swap: Option<Account<'info, SwapState>>,
+    
+    #[account(
+        mut,
+        seeds = [b"vesting", escrow.key().as_ref()],
+        bump = schedule.bump,
+        close = authority
+    )]
+    pub schedule: Option<Account<'info, VestingSchedule>>,
+    
+    // Anyone can clean up a finished escrow
+    pub caller: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct GetEscrowStatus<'info> {
+    #[account(
+        seeds = [b"escrow", escrow.authority.as_ref(), &escrow.created_at.to_le_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, EscrowState>,
+}
+
+#[derive(Accounts)]
+pub struct CreateSwap<'info> {
+    #[account(
+        seeds = [b"escrow", escrow.authority.as_ref(), &escrow.created_at.to_le_bytes()],
+        bump = escrow.bump,
+        has_one = authority
+    )]
+    pub escrow: Account<'info, EscrowState>,
+    
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + SwapState::INIT_SPACE,
+        seeds = [b"swap", escrow.key().as_ref()],
+        bump
+    )]
+    pub swap: Account<'info, SwapState>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositSwapNft<'info> {
+    #[account(
+        seeds = [b"escrow", escrow.authority.as_ref(), &escrow.created_at.to_le_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, EscrowState>,
+    
+    #[account(
+        mut,
+        seeds = [b"swap", escrow.key().as_ref()],
+        bump = swap.bump
+    )]
+    pub swap: Account<'info, SwapState>,
+    
+    #[account(mut)]
+    pub party: Signer<'info>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = party,
+        constraint = party_token_account.amount == 1 @ EscrowError::InvalidAmount
+    )]
+    pub party_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        init_if_needed,
+        payer = party,
+        associated_token::mint = mint,
+        associated_token::authority = escrow
+    )]
+    pub escrow_token_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct DepositSwapSol<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.authority.as_ref(), &escrow.created_at.to_le_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, EscrowState>,
+    
+    #[account(
+        mut,
+        seeds = [b"swap", escrow.key().as_ref()],
+        bump = swap.bump
+    )]
+    pub swap: Account<'info, SwapState>,
+    
+    #[account(mut)]
+    pub party: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptSwap<'info> {
+    #[account(
+        seeds = [b"escrow", escrow.authority.as_ref(), &escrow.created_at.to_le_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, EscrowState>,
+    
+    #[account(
+        mut,
+        seeds = [b"swap", escrow.key().as_ref()],
+        bump = swap.bump
+    )]
+    pub swap: Account<'info, SwapState>,
+    
+    pub party: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelSwap<'info> {
+    #[account(
+        mut,
+        seeds = [b"escrow", escrow.authority.as_ref(), &escrow.created_at.to_le_bytes()],
+        bump = escrow.bump
+    )]
+    pub escrow: Account<'info, EscrowState>,
+    
+    #[account(
+        mut,
+        seeds = [b"swap", escrow.key().as_ref()],
+        bump = swap.bump,
+        has_one = initiator,
+        has_one = counterparty
+    )]
+    pub swap: Account<'info, SwapState>,
+    
+    pub party: Signer<'info>,
+    
+    /// CHECK: Initiator wallet receiving its SOL deposit back
+    #[account(mut)]
+    pub initiator: AccountInfo<'info>,
+    
+    /// CHECK: Counterparty wallet receiving its SOL deposit back
+    #[account(mut)]
+    pub counterparty: AccountInfo<'info>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct EscrowState {
+    pub authority: Pubkey,              // 32 (seller for direct sales)
+    pub buyer: Option<Pubkey>,          // 1 + 32 (designated direct sale buyer)
+    pub arbiter: Option<Pubkey>,        // 1 + 32 (settles disputes)
+    pub escrow_type: EscrowType,        // 1 + size
+    pub created_at: i64,                // 8
+    pub expires_at: Option<i64>,        // 1 + 8
+    pub nft_mint: Option<Pubkey>,       // 1 + 32 (first NFT deposited)
+    pub nft_count: u32,                 // 4
+    pub sol_amount: u64,                // 8
+    pub depositor_count: u32,           // 4 (deposit records)
+    pub price: u64,                     // 8 (direct sale price in lamports)
+    pub is_released: bool,              // 1
+    pub is_emergency_withdrawn: bool,   // 1
+    pub is_disputed: bool,              // 1
+    pub release_logic: ConditionLogic,  // 1
+    #[max_len(MAX_RELEASE_CONDITIONS)]
+    pub release_conditions: Vec<ReleaseCondition>, // 4 + (1 + 32) * MAX_RELEASE_CONDITIONS
+    #[max_len(MAX_APPROVERS)]
+    pub approvers: Vec<Pubkey>,         // 4 + 32 * MAX_APPROVERS
+    pub approvals: u8,                  // 1 (bitmask over approvers)
+    pub bump: u8,                       // 1
+}
+
+impl EscrowState {
+    pub const INIT_SPACE: usize = 32 + 1 + 32 + 1 + 32 + 1 + 1 + 8 + 1 + 8 + 1 + 32 + 4 + 8 + 4 + 8 + 1 + 1 + 1
+        + 1
+        + 4 + (1 + 32) * MAX_RELEASE_CONDITIONS
+        + 4 + 32 * MAX_APPROVERS
+        + 1 + 1; // 480 bytes
+
+    /// Number of listing/auction accounts release has to be given for evaluation
+    pub fn settlement_condition_count(&self) -> usize {
+        self.release_conditions.iter()
+            .filter(|condition| matches!(
+                condition,
+                ReleaseCondition::ListingSettled { .. } | ReleaseCondition::AuctionSettled { .. }
+            ))
+            .count()
+    }
+
+    /// Evaluate the release conditions, reading settlement accounts in condition order
+    pub fn release_conditions_met<'info>(
+        &self,
+        now: i64,
+        accounts: &'info [AccountInfo<'info>],
+    ) -> Result<bool> {
+        if self.release_conditions.is_empty() {
+            return Ok(true);
+        }
+
+        let mut accounts = accounts.iter();
+        let mut results = Vec::with_capacity(self.release_conditions.len());
+        for condition in &self.release_conditions {
+            let met = match condition {
+                ReleaseCondition::AfterTimestamp { timestamp } => now >= *timestamp,
+                ReleaseCondition::Approvals { threshold } => {
+                    self.approvals.count_ones() >= *threshold as u32
+                }
+                ReleaseCondition::ListingSettled { listing } => {
+                    let info = accounts.next().ok_or(EscrowError::ConditionAccountMissing)?;
+                    require_keys_eq!(info.key(), *listing, EscrowError::ConditionAccountMissing);
+                    // Relisting clears the buyer but keeps the sale time, so the listing counts as
+                    // settled once it sold after this escrow was created
+                    info.owner == &listing::ID && {
+                        let last_sold_at = Account::<ListingState>::try_from(info)?.last_sold_at;
+                        last_sold_at > 0 && last_sold_at >= self.created_at
+                    }
+                }
+                ReleaseCondition::AuctionSettled { auction } => {
+                    let info = accounts.next().ok_or(EscrowError::ConditionAccountMissing)?;
+                    require_keys_eq!(info.key(), *auction, EscrowError::ConditionAccountMissing);
+                    // Settling without a sale, or canceling, never records a winner
+                    info.owner == &auction::ID
+                        && Account::<AuctionState>::try_from(info)?.winner.is_some()
+                }
+            };
+            results.push(met);
+        }
+
+        Ok(match self.release_logic {
+            ConditionLogic::All => results.iter().all(|met| *met),
+            ConditionLogic::Any => results.iter().any(|met| *met),
+        })
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct DepositRecord {
+    pub escrow: Pubkey,                 // 32
+    pub depositor: Pubkey,              // 32
+    pub sol_amount: u64,                // 8
+    #[max_len(MAX_DEPOSITOR_NFTS)]
+    pub mints: Vec<Pubkey>,             // 4 + 32 * MAX_DEPOSITOR_NFTS
+    pub bump: u8,                       // 1
+}
+
+impl DepositRecord {
+    pub const INIT_SPACE: usize = 32 + 32 + 8 + 4 + 32 * MAX_DEPOSITOR_NFTS + 1; // 205 bytes
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct SwapState {
+    pub escrow: Pubkey,                 // 32
+    pub initiator: Pubkey,              // 32 (escrow authority)
+    pub counterparty: Pubkey,           // 32
+    #[max_len(MAX_SWAP_ITEMS)]
+    pub initiator_mints: Vec<Pubkey>,   // 4 + 32 * MAX_SWAP_ITEMS
+    #[max_len(MAX_SWAP_ITEMS)]
+    pub counterparty_mints: Vec<Pubkey>, // 4 + 32 * MAX_SWAP_ITEMS
+    pub initiator_sol: u64,             // 8
+    pub counterparty_sol: u64,          // 8
+    pub initiator_accepted: bool,       // 1
+    pub counterparty_accepted: bool,    // 1
+    pub is_canceled: bool,              // 1
+    pub bump: u8,                       // 1
+}
+
+impl SwapState {
+    pub const INIT_SPACE: usize = 32 + 32 + 32
+        + 4 + 32 * MAX_SWAP_ITEMS
+        + 4 + 32 * MAX_SWAP_ITEMS
+        + 8 + 8 + 1 + 1 + 1 + 1; // 380 bytes
+
+    /// Which side `party` is on, failing for anyone outside the swap
+    pub fn is_initiator(&self, party: &Pubkey) -> Result<bool> {
+        if *party == self.initiator {
+            Ok(true)
+        } else if *party == self.counterparty {
+            Ok(false)
+        } else {
+            err!(EscrowError::NotSwapParty)
+        }
+    }
+
+    pub fn is_fully_accepted(&self) -> bool {
+        self.initiator_accepted && self.counterparty_accepted
+    }
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct VestingSchedule {
+    pub escrow: Pubkey,                 // 32
+    pub creator: Pubkey,                // 32
+    pub beneficiary: Pubkey,            // 32
+    pub kind: VestingKind,              // 1
+    pub total_amount: u64,              // 8
+    pub claimed_amount: u64,            // 8
+    pub start_time: i64,                // 8
+    pub cliff_time: i64,                // 8
+    pub end_time: i64,                  // 8
+    pub is_revoked: bool,               // 1
+    pub bump: u8,                       // 1
+}
+
+impl VestingSchedule {
+    pub const INIT_SPACE: usize = 32 + 32 + 32 + 1 + 8 + 8 + 8 + 8 + 8 + 1 + 1; // 139 bytes
+
+    /// Total unlocked at `now`, claimed or not
+    pub fn vested_amount(&self, now: i64) -> Result<u64> {
+        // A revoked schedule's total was frozen at what had vested
+        if self.is_revoked || now >= self.end_time {
+            return Ok(self.total_amount);
+        }
+        if now < self.cliff_time {
+            return Ok(0);
+        }
+
+        match self.kind {
+            VestingKind::Cliff => Ok(self.total_amount),
+            VestingKind::Linear => {
+                let vested = (self.total_amount as u128)
+                    .checked_mul((now - self.start_time) as u128)
+                    .ok_or(EscrowError::MathOverflow)?
+                    .checked_div((self.end_time - self.start_time) as u128)
+                    .ok_or(EscrowError::MathOverflow)?;
+                u64::try_from(vested).map_err(|_| EscrowError::MathOverflow.into())
+            }
+        }
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum VestingKind {
+    /// Unlocks pro rata from `start_time` to `end_time`, nothing before `cliff_time`
+    Linear,
+    /// Unlocks everything at `cliff_time`
+    Cliff,
+}
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
 pub enum EscrowType {
@@ -458,6 +2222,7 @@ pub enum EscrowType {
     Auction,
     DirectSale,
     Swap,
+    Vesting,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
@@ -466,6 +2231,30 @@ pub enum EscrowStatus {
     Expired,
     Released,
     EmergencyWithdrawn,
+    Disputed,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum ConditionLogic {
+    All,
+    Any,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq, InitSpace)]
+pub enum ReleaseCondition {
+    AfterTimestamp { timestamp: i64 },
+    Approvals { threshold: u8 },
+    /// Met once the listing sells after the escrow was created, relisted or not, and only while
+    /// the listing account stays open
+    ListingSettled { listing: Pubkey },
+    /// Met once the auction sold the NFT, and only until the seller closes the auction
+    AuctionSettled { auction: Pubkey },
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DisputeParty {
+    Seller,
+    Buyer,
 }
 
 #[event]
@@ -477,6 +2266,99 @@ pub struct EscrowCreated {
     pub expires_at: Option<i64>,
 }
 
+#[event]
+pub struct DirectSaleCreated {
+    pub escrow: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub price: u64,
+}
+
+#[event]
+pub struct ReleaseConditionsSet {
+    pub escrow: Pubkey,
+    pub logic: ConditionLogic,
+    pub conditions: Vec<ReleaseCondition>,
+    pub approvers: Vec<Pubkey>,
+}
+
+#[event]
+pub struct ReleaseApproved {
+    pub escrow: Pubkey,
+    pub approver: Pubkey,
+    pub approvals: u8,
+}
+
+#[event]
+pub struct VestingScheduleCreated {
+    pub escrow: Pubkey,
+    pub schedule: Pubkey,
+    pub creator: Pubkey,
+    pub beneficiary: Pubkey,
+    pub kind: VestingKind,
+    pub total_amount: u64,
+    pub start_time: i64,
+    pub cliff_time: i64,
+    pub end_time: i64,
+}
+
+#[event]
+pub struct VestedClaimed {
+    pub escrow: Pubkey,
+    pub schedule: Pubkey,
+    pub beneficiary: Pubkey,
+    pub amount: u64,
+    pub total_claimed: u64,
+}
+
+#[event]
+pub struct VestingRevoked {
+    pub escrow: Pubkey,
+    pub schedule: Pubkey,
+    pub creator: Pubkey,
+    pub vested_amount: u64,
+    pub returned_amount: u64,
+}
+
+#[event]
+pub struct ArbiterSet {
+    pub escrow: Pubkey,
+    pub arbiter: Option<Pubkey>,
+}
+
+#[event]
+pub struct DisputeRaised {
+    pub escrow: Pubkey,
+    pub raised_by: Pubkey,
+}
+
+#[event]
+pub struct DisputeResolved {
+    pub escrow: Pubkey,
+    pub arbiter: Pubkey,
+    pub nft_mint: Option<Pubkey>,
+    pub nft_count: u32,
+    pub nft_recipient: Pubkey,
+    pub seller_sol_amount: u64,
+    pub buyer_sol_amount: u64,
+}
+
+#[event]
+pub struct ExpiredEscrowRefunded {
+    pub escrow: Pubkey,
+    pub nfts_returned: u32,
+    pub sol_refunded: u64,
+    pub depositors: Vec<Pubkey>,
+}
+
+#[event]
+pub struct EscrowClosed {
+    pub escrow: Pubkey,
+    pub authority: Pubkey,
+    pub depositors: u32,
+    pub token_accounts_closed: u32,
+}
+
 #[event]
 pub struct NftDeposited {
     pub escrow: Pubkey,
@@ -497,6 +2379,7 @@ pub struct AssetsReleased {
     pub escrow: Pubkey,
     pub authority: Pubkey,
     pub nft_mint: Option<Pubkey>,
+    pub nft_count: u32,
     pub sol_amount: u64,
     pub nft_recipient: Pubkey,
     pub sol_recipient: Pubkey,
@@ -507,10 +2390,46 @@ pub struct EmergencyWithdrawal {
     pub escrow: Pubkey,
     pub admin: Pubkey,
     pub nft_mint: Option<Pubkey>,
+    pub nft_count: u32,
     pub sol_amount: u64,
     pub recovery_account: Pubkey,
 }
 
+#[event]
+pub struct SwapCreated {
+    pub escrow: Pubkey,
+    pub swap: Pubkey,
+    pub initiator: Pubkey,
+    pub counterparty: Pubkey,
+}
+
+#[event]
+pub struct SwapAccepted {
+    pub escrow: Pubkey,
+    pub swap: Pubkey,
+    pub party: Pubkey,
+    pub fully_accepted: bool,
+}
+
+#[event]
+pub struct SwapCanceled {
+    pub escrow: Pubkey,
+    pub swap: Pubkey,
+    pub canceled_by: Pubkey,
+}
+
+#[event]
+pub struct SwapReleased {
+    pub escrow: Pubkey,
+    pub swap: Pubkey,
+    pub initiator: Pubkey,
+    pub counterparty: Pubkey,
+    pub initiator_mints: Vec<Pubkey>,
+    pub counterparty_mints: Vec<Pubkey>,
+    pub initiator_sol: u64,
+    pub counterparty_sol: u64,
+}
+
 #[error_code]
 pub enum EscrowError {
     #[msg("Escrow already released")]
@@ -529,21 +2448,76 @@ pub enum EscrowError {
     MathOverflow,
     #[msg("Unauthorized access")]
     Unauthorized,
+    #[msg("Escrow and recipient token accounts are required to release an NFT")]
+    TokenAccountMissing,
+    #[msg("Swap escrows take deposits through the swap instructions")]
+    SwapEscrow,
+    #[msg("Escrow is not a swap escrow")]
+    NotSwapEscrow,
+    #[msg("Swap account is required to release a swap escrow")]
+    SwapAccountMissing,
+    #[msg("Vesting schedule account is required to close a vesting escrow")]
+    VestingScheduleMissing,
+    #[msg("Counterparty cannot be the swap initiator")]
+    InvalidCounterparty,
+    #[msg("Signer is not a party to this swap")]
+    NotSwapParty,
+    #[msg("Each side of a swap is limited to MAX_SWAP_ITEMS NFTs")]
+    TooManySwapItems,
+    #[msg("Both sides must deposit something before accepting")]
+    EmptySwapSide,
+    #[msg("Swap has not been accepted by both parties")]
+    SwapNotAccepted,
+    #[msg("Swap was accepted by both parties and can no longer be canceled")]
+    SwapAlreadyAccepted,
+    #[msg("Swap has been canceled")]
+    SwapCanceled,
+    #[msg("Swap item accounts are missing or do not match the swap")]
+    InvalidSwapAccounts,
+    #[msg("Direct sale escrows must be created with a buyer and price")]
+    DirectSaleTermsRequired,
+    #[msg("Deposit would exceed the direct sale price")]
+    ExceedsPrice,
+    #[msg("Direct sale needs the seller's NFT and the buyer's full payment")]
+    DirectSaleIncomplete,
+    #[msg("Release accounts do not match the escrow's recorded parties")]
+    InvalidRecipient,
+    #[msg("Escrow is under dispute")]
+    EscrowDisputed,
+    #[msg("Escrow is not under dispute")]
+    NoDispute,
+    #[msg("Escrow has no arbiter")]
+    NoArbiter,
+    #[msg("Arbiter cannot be a party to the escrow")]
+    InvalidArbiter,
+    #[msg("Disputes are only supported on direct sales")]
+    NotDirectSale,
+    #[msg("Arbiter can only be changed before any deposit")]
+    EscrowNotEmpty,
+    #[msg("Escrow has not expired")]
+    EscrowNotExpired,
+    #[msg("Deposit record accounts are missing or do not match the escrow")]
+    InvalidDepositAccounts,
+    #[msg("Depositor already holds MAX_DEPOSITOR_NFTS NFTs in this escrow")]
+    TooManyDepositorNfts,
+    #[msg("Invalid release conditions or approvers")]
+    InvalidReleaseConditions,
+    #[msg("Signer is not a named approver")]
+    NotApprover,
+    #[msg("Escrow release conditions are not met")]
+    ReleaseConditionsNotMet,
+    #[msg("Listing or auction account for a release condition is missing")]
+    ConditionAccountMissing,
+    #[msg("Vesting escrows pay out through their vesting schedule")]
+    VestingEscrow,
+    #[msg("Escrow is not a vesting escrow")]
+    NotVestingEscrow,
+    #[msg("Vesting schedule times must satisfy start <= cliff <= end and start < end")]
+    InvalidVestingSchedule,
+    #[msg("Nothing has vested since the last claim")]
+    NothingVested,
+    #[msg("Vesting schedule has already been revoked")]
+    VestingRevoked,
+    #[msg("Escrow has not been released, withdrawn or canceled")]
+    EscrowNotFinished,
 }
-
-// External module reference for CPI
-pub mod marketplace {
-    use super::*;
-    
-    #[account]
-    #[derive(InitSpace)]
-    pub struct MarketplaceState {
-        pub authority: Pubkey,
-        pub treasury: Pubkey,
-        pub platform_fee: u16,
-        pub total_volume: u64,
-        pub total_trades: u64,
-        pub is_paused: bool,
-        pub bump: u8,
-    }
-}
\ No newline at end of file
@@ -1,66 +1,16 @@
 #![allow(unexpected_cfgs)]
 #![allow(deprecated)]
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer, Mint};
-use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, CloseAccount, Token, TokenAccount, Transfer, Mint};
+use anchor_spl::associated_token::{self, get_associated_token_address, AssociatedToken, Create};
+use marketplace::program::Marketplace;
+use marketplace::{check_marketplace_active, MarketplaceState, MARKETPLACE_SEED, SALE_AUTHORITY_SEED};
+use royalty::{calculate_creator_royalties, load_metadata, total_royalties, CreatorRoyalty, RoyaltyConfig};
 
-declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
+declare_id!("AtGsfS1vsVj1jdBrtRCrVj4Xe8Mzm1nCCb3v5niWGNVa");
 
-// Import the marketplace module properly
-pub mod marketplace {
-    use super::*;
-    
-    #[account]
-    #[derive(InitSpace)]
-    pub struct MarketplaceState {
-        pub authority: Pubkey,
-        pub treasury: Pubkey,
-        pub fee_basis_points: u16,
-        pub is_paused: bool,
-        pub total_sales: u64,
-        pub total_volume: u64,
-        pub bump: u8,
-    }
-    
-    impl MarketplaceState {
-        pub fn calculate_platform_fee(&self, sale_price: u64) -> Result<u64> {
-            let fee = (sale_price as u128)
-                .checked_mul(self.fee_basis_points as u128)
-                .ok_or(crate::ListingError::MathOverflow)?
-                .checked_div(10000)
-                .ok_or(crate::ListingError::MathOverflow)? as u64;
-            Ok(fee)
-        }
-    }
-    
-    pub mod cpi {
-        use super::*;
-        
-        pub mod accounts {
-            use super::*;
-            
-            #[derive(Accounts)]
-            pub struct UpdateStats<'info> {
-                #[account(mut)]
-                pub marketplace: Account<'info, super::MarketplaceState>,
-            }
-        }
-        
-        pub fn update_stats<'info>(
-            mut ctx: CpiContext<'_, '_, '_, 'info, accounts::UpdateStats<'info>>, 
-            sale_amount: u64
-        ) -> Result<()> {
-            let marketplace = &mut ctx.accounts.marketplace;
-            marketplace.total_sales = marketplace.total_sales.checked_add(1)
-                .ok_or(crate::ListingError::MathOverflow)?;
-            marketplace.total_volume = marketplace.total_volume.checked_add(sale_amount)
-                .ok_or(crate::ListingError::MathOverflow)?;
-            Ok(())
-        }
-    }
-}
-
-// Declare the marketplace program ID separately
+/// Maximum number of NFTs sold together in one bundle listing
+pub const MAX_BUNDLE_SIZE: usize = 8;
 
 #[program]
 pub mod listing {
@@ -71,10 +21,21 @@ pub mod listing {
         ctx: Context<ListNft>,
         price: u64,
         expiry: Option<i64>,
+        payment_mint: Option<Pubkey>,
     ) -> Result<()> {
         // Validate marketplace is active
-        require!(!ctx.accounts.marketplace.is_paused, ListingError::MarketplacePaused);
+        check_marketplace_active(&ctx.accounts.marketplace)?;
         require!(price > 0, ListingError::InvalidPrice);
+        // A sold listing left open is reused, but a live one is never overwritten
+        require!(!ctx.accounts.listing.is_active, ListingError::ListingStillActive);
+        
+        // SPL-priced listings must use an allowlisted payment mint
+        if let Some(payment_mint) = payment_mint {
+            require!(
+                ctx.accounts.marketplace.is_accepted_payment_mint(&payment_mint),
+                ListingError::PaymentMintNotAccepted
+            );
+        }
         
         // Validate expiry if provided
         if let Some(expiry_time) = expiry {
@@ -98,22 +59,77 @@ pub mod listing {
         listing.seller = ctx.accounts.seller.key();
         listing.mint = ctx.accounts.mint.key();
         listing.price = price;
+        listing.payment_mint = payment_mint;
         listing.created_at = Clock::get()?.unix_timestamp;
         listing.expiry = expiry;
         listing.is_active = true;
         listing.bump = ctx.bumps.listing;
+        listing.buyer = None;
 
         emit!(NftListed {
             listing: listing.key(),
             seller: listing.seller,
             mint: listing.mint,
             price,
+            payment_mint,
             expiry,
         });
 
         Ok(())
     }
 
+    /// Grow a listing created under an older `ListingState` layout to the current one
+    /// (permissionless, the caller pays the extra rent)
+    ///
+    /// Appended fields are zero-filled, which decodes as `None`, so a migrated listing stays
+    /// priced in lamports
+    pub fn migrate_listing(ctx: Context<MigrateListing>) -> Result<()> {
+        let listing = ctx.accounts.listing.to_account_info();
+        let old_len = listing.data_len();
+        let new_len = 8 + ListingState::INIT_SPACE;
+        require!(old_len < new_len, ListingError::ListingAlreadyMigrated);
+        require!(
+            old_len >= 8 && listing.try_borrow_data()?[..8] == <ListingState as anchor_lang::Discriminator>::DISCRIMINATOR,
+            ListingError::InvalidListingAccount
+        );
+
+        let rent_due = Rent::get()?.minimum_balance(new_len).saturating_sub(listing.lamports());
+        if rent_due > 0 {
+            let rent_ctx = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: listing.clone(),
+                },
+            );
+            anchor_lang::system_program::transfer(rent_ctx, rent_due)?;
+        }
+        listing.realloc(new_len, true)?;
+
+        emit!(ListingMigrated {
+            listing: listing.key(),
+            old_len: old_len as u32,
+            new_len: new_len as u32,
+        });
+
+        Ok(())
+    }
+
+    /// Close a sold listing and return its rent to the seller
+    pub fn close_listing(ctx: Context<CloseListing>) -> Result<()> {
+        let listing = &ctx.accounts.listing;
+        require!(!listing.is_active, ListingError::ListingStillActive);
+
+        emit!(ListingClosed {
+            listing: listing.key(),
+            seller: listing.seller,
+            mint: listing.mint,
+            buyer: listing.buyer,
+        });
+
+        Ok(())
+    }
+
     /// Update listing price (only seller)
     pub fn update_listing(
         ctx: Context<UpdateListing>,
@@ -146,15 +162,18 @@ pub mod listing {
     }
 
     /// Cancel listing and return NFT to seller
+    ///
+    /// The listing and its escrow token account are closed and their rent returned to the seller
     pub fn cancel_listing(ctx: Context<CancelListing>) -> Result<()> {
         require!(ctx.accounts.listing.is_active, ListingError::ListingNotActive);
 
         // Transfer NFT back to seller
-        let listing_key = ctx.accounts.listing.key();
+        let listing = &ctx.accounts.listing;
         let seeds = &[
             b"listing",
-            listing_key.as_ref(),
-            &[ctx.accounts.listing.bump],
+            listing.mint.as_ref(),
+            listing.seller.as_ref(),
+            &[listing.bump],
         ];
         let signer = &[&seeds[..]];
 
@@ -169,6 +188,17 @@ pub mod listing {
         );
         token::transfer(transfer_ctx, 1)?;
 
+        let close_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.listing_token_account.to_account_info(),
+                destination: ctx.accounts.seller.to_account_info(),
+                authority: ctx.accounts.listing.to_account_info(),
+            },
+            signer,
+        );
+        token::close_account(close_ctx)?;
+
         // Mark listing as inactive
         let listing = &mut ctx.accounts.listing;
         listing.is_active = false;
@@ -182,11 +212,14 @@ pub mod listing {
         Ok(())
     }
 
-    /// Buy NFT from listing
-    pub fn buy_nft(ctx: Context<BuyNft>) -> Result<()> {
+    /// Buy NFT from listing, paying platform fee and creator royalties
+    ///
+    /// The escrow token account is closed to the seller. The sold listing is kept as a record of
+    /// the sale until the seller calls `close_listing` or lists the mint again
+    pub fn buy_nft<'info>(ctx: Context<'_, '_, '_, 'info, BuyNft<'info>>) -> Result<()> {
         let listing = &ctx.accounts.listing;
         require!(listing.is_active, ListingError::ListingNotActive);
-        require!(!ctx.accounts.marketplace.is_paused, ListingError::MarketplacePaused);
+        check_marketplace_active(&ctx.accounts.marketplace)?;
 
         // Check if listing has expired
         if let Some(expiry) = listing.expiry {
@@ -196,38 +229,131 @@ pub mod listing {
 
         let sale_price = listing.price;
         
-        // Calculate platform fee
+        // Calculate platform fee and creator royalties
         let platform_fee = ctx.accounts.marketplace.calculate_platform_fee(sale_price)?;
+        let metadata = load_metadata(&ctx.accounts.metadata.to_account_info(), &listing.mint)?;
+        let creator_royalties = calculate_creator_royalties(&ctx.accounts.royalty_config, &metadata, sale_price);
+        let royalty_fee = total_royalties(&creator_royalties)?;
         let seller_proceeds = sale_price.checked_sub(platform_fee)
+            .and_then(|amount| amount.checked_sub(royalty_fee))
             .ok_or(ListingError::MathOverflow)?;
 
-        // Transfer platform fee to treasury
-        if platform_fee > 0 {
-            let fee_transfer_ctx = CpiContext::new(
-                ctx.accounts.system_program.to_account_info(),
-                anchor_lang::system_program::Transfer {
-                    from: ctx.accounts.buyer.to_account_info(),
-                    to: ctx.accounts.treasury.to_account_info(),
-                },
-            );
-            anchor_lang::system_program::transfer(fee_transfer_ctx, platform_fee)?;
-        }
+        match listing.payment_mint {
+            None => {
+                // Transfer platform fee to treasury
+                if platform_fee > 0 {
+                    let fee_transfer_ctx = CpiContext::new(
+                        ctx.accounts.system_program.to_account_info(),
+                        anchor_lang::system_program::Transfer {
+                            from: ctx.accounts.buyer.to_account_info(),
+                            to: ctx.accounts.treasury.to_account_info(),
+                        },
+                    );
+                    anchor_lang::system_program::transfer(fee_transfer_ctx, platform_fee)?;
+                }
 
-        // Transfer payment to seller (minus platform fee)
-        let payment_transfer_ctx = CpiContext::new(
-            ctx.accounts.system_program.to_account_info(),
-            anchor_lang::system_program::Transfer {
-                from: ctx.accounts.buyer.to_account_info(),
-                to: ctx.accounts.seller.to_account_info(),
-            },
-        );
-        anchor_lang::system_program::transfer(payment_transfer_ctx, seller_proceeds)?;
+                // Pay creator royalties to the accounts passed in remaining accounts
+                for creator in &creator_royalties {
+                    if creator.amount > 0 {
+                        let creator_account = ctx.remaining_accounts
+                            .iter()
+                            .find(|acc| acc.key() == creator.address)
+                            .ok_or(ListingError::CreatorAccountNotFound)?;
+
+                        let royalty_transfer_ctx = CpiContext::new(
+                            ctx.accounts.system_program.to_account_info(),
+                            anchor_lang::system_program::Transfer {
+                                from: ctx.accounts.buyer.to_account_info(),
+                                to: creator_account.clone(),
+                            },
+                        );
+                        anchor_lang::system_program::transfer(royalty_transfer_ctx, creator.amount)?;
+                    }
+                }
+
+                // Transfer payment to seller (minus platform fee and royalties)
+                let payment_transfer_ctx = CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.buyer.to_account_info(),
+                        to: ctx.accounts.seller.to_account_info(),
+                    },
+                );
+                anchor_lang::system_program::transfer(payment_transfer_ctx, seller_proceeds)?;
+            }
+            Some(payment_mint) => {
+                let buyer_payment_account = ctx.accounts.buyer_payment_account.as_ref()
+                    .ok_or(ListingError::PaymentAccountMissing)?;
+                let seller_payment_account = ctx.accounts.seller_payment_account.as_ref()
+                    .ok_or(ListingError::PaymentAccountMissing)?;
+                let treasury_payment_account = ctx.accounts.treasury_payment_account.as_ref()
+                    .ok_or(ListingError::PaymentAccountMissing)?;
+
+                // Proceeds and fees go to the canonical ATAs of the payment mint
+                require_keys_eq!(buyer_payment_account.mint, payment_mint, ListingError::InvalidPaymentAccount);
+                require_keys_eq!(
+                    seller_payment_account.key(),
+                    get_associated_token_address(&listing.seller, &payment_mint),
+                    ListingError::InvalidPaymentAccount
+                );
+                require_keys_eq!(
+                    treasury_payment_account.key(),
+                    get_associated_token_address(&ctx.accounts.treasury.key(), &payment_mint),
+                    ListingError::InvalidPaymentAccount
+                );
+
+                // Transfer platform fee to the treasury ATA
+                if platform_fee > 0 {
+                    let fee_transfer_ctx = CpiContext::new(
+                        ctx.accounts.token_program.to_account_info(),
+                        Transfer {
+                            from: buyer_payment_account.to_account_info(),
+                            to: treasury_payment_account.to_account_info(),
+                            authority: ctx.accounts.buyer.to_account_info(),
+                        },
+                    );
+                    token::transfer(fee_transfer_ctx, platform_fee)?;
+                }
+
+                // Pay creator royalties to their ATAs passed in remaining accounts
+                for creator in &creator_royalties {
+                    if creator.amount > 0 {
+                        let creator_payment_account = get_associated_token_address(&creator.address, &payment_mint);
+                        let creator_account = ctx.remaining_accounts
+                            .iter()
+                            .find(|acc| acc.key() == creator_payment_account)
+                            .ok_or(ListingError::CreatorAccountNotFound)?;
+
+                        let royalty_transfer_ctx = CpiContext::new(
+                            ctx.accounts.token_program.to_account_info(),
+                            Transfer {
+                                from: buyer_payment_account.to_account_info(),
+                                to: creator_account.clone(),
+                                authority: ctx.accounts.buyer.to_account_info(),
+                            },
+                        );
+                        token::transfer(royalty_transfer_ctx, creator.amount)?;
+                    }
+                }
+
+                // Transfer payment to seller ATA (minus platform fee and royalties)
+                let payment_transfer_ctx = CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: buyer_payment_account.to_account_info(),
+                        to: seller_payment_account.to_account_info(),
+                        authority: ctx.accounts.buyer.to_account_info(),
+                    },
+                );
+                token::transfer(payment_transfer_ctx, seller_proceeds)?;
+            }
+        }
 
         // Transfer NFT to buyer
-        let listing_key = listing.key();
         let seeds = &[
             b"listing",
-            listing_key.as_ref(),
+            listing.mint.as_ref(),
+            listing.seller.as_ref(),
             &[listing.bump],
         ];
         let signer = &[&seeds[..]];
@@ -243,16 +369,39 @@ pub mod listing {
         );
         token::transfer(nft_transfer_ctx, 1)?;
 
-        // Mark listing as inactive
+        let close_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.listing_token_account.to_account_info(),
+                destination: ctx.accounts.seller.to_account_info(),
+                authority: ctx.accounts.listing.to_account_info(),
+            },
+            signer,
+        );
+        token::close_account(close_ctx)?;
+
+        // Mark listing as sold, the record stays as proof of sale until `close_listing`
         let listing = &mut ctx.accounts.listing;
         listing.is_active = false;
+        listing.buyer = Some(ctx.accounts.buyer.key());
+        listing.last_sold_at = Clock::get()?.unix_timestamp;
 
-        // Update marketplace stats via CPI
+        // Update marketplace stats via CPI, signed by our sale authority
+        let sale_authority_seeds = &[SALE_AUTHORITY_SEED, &[ctx.bumps.sale_authority]];
+        let sale_authority_signer = &[&sale_authority_seeds[..]];
         let cpi_accounts = marketplace::cpi::accounts::UpdateStats {
-            marketplace: ctx.accounts.marketplace.clone(),
+            marketplace: ctx.accounts.marketplace.to_account_info(),
+            sale_authority: ctx.accounts.sale_authority.to_account_info(),
+            caller_program: ctx.accounts.listing_program.to_account_info(),
         };
-        let cpi_ctx = CpiContext::new(ctx.accounts.marketplace_program.to_account_info(), cpi_accounts);
-        marketplace::cpi::update_stats(cpi_ctx, sale_price)?;
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.marketplace_program.to_account_info(),
+            cpi_accounts,
+            sale_authority_signer,
+        );
+        // Total volume is tracked in lamports, so token sales only count as a sale
+        let volume = if listing.payment_mint.is_none() { sale_price } else { 0 };
+        marketplace::cpi::update_stats(cpi_ctx, volume)?;
 
         emit!(NftSold {
             listing: listing.key(),
@@ -260,7 +409,11 @@ pub mod listing {
             buyer: ctx.accounts.buyer.key(),
             mint: listing.mint,
             price: sale_price,
+            payment_mint: listing.payment_mint,
             platform_fee,
+            royalty_fee,
+            seller_proceeds,
+            creators: creator_royalties,
         });
 
         Ok(())
@@ -280,10 +433,10 @@ pub mod listing {
         }
 
         // Transfer NFT back to seller
-        let listing_key = listing.key();
         let seeds = &[
             b"listing",
-            listing_key.as_ref(),
+            listing.mint.as_ref(),
+            listing.seller.as_ref(),
             &[listing.bump],
         ];
         let signer = &[&seeds[..]];
@@ -299,6 +452,17 @@ pub mod listing {
         );
         token::transfer(transfer_ctx, 1)?;
 
+        let close_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.listing_token_account.to_account_info(),
+                destination: ctx.accounts.seller.to_account_info(),
+                authority: ctx.accounts.listing.to_account_info(),
+            },
+            signer,
+        );
+        token::close_account(close_ctx)?;
+
         // Mark listing as inactive
         let listing = &mut ctx.accounts.listing;
         listing.is_active = false;
@@ -311,181 +475,1357 @@ pub mod listing {
 
         Ok(())
     }
-}
 
-#[derive(Accounts)]
-pub struct ListNft<'info> {
-    #[account(
-        init,
-        payer = seller,
-        space = 8 + ListingState::INIT_SPACE,
-        seeds = [b"listing", mint.key().as_ref(), seller.key().as_ref()],
-        bump
-    )]
-    pub listing: Account<'info, ListingState>,
-    
-    #[account(mut)]
-    pub seller: Signer<'info>,
-    
-    pub mint: Account<'info, Mint>,
-    
-    #[account(
-        mut,
-        associated_token::mint = mint,
-        associated_token::authority = seller,
-        constraint = seller_token_account.amount == 1
-    )]
-    pub seller_token_account: Account<'info, TokenAccount>,
-    
-    #[account(
-        init,
-        payer = seller,
-        associated_token::mint = mint,
-        associated_token::authority = listing
-    )]
-    pub listing_token_account: Account<'info, TokenAccount>,
-    
-    /// CHECK: Metadata account for the NFT - using UncheckedAccount since mpl_token_metadata::accounts::Metadata doesn't implement required traits
-    #[account(
-        constraint = metadata.key() == find_metadata_account(&mint.key()).0
-    )]
-    pub metadata: UncheckedAccount<'info>,
-    
-    pub marketplace: Account<'info, marketplace::MarketplaceState>,
-    
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
-}
+    /// Place a standing SOL offer on any NFT of a verified collection
+    pub fn make_collection_offer(
+        ctx: Context<MakeCollectionOffer>,
+        collection: Pubkey,
+        price: u64,
+        quantity: u32,
+        expiry: Option<i64>,
+    ) -> Result<()> {
+        check_marketplace_active(&ctx.accounts.marketplace)?;
+        require!(price > 0, ListingError::InvalidPrice);
+        require!(quantity > 0, ListingError::InvalidQuantity);
 
-#[derive(Accounts)]
-pub struct UpdateListing<'info> {
-    #[account(
-        mut,
-        seeds = [b"listing", listing.mint.as_ref(), listing.seller.as_ref()],
-        bump = listing.bump,
-        has_one = seller
-    )]
-    pub listing: Account<'info, ListingState>,
-    
-    pub seller: Signer<'info>,
-}
+        // Validate expiry if provided
+        if let Some(expiry_time) = expiry {
+            let clock = Clock::get()?;
+            require!(expiry_time > clock.unix_timestamp, ListingError::InvalidExpiry);
+        }
 
-#[derive(Accounts)]
-pub struct CancelListing<'info> {
-    #[account(
-        mut,
-        seeds = [b"listing", listing.mint.as_ref(), listing.seller.as_ref()],
-        bump = listing.bump,
-        has_one = seller
-    )]
-    pub listing: Account<'info, ListingState>,
-    
-    #[account(mut)]
-    pub seller: Signer<'info>,
-    
-    #[account(
-        mut,
-        associated_token::mint = listing.mint,
-        associated_token::authority = listing
-    )]
-    pub listing_token_account: Account<'info, TokenAccount>,
-    
-    #[account(
-        mut,
-        associated_token::mint = listing.mint,
-        associated_token::authority = seller
-    )]
-    pub seller_token_account: Account<'info, TokenAccount>,
-    
-    pub token_program: Program<'info, Token>,
-}
+        // Escrow the full offer amount in the offer account
+        let escrow_amount = price.checked_mul(quantity as u64)
+            .ok_or(ListingError::MathOverflow)?;
+        let escrow_transfer_ctx = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.buyer.to_account_info(),
+                to: ctx.accounts.offer.to_account_info(),
+            },
+        );
+        anchor_lang::system_program::transfer(escrow_transfer_ctx, escrow_amount)?;
 
-#[derive(Accounts)]
-pub struct BuyNft<'info> {
-    #[account(
-        mut,
-        seeds = [b"listing", listing.mint.as_ref(), listing.seller.as_ref()],
-        bump = listing.bump
-    )]
-    pub listing: Account<'info, ListingState>,
-    
-    #[account(mut)]
-    pub buyer: Signer<'info>,
-    
-    /// CHECK: Seller account for payment
-    #[account(
-        mut,
-        constraint = seller.key() == listing.seller
-    )]
-    pub seller: AccountInfo<'info>,
-    
-    #[account(
-        mut,
-        associated_token::mint = listing.mint,
-        associated_token::authority = listing
-    )]
-    pub listing_token_account: Account<'info, TokenAccount>,
-    
-    #[account(
-        init_if_needed,
-        payer = buyer,
-        associated_token::mint = mint,
-        associated_token::authority = buyer
-    )]
-    pub buyer_token_account: Account<'info, TokenAccount>,
+        let offer = &mut ctx.accounts.offer;
+        offer.buyer = ctx.accounts.buyer.key();
+        offer.collection = collection;
+        offer.price = price;
+        offer.quantity = quantity;
+        offer.filled = 0;
+        offer.created_at = Clock::get()?.unix_timestamp;
+        offer.expiry = expiry;
+        offer.bump = ctx.bumps.offer;
 
-    #[account(
-        constraint = mint.key() == listing.mint
-    )]
-    pub mint: Account<'info, Mint>,
-    
-    #[account(mut)]
-    pub marketplace: Account<'info, marketplace::MarketplaceState>,
-    
-    /// CHECK: Treasury account from marketplace
-    #[account(
-        mut,
-        constraint = treasury.key() == marketplace.treasury
-    )]
-    pub treasury: AccountInfo<'info>,
-    
-    /// CHECK: Marketplace program for CPI
-    pub marketplace_program: AccountInfo<'info>,
-    pub token_program: Program<'info, Token>,
-    pub associated_token_program: Program<'info, AssociatedToken>,
-    pub system_program: Program<'info, System>,
-    pub rent: Sysvar<'info, Rent>,
-}
+        emit!(CollectionOfferMade {
+            offer: offer.key(),
+            buyer: offer.buyer,
+            collection,
+            price,
+            quantity,
+            expiry,
+        });
 
-#[derive(Accounts)]
-pub struct RecoverExpiredListing<'info> {
-    #[account(
-        mut,
-        seeds = [b"listing", listing.mint.as_ref(), listing.seller.as_ref()],
-        bump = listing.bump
+        Ok(())
+    }
+
+    /// Cancel a collection offer, refunding the unfilled escrow and rent to the buyer
+    pub fn cancel_collection_offer(ctx: Context<CancelCollectionOffer>) -> Result<()> {
+        let offer = &ctx.accounts.offer;
+        let refund_amount = offer.price.checked_mul(offer.quantity as u64)
+            .ok_or(ListingError::MathOverflow)?;
+
+        emit!(CollectionOfferCanceled {
+            offer: offer.key(),
+            buyer: offer.buyer,
+            collection: offer.collection,
+            refund_amount,
+        });
+
+        Ok(())
+    }
+
+    /// Sell an NFT of the offer's collection into a standing collection offer
+    pub fn accept_collection_offer<'info>(
+        ctx: Context<'_, '_, '_, 'info, AcceptCollectionOffer<'info>>,
+    ) -> Result<()> {
+        let offer = &ctx.accounts.offer;
+        check_marketplace_active(&ctx.accounts.marketplace)?;
+        require!(offer.quantity > 0, ListingError::OfferFilled);
+
+        // Check if offer has expired
+        if let Some(expiry) = offer.expiry {
+            let clock = Clock::get()?;
+            require!(clock.unix_timestamp <= expiry, ListingError::OfferExpired);
+        }
+
+        // The NFT must belong to the offer's collection, and the collection must be verified
+        let mint_key = ctx.accounts.mint.key();
+        let metadata = load_metadata(&ctx.accounts.metadata.to_account_info(), &mint_key)?;
+        let collection = metadata.collection.as_ref()
+            .ok_or(ListingError::CollectionNotVerified)?;
+        require!(collection.verified, ListingError::CollectionNotVerified);
+        require_keys_eq!(collection.key, offer.collection, ListingError::CollectionMismatch);
+
+        let sale_price = offer.price;
+
+        // Calculate platform fee and creator royalties
+        let platform_fee = ctx.accounts.marketplace.calculate_platform_fee(sale_price)?;
+        let creator_royalties = calculate_creator_royalties(&ctx.accounts.royalty_config, &metadata, sale_price);
+        let royalty_fee = total_royalties(&creator_royalties)?;
+        let seller_proceeds = sale_price.checked_sub(platform_fee)
+            .and_then(|amount| amount.checked_sub(royalty_fee))
+            .ok_or(ListingError::MathOverflow)?;
+
+        // Pay out of the lamports escrowed in the offer account
+        pay_from_offer_escrow(
+            &ctx.accounts.offer.to_account_info(),
+            &ctx.accounts.treasury,
+            &ctx.accounts.seller.to_account_info(),
+            ctx.remaining_accounts,
+            &creator_royalties,
+            platform_fee,
+            seller_proceeds,
+        )?;
+
+        // Transfer NFT to buyer
+        let nft_transfer_ctx = CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.seller_token_account.to_account_info(),
+                to: ctx.accounts.buyer_token_account.to_account_info(),
+                authority: ctx.accounts.seller.to_account_info(),
+            },
+        );
+        token::transfer(nft_transfer_ctx, 1)?;
+
+        // Consume one unit of the offer
+        let offer = &mut ctx.accounts.offer;
+        offer.quantity -= 1;
+        offer.filled = offer.filled.checked_add(1)
+            .ok_or(ListingError::MathOverflow)?;
+
+        // Update marketplace stats via CPI, signed by our sale authority
+        let sale_authority_seeds = &[SALE_AUTHORITY_SEED, &[ctx.bumps.sale_authority]];
+        let sale_authority_signer = &[&sale_authority_seeds[..]];
+        let cpi_accounts = marketplace::cpi::accounts::UpdateStats {
+            marketplace: ctx.accounts.marketplace.to_account_info(),
+            sale_authority: ctx.accounts.sale_authority.to_account_info(),
+            caller_program: ctx.accounts.listing_program.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.marketplace_program.to_account_info(),
+            cpi_accounts,
+            sale_authority_signer,
+        );
+        marketplace::cpi::update_stats(cpi_ctx, sale_price)?;
+
+        emit!(CollectionOfferAccepted {
+            offer: offer.key(),
+            buyer: offer.buyer,
+            seller: ctx.accounts.seller.key(),
+            mint: mint_key,
+            collection: offer.collection,
+            price: sale_price,
+            platform_fee,
+            royalty_fee,
+            seller_proceeds,
+            remaining_quantity: offer.quantity,
+            creators: creator_royalties,
+        });
+
+        // A filled offer holds no more escrow, return its rent to the buyer
+        if offer.quantity == 0 {
+            ctx.accounts.offer.close(ctx.accounts.buyer.to_account_info())?;
+        }
+
+        Ok(())
+    }
+
+    /// Offer lamports for a specific NFT, listed or not
+    pub fn make_offer(
+        ctx: Context<MakeOffer>,
+        amount: u64,
+        expiry: Option<i64>,
+    ) -> Result<()> {
+        check_marketplace_active(&ctx.accounts.marketplace)?;
+        require!(amount > 0, ListingError::InvalidPrice);
+
+        // Validate expiry if provided
+        if let Some(expiry_time) = expiry {
+            let clock = Clock::get()?;
+            require!(expiry_time > clock.unix_timestamp, ListingError::InvalidExpiry);
+        }
+
+        // Escrow the offer amount in the offer account
+        let escrow_transfer_ctx = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.buyer.to_account_info(),
+                to: ctx.accounts.offer.to_account_info(),
+            },
+        );
+        anchor_lang::system_program::transfer(escrow_transfer_ctx, amount)?;
+
+        let offer = &mut ctx.accounts.offer;
+        offer.buyer = ctx.accounts.buyer.key();
+        offer.mint = ctx.accounts.mint.key();
+        offer.amount = amount;
+        offer.created_at = Clock::get()?.unix_timestamp;
+        offer.expiry = expiry;
+        offer.bump = ctx.bumps.offer;
+
+        emit!(OfferMade {
+            offer: offer.key(),
+            buyer: offer.buyer,
+            mint: offer.mint,
+            amount,
+            expiry,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel an offer, refunding the escrow and rent to the buyer
+    pub fn cancel_offer(ctx: Context<CancelOffer>) -> Result<()> {
+        let offer = &ctx.accounts.offer;
+
+        emit!(OfferCanceled {
+            offer: offer.key(),
+            buyer: offer.buyer,
+            mint: offer.mint,
+            amount: offer.amount,
+        });
+
+        Ok(())
+    }
+
+    /// Accept an offer as the NFT holder, canceling any active listing for the mint
+    pub fn accept_offer<'info>(ctx: Context<'_, '_, '_, 'info, AcceptOffer<'info>>) -> Result<()> {
+        let offer = &ctx.accounts.offer;
+        check_marketplace_active(&ctx.accounts.marketplace)?;
+
+        // Check if offer has expired
+        if let Some(expiry) = offer.expiry {
+            let clock = Clock::get()?;
+            require!(clock.unix_timestamp <= expiry, ListingError::OfferExpired);
+        }
+
+        let sale_price = offer.amount;
+        let mint_key = offer.mint;
+
+        // Calculate platform fee and creator royalties
+        let platform_fee = ctx.accounts.marketplace.calculate_platform_fee(sale_price)?;
+        let metadata = load_metadata(&ctx.accounts.metadata.to_account_info(), &mint_key)?;
+        let creator_royalties = calculate_creator_royalties(&ctx.accounts.royalty_config, &metadata, sale_price);
+        let royalty_fee = total_royalties(&creator_royalties)?;
+        let seller_proceeds = sale_price.checked_sub(platform_fee)
+            .and_then(|amount| amount.checked_sub(royalty_fee))
+            .ok_or(ListingError::MathOverflow)?;
+
+        // Pay out of the lamports escrowed in the offer account
+        pay_from_offer_escrow(
+            &ctx.accounts.offer.to_account_info(),
+            &ctx.accounts.treasury,
+            &ctx.accounts.seller.to_account_info(),
+            ctx.remaining_accounts,
+            &creator_royalties,
+            platform_fee,
+            seller_proceeds,
+        )?;
+
+        // Deliver the NFT from the listing escrow if listed, otherwise from the seller's wallet
+        let active_listing = ctx.accounts.listing.as_ref()
+            .filter(|listing| listing.is_active);
+        match active_listing {
+            Some(listing) => {
+                let listing_token_account = ctx.accounts.listing_token_account.as_ref()
+                    .ok_or(ListingError::ListingTokenAccountMissing)?;
+                require_keys_eq!(
+                    listing_token_account.key(),
+                    get_associated_token_address(&listing.key(), &mint_key),
+                    ListingError::ListingTokenAccountMissing
+                );
+
+                let seller_key = ctx.accounts.seller.key();
+                let seeds = &[
+                    b"listing",
+                    mint_key.as_ref(),
+                    seller_key.as_ref(),
+                    &[listing.bump],
+                ];
+                let signer = &[&seeds[..]];
+
+                let nft_transfer_ctx = CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: listing_token_account.to_account_info(),
+                        to: ctx.accounts.buyer_token_account.to_account_info(),
+                        authority: listing.to_account_info(),
+                    },
+                    signer,
+                );
+                token::transfer(nft_transfer_ctx, 1)?;
+
+                let close_ctx = CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    CloseAccount {
+                        account: listing_token_account.to_account_info(),
+                        destination: ctx.accounts.seller.to_account_info(),
+                        authority: listing.to_account_info(),
+                    },
+                    signer,
+                );
+                token::close_account(close_ctx)?;
+            }
+            None => {
+                let nft_transfer_ctx = CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    Transfer {
+                        from: ctx.accounts.seller_token_account.to_account_info(),
+                        to: ctx.accounts.buyer_token_account.to_account_info(),
+                        authority: ctx.accounts.seller.to_account_info(),
+                    },
+                );
+                token::transfer(nft_transfer_ctx, 1)?;
+            }
+        }
+
+        // The listed NFT is gone, so the listing is canceled and closed in the same transaction
+        if let Some(listing) = ctx.accounts.listing.as_mut() {
+            if listing.is_active {
+                listing.is_active = false;
+
+                emit!(ListingCanceled {
+                    listing: listing.key(),
+                    seller: listing.seller,
+                    mint: listing.mint,
+                });
+
+                listing.close(ctx.accounts.seller.to_account_info())?;
+            }
+        }
+
+        // Update marketplace stats via CPI, signed by our sale authority
+        let sale_authority_seeds = &[SALE_AUTHORITY_SEED, &[ctx.bumps.sale_authority]];
+        let sale_authority_signer = &[&sale_authority_seeds[..]];
+        let cpi_accounts = marketplace::cpi::accounts::UpdateStats {
+            marketplace: ctx.accounts.marketplace.to_account_info(),
+            sale_authority: ctx.accounts.sale_authority.to_account_info(),
+            caller_program: ctx.accounts.listing_program.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.marketplace_program.to_account_info(),
+            cpi_accounts,
+            sale_authority_signer,
+        );
+        marketplace::cpi::update_stats(cpi_ctx, sale_price)?;
+
+        emit!(OfferAccepted {
+            offer: ctx.accounts.offer.key(),
+            buyer: ctx.accounts.offer.buyer,
+            seller: ctx.accounts.seller.key(),
+            mint: mint_key,
+            price: sale_price,
+            platform_fee,
+            royalty_fee,
+            seller_proceeds,
+            creators: creator_royalties,
+        });
+
+        Ok(())
+    }
+
+    /// List several NFTs as one bundle at a single price
+    ///
+    /// Remaining accounts: `[mint, seller_token_account, bundle_token_account]` per item
+    pub fn create_bundle_listing<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CreateBundleListing<'info>>,
+        bundle_id: u64,
+        price: u64,
+        expiry: Option<i64>,
+    ) -> Result<()> {
+        check_marketplace_active(&ctx.accounts.marketplace)?;
+        require!(price > 0, ListingError::InvalidPrice);
+
+        // Validate expiry if provided
+        if let Some(expiry_time) = expiry {
+            let clock = Clock::get()?;
+            require!(expiry_time > clock.unix_timestamp, ListingError::InvalidExpiry);
+        }
+
+        let items = ctx.remaining_accounts;
+        #[allow(clippy::manual_is_multiple_of)]
+        let whole_items = items.len() % 3 == 0;
+        require!(whole_items, ListingError::InvalidBundleAccounts);
+        let item_count = items.len() / 3;
+        require!(
+            (2..=MAX_BUNDLE_SIZE).contains(&item_count),
+            ListingError::InvalidBundleSize
+        );
+
+        let seller_key = ctx.accounts.seller.key();
+        let bundle_key = ctx.accounts.bundle.key();
+        let mut mints = Vec::with_capacity(item_count);
+
+        // Move every NFT into a bundle-owned ATA
+        for item in items.chunks(3) {
+            let (mint, seller_token_account, bundle_token_account) = (&item[0], &item[1], &item[2]);
+            require!(!mints.contains(mint.key), ListingError::DuplicateBundleMint);
+
+            let seller_tokens = Account::<TokenAccount>::try_from(seller_token_account)?;
+            require_keys_eq!(
+                seller_token_account.key(),
+                get_associated_token_address(&seller_key, mint.key),
+                ListingError::InvalidBundleAccounts
+            );
+            require!(seller_tokens.amount == 1, ListingError::InvalidBundleAccounts);
+            require_keys_eq!(
+                bundle_token_account.key(),
+                get_associated_token_address(&bundle_key, mint.key),
+                ListingError::InvalidBundleAccounts
+            );
+
+            let create_ctx = CpiContext::new(
+                ctx.accounts.associated_token_program.to_account_info(),
+                Create {
+                    payer: ctx.accounts.seller.to_account_info(),
+                    associated_token: bundle_token_account.clone(),
+                    authority: ctx.accounts.bundle.to_account_info(),
+                    mint: mint.clone(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                },
+            );
+            // Anyone can create the bundle PDA's ATAs ahead of time, so existing ones are reused
+            associated_token::create_idempotent(create_ctx)?;
+
+            let transfer_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: seller_token_account.clone(),
+                    to: bundle_token_account.clone(),
+                    authority: ctx.accounts.seller.to_account_info(),
+                },
+            );
+            token::transfer(transfer_ctx, 1)?;
+
+            mints.push(mint.key());
+        }
+
+        let bundle = &mut ctx.accounts.bundle;
+        bundle.seller = seller_key;
+        bundle.bundle_id = bundle_id;
+        bundle.price = price;
+        bundle.mints = mints;
+        bundle.created_at = Clock::get()?.unix_timestamp;
+        bundle.expiry = expiry;
+        bundle.is_active = true;
+        bundle.bump = ctx.bumps.bundle;
+
+        emit!(BundleListed {
+            bundle: bundle.key(),
+            seller: bundle.seller,
+            bundle_id,
+            mints: bundle.mints.clone(),
+            price,
+            expiry,
+        });
+
+        Ok(())
+    }
+
+    /// Cancel a bundle listing and return every NFT to the seller, closing the bundle accounts
+    ///
+    /// Remaining accounts: `[bundle_token_account, seller_token_account]` per item, in bundle order
+    pub fn cancel_bundle_listing<'info>(
+        ctx: Context<'_, '_, 'info, 'info, CancelBundleListing<'info>>,
+    ) -> Result<()> {
+        let bundle = &ctx.accounts.bundle;
+        require!(bundle.is_active, ListingError::ListingNotActive);
+
+        return_bundle_items(
+            &ctx.accounts.bundle,
+            &ctx.accounts.token_program,
+            &ctx.accounts.seller.to_account_info(),
+            ctx.remaining_accounts,
+        )?;
+
+        let bundle = &mut ctx.accounts.bundle;
+        bundle.is_active = false;
+
+        emit!(BundleListingCanceled {
+            bundle: bundle.key(),
+            seller: bundle.seller,
+            bundle_id: bundle.bundle_id,
+        });
+
+        Ok(())
+    }
+
+    /// Return the NFTs of an expired bundle to its seller (anyone can call)
+    ///
+    /// Remaining accounts: `[bundle_token_account, seller_token_account]` per item, in bundle order
+    pub fn recover_expired_bundle<'info>(
+        ctx: Context<'_, '_, 'info, 'info, RecoverExpiredBundle<'info>>,
+    ) -> Result<()> {
+        let bundle = &ctx.accounts.bundle;
+        require!(bundle.is_active, ListingError::ListingNotActive);
+
+        let expiry = bundle.expiry.ok_or(ListingError::ListingHasNoExpiry)?;
+        let clock = Clock::get()?;
+        require!(clock.unix_timestamp > expiry, ListingError::ListingNotExpired);
+
+        return_bundle_items(
+            &ctx.accounts.bundle,
+            &ctx.accounts.token_program,
+            &ctx.accounts.seller,
+            ctx.remaining_accounts,
+        )?;
+
+        let bundle = &mut ctx.accounts.bundle;
+        bundle.is_active = false;
+
+        emit!(ExpiredBundleRecovered {
+            bundle: bundle.key(),
+            seller: bundle.seller,
+            bundle_id: bundle.bundle_id,
+        });
+
+        Ok(())
+    }
+
+    /// Buy every NFT of a bundle atomically at the bundle price
+    ///
+    /// Remaining accounts: `[mint, bundle_token_account, buyer_token_account, metadata]` per item,
+    /// in bundle order, followed by the creator accounts owed royalties. Missing buyer token
+    /// accounts are created
+    pub fn buy_bundle<'info>(ctx: Context<'_, '_, 'info, 'info, BuyBundle<'info>>) -> Result<()> {
+        let bundle = &ctx.accounts.bundle;
+        require!(bundle.is_active, ListingError::ListingNotActive);
+        check_marketplace_active(&ctx.accounts.marketplace)?;
+
+        // Check if bundle has expired
+        if let Some(expiry) = bundle.expiry {
+            let clock = Clock::get()?;
+            require!(clock.unix_timestamp <= expiry, ListingError::ListingExpired);
+        }
+
+        let item_count = bundle.mints.len();
+        require!(ctx.remaining_accounts.len() >= item_count * 4, ListingError::InvalidBundleAccounts);
+        let (items, creator_accounts) = ctx.remaining_accounts.split_at(item_count * 4);
+
+        let sale_price = bundle.price;
+        let buyer_key = ctx.accounts.buyer.key();
+        let bundle_key = bundle.key();
+
+        // Royalties are owed on each item's equal share of the bundle price, the last
+        // item absorbing the rounding remainder
+        let item_share = sale_price / item_count as u64;
+        let mut creator_royalties: Vec<CreatorRoyalty> = Vec::new();
+        for (index, (mint, item)) in bundle.mints.iter().zip(items.chunks(4)).enumerate() {
+            let (mint_account, bundle_token_account, buyer_token_account, metadata_account) =
+                (&item[0], &item[1], &item[2], &item[3]);
+            require_keys_eq!(mint_account.key(), *mint, ListingError::InvalidBundleAccounts);
+            require_keys_eq!(
+                bundle_token_account.key(),
+                get_associated_token_address(&bundle_key, mint),
+                ListingError::InvalidBundleAccounts
+            );
+            require_keys_eq!(
+                buyer_token_account.key(),
+                get_associated_token_address(&buyer_key, mint),
+                ListingError::InvalidBundleAccounts
+            );
+            require_keys_eq!(
+                metadata_account.key(),
+                find_metadata_account(mint).0,
+                ListingError::InvalidBundleAccounts
+            );
+
+            let share = if index == item_count - 1 {
+                sale_price - item_share * (item_count as u64 - 1)
+            } else {
+                item_share
+            };
+            let metadata = load_metadata(metadata_account, mint)?;
+            for royalty in calculate_creator_royalties(&ctx.accounts.royalty_config, &metadata, share) {
+                match creator_royalties.iter_mut().find(|existing| existing.address == royalty.address) {
+                    Some(existing) => {
+                        existing.amount = existing.amount.checked_add(royalty.amount)
+                            .ok_or(ListingError::MathOverflow)?;
+                    }
+                    None => creator_royalties.push(royalty),
+                }
+            }
+        }
+
+        // Calculate platform fee and seller proceeds
+        let platform_fee = ctx.accounts.marketplace.calculate_platform_fee(sale_price)?;
+        let royalty_fee = total_royalties(&creator_royalties)?;
+        let seller_proceeds = sale_price.checked_sub(platform_fee)
+            .and_then(|amount| amount.checked_sub(royalty_fee))
+            .ok_or(ListingError::MathOverflow)?;
+
+        // Transfer platform fee to treasury
+        if platform_fee > 0 {
+            let fee_transfer_ctx = CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                anchor_lang::system_program::Transfer {
+                    from: ctx.accounts.buyer.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                },
+            );
+            anchor_lang::system_program::transfer(fee_transfer_ctx, platform_fee)?;
+        }
+
+        // Pay creator royalties to the accounts passed after the bundle items
+        for creator in &creator_royalties {
+            if creator.amount > 0 {
+                let creator_account = creator_accounts
+                    .iter()
+                    .find(|acc| acc.key() == creator.address)
+                    .ok_or(ListingError::CreatorAccountNotFound)?;
+
+                let royalty_transfer_ctx = CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.buyer.to_account_info(),
+                        to: creator_account.clone(),
+                    },
+                );
+                anchor_lang::system_program::transfer(royalty_transfer_ctx, creator.amount)?;
+            }
+        }
+
+        // Transfer payment to seller (minus platform fee and royalties)
+        let payment_transfer_ctx = CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            anchor_lang::system_program::Transfer {
+                from: ctx.accounts.buyer.to_account_info(),
+                to: ctx.accounts.seller.to_account_info(),
+            },
+        );
+        anchor_lang::system_program::transfer(payment_transfer_ctx, seller_proceeds)?;
+
+        // Transfer every NFT to the buyer
+        let bundle_id_bytes = bundle.bundle_id.to_le_bytes();
+        let seeds = &[
+            b"bundle",
+            bundle.seller.as_ref(),
+            bundle_id_bytes.as_ref(),
+            &[bundle.bump],
+        ];
+        let signer = &[&seeds[..]];
+
+        for item in items.chunks(4) {
+            let (mint_account, bundle_token_account, buyer_token_account) = (&item[0], &item[1], &item[2]);
+
+            // Like `buy_nft`, the buyer's token accounts are created when missing
+            let create_ctx = CpiContext::new(
+                ctx.accounts.associated_token_program.to_account_info(),
+                Create {
+                    payer: ctx.accounts.buyer.to_account_info(),
+                    associated_token: buyer_token_account.clone(),
+                    authority: ctx.accounts.buyer.to_account_info(),
+                    mint: mint_account.clone(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                    token_program: ctx.accounts.token_program.to_account_info(),
+                },
+            );
+            associated_token::create_idempotent(create_ctx)?;
+
+            let nft_transfer_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
+                    from: bundle_token_account.clone(),
+                    to: buyer_token_account.clone(),
+                    authority: ctx.accounts.bundle.to_account_info(),
+                },
+                signer,
+            );
+            token::transfer(nft_transfer_ctx, 1)?;
+
+            // The seller paid for the bundle token accounts when listing
+            let close_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                CloseAccount {
+                    account: bundle_token_account.clone(),
+                    destination: ctx.accounts.seller.to_account_info(),
+                    authority: ctx.accounts.bundle.to_account_info(),
+                },
+                signer,
+            );
+            token::close_account(close_ctx)?;
+        }
+
+        // Mark bundle as inactive
+        let bundle = &mut ctx.accounts.bundle;
+        bundle.is_active = false;
+
+        // Update marketplace stats via CPI, signed by our sale authority
+        let sale_authority_seeds = &[SALE_AUTHORITY_SEED, &[ctx.bumps.sale_authority]];
+        let sale_authority_signer = &[&sale_authority_seeds[..]];
+        let cpi_accounts = marketplace::cpi::accounts::UpdateStats {
+            marketplace: ctx.accounts.marketplace.to_account_info(),
+            sale_authority: ctx.accounts.sale_authority.to_account_info(),
+            caller_program: ctx.accounts.listing_program.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new_with_signer(
+            ctx.accounts.marketplace_program.to_account_info(),
+            cpi_accounts,
+            sale_authority_signer,
+        );
+        marketplace::cpi::update_stats(cpi_ctx, sale_price)?;
+
+        emit!(BundleSold {
+            bundle: bundle.key(),
+            seller: bundle.seller,
+            buyer: buyer_key,
+            mints: bundle.mints.clone(),
+            price: sale_price,
+            platform_fee,
+            royalty_fee,
+            seller_proceeds,
+            creators: creator_royalties,
+        });
+
+        Ok(())
+    }
+}
+
+#[derive(Accounts)]
+pub struct ListNft<'info> {
+    #[account(
+        init_if_needed,
+        payer = seller,
+        space = 8 + ListingState::INIT_SPACE,
+        seeds = [b"listing", mint.key().as_ref(), seller.key().as_ref()],
+        bump
+    )]
+    pub listing: Account<'info, ListingState>,
+    
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = seller,
+        constraint = seller_token_account.amount == 1
+    )]
+    pub seller_token_account: Account<'info, TokenAccount>,
+    
+    // Anyone can create the listing PDA's ATA ahead of a relist, so an existing empty one is reused
+    #[account(
+        init_if_needed,
+        payer = seller,
+        associated_token::mint = mint,
+        associated_token::authority = listing
+    )]
+    pub listing_token_account: Account<'info, TokenAccount>,
+    
+    /// CHECK: Metadata account for the NFT - using UncheckedAccount since mpl_token_metadata::accounts::Metadata doesn't implement required traits
+    #[account(
+        constraint = metadata.key() == find_metadata_account(&mint.key()).0
+    )]
+    pub metadata: UncheckedAccount<'info>,
+    
+    #[account(
+        seeds = [MARKETPLACE_SEED],
+        bump = marketplace.bump,
+        seeds::program = marketplace::ID
+    )]
+    pub marketplace: Account<'info, MarketplaceState>,
+    
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateListing<'info> {
+    /// CHECK: Listing under an older layout that `Account` cannot decode, checked by owner and
+    /// discriminator
+    #[account(mut, owner = crate::ID)]
+    pub listing: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CloseListing<'info> {
+    #[account(
+        mut,
+        seeds = [b"listing", listing.mint.as_ref(), listing.seller.as_ref()],
+        bump = listing.bump,
+        has_one = seller,
+        close = seller
+    )]
+    pub listing: Account<'info, ListingState>,
+    
+    #[account(mut)]
+    pub seller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateListing<'info> {
+    #[account(
+        mut,
+        seeds = [b"listing", listing.mint.as_ref(), listing.seller.as_ref()],
+        bump = listing.bump,
+        has_one = seller
+    )]
+    pub listing: Account<'info, ListingState>,
+    
+    pub seller: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelListing<'info> {
+    #[account(
+        mut,
+        seeds = [b"listing", listing.mint.as_ref(), listing.seller.as_ref()],
+        bump = listing.bump,
+        has_one = seller,
+        close = seller
+    )]
+    pub listing: Account<'info, ListingState>,
+    
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    
+    #[account(
+        mut,
+        associated_token::mint = listing.mint,
+        associated_token::authority = listing
+    )]
+    pub listing_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        associated_token::mint = listing.mint,
+        associated_token::authority = seller
+    )]
+    pub seller_token_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct BuyNft<'info> {
+    #[account(
+        mut,
+        seeds = [b"listing", listing.mint.as_ref(), listing.seller.as_ref()],
+        bump = listing.bump
+    )]
+    pub listing: Account<'info, ListingState>,
+    
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    /// CHECK: Seller account for payment
+    #[account(
+        mut,
+        constraint = seller.key() == listing.seller
+    )]
+    pub seller: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        associated_token::mint = listing.mint,
+        associated_token::authority = listing
+    )]
+    pub listing_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        init_if_needed,
+        payer = buyer,
+        associated_token::mint = mint,
+        associated_token::authority = buyer
+    )]
+    pub buyer_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        constraint = mint.key() == listing.mint
+    )]
+    pub mint: Account<'info, Mint>,
+    
+    /// CHECK: Metadata account for the NFT, deserialized by `load_metadata`
+    #[account(
+        constraint = metadata.key() == find_metadata_account(&mint.key()).0
+    )]
+    pub metadata: UncheckedAccount<'info>,
+    
+    #[account(
+        seeds = [b"royalty_config"],
+        bump = royalty_config.bump,
+        seeds::program = royalty::ID
+    )]
+    pub royalty_config: Account<'info, RoyaltyConfig>,
+    
+    #[account(
+        mut,
+        seeds = [MARKETPLACE_SEED],
+        bump = marketplace.bump,
+        seeds::program = marketplace::ID
+    )]
+    pub marketplace: Account<'info, MarketplaceState>,
+    
+    /// CHECK: Treasury account from marketplace
+    #[account(
+        mut,
+        constraint = treasury.key() == marketplace.treasury
+    )]
+    pub treasury: AccountInfo<'info>,
+    
+    /// Buyer's token account for SPL-priced listings
+    #[account(
+        mut,
+        token::authority = buyer
+    )]
+    pub buyer_payment_account: Option<Account<'info, TokenAccount>>,
+    
+    /// Seller's payment mint ATA for SPL-priced listings
+    #[account(mut)]
+    pub seller_payment_account: Option<Account<'info, TokenAccount>>,
+    
+    /// Treasury's payment mint ATA for SPL-priced listings
+    #[account(mut)]
+    pub treasury_payment_account: Option<Account<'info, TokenAccount>>,
+    
+    /// CHECK: PDA signer proving the stats update comes from this program
+    #[account(
+        seeds = [SALE_AUTHORITY_SEED],
+        bump
+    )]
+    pub sale_authority: UncheckedAccount<'info>,
+    
+    pub listing_program: Program<'info, crate::program::Listing>,
+    pub marketplace_program: Program<'info, Marketplace>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+pub struct RecoverExpiredListing<'info> {
+    #[account(
+        mut,
+        seeds = [b"listing", listing.mint.as_ref(), listing.seller.as_ref()],
+        bump = listing.bump,
+        close = seller
+    )]
+    pub listing: Account<'info, ListingState>,
+    
+    /// CHECK: Can be called by anyone for expired listings
+    pub caller: Signer<'info>,
+    
+    /// CHECK: Seller receiving the listing rent back
+    #[account(
+        mut,
+        constraint = seller.key() == listing.seller
+    )]
+    pub seller: AccountInfo<'info>,
+    
+    #[account(
+        mut,
+        associated_token::mint = listing.mint,
+        associated_token::authority = listing
+    )]
+    pub listing_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        associated_token::mint = listing.mint,
+        associated_token::authority = listing.seller
+    )]
+    pub seller_token_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+#[instruction(collection: Pubkey)]
+pub struct MakeCollectionOffer<'info> {
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + CollectionOfferState::INIT_SPACE,
+        seeds = [b"collection_offer", collection.as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub offer: Account<'info, CollectionOfferState>,
+    
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    #[account(
+        seeds = [MARKETPLACE_SEED],
+        bump = marketplace.bump,
+        seeds::program = marketplace::ID
+    )]
+    pub marketplace: Account<'info, MarketplaceState>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelCollectionOffer<'info> {
+    #[account(
+        mut,
+        seeds = [b"collection_offer", offer.collection.as_ref(), offer.buyer.as_ref()],
+        bump = offer.bump,
+        has_one = buyer,
+        close = buyer
+    )]
+    pub offer: Account<'info, CollectionOfferState>,
+    
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptCollectionOffer<'info> {
+    #[account(
+        mut,
+        seeds = [b"collection_offer", offer.collection.as_ref(), offer.buyer.as_ref()],
+        bump = offer.bump
+    )]
+    pub offer: Account<'info, CollectionOfferState>,
+    
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    
+    /// CHECK: Offer maker, receives the NFT and the offer rent once it is filled
+    #[account(
+        mut,
+        constraint = buyer.key() == offer.buyer
+    )]
+    pub buyer: AccountInfo<'info>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    /// CHECK: Metadata account for the NFT, deserialized by `load_metadata`
+    #[account(
+        constraint = metadata.key() == find_metadata_account(&mint.key()).0
+    )]
+    pub metadata: UncheckedAccount<'info>,
+    
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = seller,
+        constraint = seller_token_account.amount == 1
+    )]
+    pub seller_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        init_if_needed,
+        payer = seller,
+        associated_token::mint = mint,
+        associated_token::authority = buyer
+    )]
+    pub buyer_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        seeds = [b"royalty_config"],
+        bump = royalty_config.bump,
+        seeds::program = royalty::ID
+    )]
+    pub royalty_config: Account<'info, RoyaltyConfig>,
+    
+    #[account(
+        mut,
+        seeds = [MARKETPLACE_SEED],
+        bump = marketplace.bump,
+        seeds::program = marketplace::ID
+    )]
+    pub marketplace: Account<'info, MarketplaceState>,
+    
+    /// CHECK: Treasury account from marketplace
+    #[account(
+        mut,
+        constraint = treasury.key() == marketplace.treasury
+    )]
+    pub treasury: AccountInfo<'info>,
+    
+    /// CHECK: PDA signer proving the stats update comes from this program
+    #[account(
+        seeds = [SALE_AUTHORITY_SEED],
+        bump
+    )]
+    pub sale_authority: UncheckedAccount<'info>,
+    
+    pub listing_program: Program<'info, crate::program::Listing>,
+    pub marketplace_program: Program<'info, Marketplace>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MakeOffer<'info> {
+    #[account(
+        init,
+        payer = buyer,
+        space = 8 + OfferState::INIT_SPACE,
+        seeds = [b"offer", mint.key().as_ref(), buyer.key().as_ref()],
+        bump
+    )]
+    pub offer: Account<'info, OfferState>,
+    
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    pub mint: Account<'info, Mint>,
+    
+    #[account(
+        seeds = [MARKETPLACE_SEED],
+        bump = marketplace.bump,
+        seeds::program = marketplace::ID
+    )]
+    pub marketplace: Account<'info, MarketplaceState>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelOffer<'info> {
+    #[account(
+        mut,
+        seeds = [b"offer", offer.mint.as_ref(), offer.buyer.as_ref()],
+        bump = offer.bump,
+        has_one = buyer,
+        close = buyer
+    )]
+    pub offer: Account<'info, OfferState>,
+    
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptOffer<'info> {
+    #[account(
+        mut,
+        seeds = [b"offer", offer.mint.as_ref(), offer.buyer.as_ref()],
+        bump = offer.bump,
+        close = buyer
+    )]
+    pub offer: Account<'info, OfferState>,
+    
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    
+    /// CHECK: Offer maker, receives the NFT and the offer account rent
+    #[account(
+        mut,
+        constraint = buyer.key() == offer.buyer
+    )]
+    pub buyer: AccountInfo<'info>,
+    
+    /// Seller's listing for the mint, canceled on acceptance if active
+    #[account(
+        mut,
+        seeds = [b"listing", mint.key().as_ref(), seller.key().as_ref()],
+        bump = listing.bump
     )]
-    pub listing: Account<'info, ListingState>,
+    pub listing: Option<Account<'info, ListingState>>,
     
-    /// CHECK: Can be called by anyone for expired listings
+    /// Listing escrow holding the NFT while the listing is active
+    #[account(mut)]
+    pub listing_token_account: Option<Account<'info, TokenAccount>>,
+    
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = seller
+    )]
+    pub seller_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        init_if_needed,
+        payer = seller,
+        associated_token::mint = mint,
+        associated_token::authority = buyer
+    )]
+    pub buyer_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        constraint = mint.key() == offer.mint
+    )]
+    pub mint: Account<'info, Mint>,
+    
+    /// CHECK: Metadata account for the NFT, deserialized by `load_metadata`
+    #[account(
+        constraint = metadata.key() == find_metadata_account(&mint.key()).0
+    )]
+    pub metadata: UncheckedAccount<'info>,
+    
+    #[account(
+        seeds = [b"royalty_config"],
+        bump = royalty_config.bump,
+        seeds::program = royalty::ID
+    )]
+    pub royalty_config: Account<'info, RoyaltyConfig>,
+    
+    #[account(
+        mut,
+        seeds = [MARKETPLACE_SEED],
+        bump = marketplace.bump,
+        seeds::program = marketplace::ID
+    )]
+    pub marketplace: Account<'info, MarketplaceState>,
+    
+    /// CHECK: Treasury account from marketplace
+    #[account(
+        mut,
+        constraint = treasury.key() == marketplace.treasury
+    )]
+    pub treasury: AccountInfo<'info>,
+    
+    /// CHECK: PDA signer proving the stats update comes from this program
+    #[account(
+        seeds = [SALE_AUTHORITY_SEED],
+        bump
+    )]
+    pub sale_authority: UncheckedAccount<'info>,
+    
+    pub listing_program: Program<'info, crate::program::Listing>,
+    pub marketplace_program: Program<'info, Marketplace>,
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(bundle_id: u64)]
+pub struct CreateBundleListing<'info> {
+    #[account(
+        init,
+        payer = seller,
+        space = 8 + BundleListingState::INIT_SPACE,
+        seeds = [b"bundle", seller.key().as_ref(), bundle_id.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub bundle: Account<'info, BundleListingState>,
+    
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    
+    #[account(
+        seeds = [MARKETPLACE_SEED],
+        bump = marketplace.bump,
+        seeds::program = marketplace::ID
+    )]
+    pub marketplace: Account<'info, MarketplaceState>,
+    
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CancelBundleListing<'info> {
+    #[account(
+        mut,
+        seeds = [b"bundle", bundle.seller.as_ref(), bundle.bundle_id.to_le_bytes().as_ref()],
+        bump = bundle.bump,
+        has_one = seller,
+        close = seller
+    )]
+    pub bundle: Account<'info, BundleListingState>,
+    
+    #[account(mut)]
+    pub seller: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RecoverExpiredBundle<'info> {
+    #[account(
+        mut,
+        seeds = [b"bundle", bundle.seller.as_ref(), bundle.bundle_id.to_le_bytes().as_ref()],
+        bump = bundle.bump,
+        close = seller
+    )]
+    pub bundle: Account<'info, BundleListingState>,
+    
+    /// CHECK: Can be called by anyone for expired bundles
     pub caller: Signer<'info>,
     
+    /// CHECK: Seller receiving the bundle rent back
     #[account(
         mut,
-        associated_token::mint = listing.mint,
-        associated_token::authority = listing
+        constraint = seller.key() == bundle.seller
     )]
-    pub listing_token_account: Account<'info, TokenAccount>,
+    pub seller: AccountInfo<'info>,
     
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct BuyBundle<'info> {
     #[account(
         mut,
-        associated_token::mint = listing.mint,
-        associated_token::authority = listing.seller
+        seeds = [b"bundle", bundle.seller.as_ref(), bundle.bundle_id.to_le_bytes().as_ref()],
+        bump = bundle.bump,
+        close = seller
     )]
-    pub seller_token_account: Account<'info, TokenAccount>,
+    pub bundle: Account<'info, BundleListingState>,
+    
+    #[account(mut)]
+    pub buyer: Signer<'info>,
+    
+    /// CHECK: Seller account for payment
+    #[account(
+        mut,
+        constraint = seller.key() == bundle.seller
+    )]
+    pub seller: AccountInfo<'info>,
+    
+    #[account(
+        seeds = [b"royalty_config"],
+        bump = royalty_config.bump,
+        seeds::program = royalty::ID
+    )]
+    pub royalty_config: Account<'info, RoyaltyConfig>,
+    
+    #[account(
+        mut,
+        seeds = [MARKETPLACE_SEED],
+        bump = marketplace.bump,
+        seeds::program = marketplace::ID
+    )]
+    pub marketplace: Account<'info, MarketplaceState>,
+    
+    /// CHECK: Treasury account from marketplace
+    #[account(
+        mut,
+        constraint = treasury.key() == marketplace.treasury
+    )]
+    pub treasury: AccountInfo<'info>,
     
+    /// CHECK: PDA signer proving the stats update comes from this program
+    #[account(
+        seeds = [SALE_AUTHORITY_SEED],
+        bump
+    )]
+    pub sale_authority: UncheckedAccount<'info>,
+    
+    pub listing_program: Program<'info, crate::program::Listing>,
+    pub marketplace_program: Program<'info, Marketplace>,
     pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
 }
 
 #[account]
@@ -498,10 +1838,166 @@ pub struct ListingState {
     pub expiry: Option<i64>,         // 1 + 8
     pub is_active: bool,             // 1
     pub bump: u8,                    // 1
+    // Fields below were appended after launch; `migrate_listing` grows older accounts
+    pub payment_mint: Option<Pubkey>, // 1 + 32 (None = lamports)
+    pub buyer: Option<Pubkey>,       // 1 + 32 (set once sold, kept until `close_listing`)
+    pub last_sold_at: i64,           // 8 (latest sale, kept across relists; 0 = never sold)
 }
 
 impl ListingState {
-    pub const INIT_SPACE: usize = 32 + 32 + 8 + 8 + 1 + 8 + 1 + 1; // 91 bytes
+    pub const INIT_SPACE: usize = 32 + 32 + 8 + 8 + 1 + 8 + 1 + 1 + 1 + 32 + 1 + 32 + 8; // 165 bytes
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct CollectionOfferState {
+    pub buyer: Pubkey,               // 32
+    pub collection: Pubkey,          // 32 (verified collection mint)
+    pub price: u64,                  // 8 (lamports per NFT)
+    pub quantity: u32,               // 4 (NFTs still wanted)
+    pub filled: u32,                 // 4
+    pub created_at: i64,             // 8
+    pub expiry: Option<i64>,         // 1 + 8
+    pub bump: u8,                    // 1
+}
+
+impl CollectionOfferState {
+    pub const INIT_SPACE: usize = 32 + 32 + 8 + 4 + 4 + 8 + 1 + 8 + 1; // 98 bytes
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct BundleListingState {
+    pub seller: Pubkey,              // 32
+    pub bundle_id: u64,              // 8
+    pub price: u64,                  // 8 (lamports for the whole bundle)
+    #[max_len(MAX_BUNDLE_SIZE)]
+    pub mints: Vec<Pubkey>,          // 4 + 32 * MAX_BUNDLE_SIZE
+    pub created_at: i64,             // 8
+    pub expiry: Option<i64>,         // 1 + 8
+    pub is_active: bool,             // 1
+    pub bump: u8,                    // 1
+}
+
+impl BundleListingState {
+    pub const INIT_SPACE: usize = 32 + 8 + 8 + 4 + 32 * MAX_BUNDLE_SIZE + 8 + 1 + 8 + 1 + 1; // 327 bytes
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct OfferState {
+    pub buyer: Pubkey,               // 32
+    pub mint: Pubkey,                // 32
+    pub amount: u64,                 // 8 (lamports)
+    pub created_at: i64,             // 8
+    pub expiry: Option<i64>,         // 1 + 8
+    pub bump: u8,                    // 1
+}
+
+impl OfferState {
+    pub const INIT_SPACE: usize = 32 + 32 + 8 + 8 + 1 + 8 + 1; // 90 bytes
+}
+
+/// Move every NFT of a bundle back to its seller and close the emptied bundle token accounts
+///
+/// `items` holds `[bundle_token_account, seller_token_account]` per item, in bundle order
+fn return_bundle_items<'info>(
+    bundle: &Account<'info, BundleListingState>,
+    token_program: &Program<'info, Token>,
+    seller: &AccountInfo<'info>,
+    items: &[AccountInfo<'info>],
+) -> Result<()> {
+    require!(items.len() == bundle.mints.len() * 2, ListingError::InvalidBundleAccounts);
+
+    let bundle_key = bundle.key();
+    let bundle_id_bytes = bundle.bundle_id.to_le_bytes();
+    let seeds = &[
+        b"bundle",
+        bundle.seller.as_ref(),
+        bundle_id_bytes.as_ref(),
+        &[bundle.bump],
+    ];
+    let signer = &[&seeds[..]];
+
+    for (mint, item) in bundle.mints.iter().zip(items.chunks(2)) {
+        let (bundle_token_account, seller_token_account) = (&item[0], &item[1]);
+        require_keys_eq!(
+            bundle_token_account.key(),
+            get_associated_token_address(&bundle_key, mint),
+            ListingError::InvalidBundleAccounts
+        );
+        require_keys_eq!(
+            seller_token_account.key(),
+            get_associated_token_address(&bundle.seller, mint),
+            ListingError::InvalidBundleAccounts
+        );
+
+        let transfer_ctx = CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            Transfer {
+                from: bundle_token_account.clone(),
+                to: seller_token_account.clone(),
+                authority: bundle.to_account_info(),
+            },
+            signer,
+        );
+        token::transfer(transfer_ctx, 1)?;
+
+        let close_ctx = CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            CloseAccount {
+                account: bundle_token_account.clone(),
+                destination: seller.clone(),
+                authority: bundle.to_account_info(),
+            },
+            signer,
+        );
+        token::close_account(close_ctx)?;
+    }
+
+    Ok(())
+}
+
+// Split lamports escrowed in an offer account between treasury, creators and seller
+fn pay_from_offer_escrow<'info>(
+    escrow: &AccountInfo<'info>,
+    treasury: &AccountInfo<'info>,
+    seller: &AccountInfo<'info>,
+    creator_accounts: &[AccountInfo<'info>],
+    creator_royalties: &[CreatorRoyalty],
+    platform_fee: u64,
+    seller_proceeds: u64,
+) -> Result<()> {
+    if platform_fee > 0 {
+        move_lamports(escrow, treasury, platform_fee)?;
+    }
+
+    // Pay creator royalties to the accounts passed in remaining accounts
+    for creator in creator_royalties {
+        if creator.amount > 0 {
+            let creator_account = creator_accounts
+                .iter()
+                .find(|acc| acc.key() == creator.address)
+                .ok_or(ListingError::CreatorAccountNotFound)?;
+
+            move_lamports(escrow, creator_account, creator.amount)?;
+        }
+    }
+
+    move_lamports(escrow, seller, seller_proceeds)?;
+
+    Ok(())
+}
+
+/// Move lamports out of a program-owned offer account
+fn move_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) -> Result<()> {
+    let remaining = from.lamports().checked_sub(amount)
+        .ok_or(ListingError::MathOverflow)?;
+    let received = to.lamports().checked_add(amount)
+        .ok_or(ListingError::MathOverflow)?;
+    **from.lamports.borrow_mut() = remaining;
+    **to.lamports.borrow_mut() = received;
+    Ok(())
 }
 
 // Helper function to find metadata account
@@ -522,9 +2018,25 @@ pub struct NftListed {
     pub seller: Pubkey,
     pub mint: Pubkey,
     pub price: u64,
+    pub payment_mint: Option<Pubkey>,
     pub expiry: Option<i64>,
 }
 
+#[event]
+pub struct ListingMigrated {
+    pub listing: Pubkey,
+    pub old_len: u32,
+    pub new_len: u32,
+}
+
+#[event]
+pub struct ListingClosed {
+    pub listing: Pubkey,
+    pub seller: Pubkey,
+    pub mint: Pubkey,
+    pub buyer: Option<Pubkey>,
+}
+
 #[event]
 pub struct ListingUpdated {
     pub listing: Pubkey,
@@ -548,7 +2060,11 @@ pub struct NftSold {
     pub buyer: Pubkey,
     pub mint: Pubkey,
     pub price: u64,
+    pub payment_mint: Option<Pubkey>,
     pub platform_fee: u64,
+    pub royalty_fee: u64,
+    pub seller_proceeds: u64,
+    pub creators: Vec<CreatorRoyalty>,
 }
 
 #[event]
@@ -558,6 +2074,106 @@ pub struct ExpiredListingRecovered {
     pub mint: Pubkey,
 }
 
+#[event]
+pub struct CollectionOfferMade {
+    pub offer: Pubkey,
+    pub buyer: Pubkey,
+    pub collection: Pubkey,
+    pub price: u64,
+    pub quantity: u32,
+    pub expiry: Option<i64>,
+}
+
+#[event]
+pub struct CollectionOfferCanceled {
+    pub offer: Pubkey,
+    pub buyer: Pubkey,
+    pub collection: Pubkey,
+    pub refund_amount: u64,
+}
+
+#[event]
+pub struct CollectionOfferAccepted {
+    pub offer: Pubkey,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub mint: Pubkey,
+    pub collection: Pubkey,
+    pub price: u64,
+    pub platform_fee: u64,
+    pub royalty_fee: u64,
+    pub seller_proceeds: u64,
+    pub remaining_quantity: u32,
+    pub creators: Vec<CreatorRoyalty>,
+}
+
+#[event]
+pub struct OfferMade {
+    pub offer: Pubkey,
+    pub buyer: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub expiry: Option<i64>,
+}
+
+#[event]
+pub struct OfferCanceled {
+    pub offer: Pubkey,
+    pub buyer: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct OfferAccepted {
+    pub offer: Pubkey,
+    pub buyer: Pubkey,
+    pub seller: Pubkey,
+    pub mint: Pubkey,
+    pub price: u64,
+    pub platform_fee: u64,
+    pub royalty_fee: u64,
+    pub seller_proceeds: u64,
+    pub creators: Vec<CreatorRoyalty>,
+}
+
+#[event]
+pub struct BundleListed {
+    pub bundle: Pubkey,
+    pub seller: Pubkey,
+    pub bundle_id: u64,
+    pub mints: Vec<Pubkey>,
+    pub price: u64,
+    pub expiry: Option<i64>,
+}
+
+#[event]
+pub struct BundleListingCanceled {
+    pub bundle: Pubkey,
+    pub seller: Pubkey,
+    pub bundle_id: u64,
+}
+
+#[event]
+pub struct ExpiredBundleRecovered {
+    pub bundle: Pubkey,
+    pub seller: Pubkey,
+    pub bundle_id: u64,
+}
+
+#[event]
+pub struct BundleSold {
+    pub bundle: Pubkey,
+    pub seller: Pubkey,
+    pub buyer: Pubkey,
+    pub mints: Vec<Pubkey>,
+    pub price: u64,
+    pub platform_fee: u64,
+    pub royalty_fee: u64,
+    pub seller_proceeds: u64,
+    pub creators: Vec<CreatorRoyalty>,
+}
+
 #[error_code]
 pub enum ListingError {
     #[msg("Invalid price provided")]
@@ -578,7 +2194,36 @@ pub enum ListingError {
     MathOverflow,
     #[msg("Insufficient funds")]
     InsufficientFunds,
-}
-
-// Re-export for external access
-pub use marketplace::MarketplaceState;
\ No newline at end of file
+    #[msg("Creator account not found")]
+    CreatorAccountNotFound,
+    #[msg("Payment mint is not accepted by the marketplace")]
+    PaymentMintNotAccepted,
+    #[msg("Payment token account missing for SPL-priced listing")]
+    PaymentAccountMissing,
+    #[msg("Payment token account does not match the listing's payment mint")]
+    InvalidPaymentAccount,
+    #[msg("Offer quantity must be greater than zero")]
+    InvalidQuantity,
+    #[msg("Offer has expired")]
+    OfferExpired,
+    #[msg("Offer has been completely filled")]
+    OfferFilled,
+    #[msg("NFT does not belong to a verified collection")]
+    CollectionNotVerified,
+    #[msg("NFT collection does not match the offer")]
+    CollectionMismatch,
+    #[msg("Listing token account is required to accept an offer on a listed NFT")]
+    ListingTokenAccountMissing,
+    #[msg("Bundle must hold between 2 and MAX_BUNDLE_SIZE NFTs")]
+    InvalidBundleSize,
+    #[msg("Bundle item accounts are missing or do not match the bundle")]
+    InvalidBundleAccounts,
+    #[msg("Bundle cannot contain the same mint twice")]
+    DuplicateBundleMint,
+    #[msg("Listing already uses the current layout")]
+    ListingAlreadyMigrated,
+    #[msg("Account is not a listing")]
+    InvalidListingAccount,
+    #[msg("Listing is still active")]
+    ListingStillActive,
+}
\ No newline at end of file
@@ -1,9 +1,20 @@
 #![allow(unexpected_cfgs)]
 #![allow(deprecated)]
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Token, TokenAccount};
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
 
-declare_id!("MKTpLcXGQHkihHzwwxNX6Zw4YXtWWPzkjHRBEtcSWh3");
+// Shared state, seeds and fee math live in `marketplace-common` so every
+// program deserializes the exact same account layout.
+pub use marketplace_common::{
+    calculate_fee, check_marketplace_active, MarketplaceError, MarketplaceState,
+    BASIS_POINTS_DENOMINATOR, DEFAULT_MAX_AUCTION_DURATION, DEFAULT_MAX_EXTENSION_AMOUNT,
+    DEFAULT_MAX_EXTENSION_WINDOW, DEFAULT_MIN_AUCTION_DURATION, MARKETPLACE_SEED, MARKETPLACE_VERSION,
+    MAX_FEE_BASIS_POINTS, MAX_PAYMENT_MINTS, MAX_REGISTERED_PROGRAMS, SALE_AUTHORITY_SEED,
+    TREASURY_SEED,
+};
+
+declare_id!("DQGMnpU1EvMJchnp9qmo8Nfb8nmmwRuk2MT4AkSyiMQu");
 
 #[program]
 pub mod marketplace {
@@ -15,7 +26,7 @@ pub mod marketplace {
         fee_basis_points: u16,
         treasury_bump: u8,
     ) -> Result<()> {
-        require!(fee_basis_points <= 1000, MarketplaceError::FeeTooHigh); // Max 10%
+        require!(fee_basis_points <= MAX_FEE_BASIS_POINTS, MarketplaceError::FeeTooHigh); // Max 10%
         
         let marketplace = &mut ctx.accounts.marketplace;
         marketplace.authority = ctx.accounts.authority.key();
@@ -26,6 +37,13 @@ pub mod marketplace {
         marketplace.total_volume = 0;
         marketplace.total_sales = 0;
         marketplace.bump = ctx.bumps.marketplace;
+        marketplace.registered_programs = Vec::new();
+        marketplace.accepted_payment_mints = Vec::new();
+        marketplace.min_auction_duration = DEFAULT_MIN_AUCTION_DURATION;
+        marketplace.max_auction_duration = DEFAULT_MAX_AUCTION_DURATION;
+        marketplace.max_extension_window = DEFAULT_MAX_EXTENSION_WINDOW;
+        marketplace.max_extension_amount = DEFAULT_MAX_EXTENSION_AMOUNT;
+        marketplace.version = MARKETPLACE_VERSION;
 
         emit!(MarketplaceInitialized {
             authority: marketplace.authority,
@@ -36,9 +54,76 @@ pub mod marketplace {
         Ok(())
     }
 
+    /// Grow a marketplace account created under an older layout to the current one and fill
+    /// in defaults for the fields it lacked (only admin)
+    ///
+    /// Every field so far was appended, so the old data stays in place and the zero-filled tail
+    /// decodes as empty lists and zeroed bounds
+    pub fn migrate_marketplace(ctx: Context<MigrateMarketplace>) -> Result<()> {
+        let marketplace_info = ctx.accounts.marketplace.to_account_info();
+        let old_len = marketplace_info.data_len();
+        let new_len = 8 + MarketplaceState::INIT_SPACE;
+
+        // The authority is the first field of every layout
+        {
+            let data = marketplace_info.try_borrow_data()?;
+            require!(
+                old_len >= 8 + 32
+                    && data[..8] == <MarketplaceState as anchor_lang::Discriminator>::DISCRIMINATOR,
+                MarketplaceError::Unauthorized
+            );
+            require_keys_eq!(
+                Pubkey::try_from(&data[8..40]).map_err(|_| MarketplaceError::Unauthorized)?,
+                ctx.accounts.authority.key(),
+                MarketplaceError::Unauthorized
+            );
+        }
+
+        if old_len < new_len {
+            let rent_due = Rent::get()?.minimum_balance(new_len)
+                .saturating_sub(marketplace_info.lamports());
+            if rent_due > 0 {
+                let rent_ctx = CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.authority.to_account_info(),
+                        to: marketplace_info.clone(),
+                    },
+                );
+                anchor_lang::system_program::transfer(rent_ctx, rent_due)?;
+            }
+            marketplace_info.realloc(new_len, true)?;
+        }
+
+        let mut marketplace = MarketplaceState::try_deserialize(
+            &mut &marketplace_info.try_borrow_data()?[..]
+        )?;
+        let old_version = marketplace.version;
+        require!(old_version < MARKETPLACE_VERSION, MarketplaceError::AlreadyMigrated);
+
+        // Accounts from before the auction bounds were added read them back as zero
+        if marketplace.max_auction_duration == 0 {
+            marketplace.min_auction_duration = DEFAULT_MIN_AUCTION_DURATION;
+            marketplace.max_auction_duration = DEFAULT_MAX_AUCTION_DURATION;
+            marketplace.max_extension_window = DEFAULT_MAX_EXTENSION_WINDOW;
+            marketplace.max_extension_amount = DEFAULT_MAX_EXTENSION_AMOUNT;
+        }
+        marketplace.version = MARKETPLACE_VERSION;
+        marketplace.try_serialize(&mut &mut marketplace_info.try_borrow_mut_data()?[..])?;
+
+        emit!(MarketplaceMigrated {
+            old_version,
+            new_version: MARKETPLACE_VERSION,
+            old_len: old_len as u32,
+            new_len: new_len.max(old_len) as u32,
+        });
+
+        Ok(())
+    }
+
     /// Update marketplace fee (only admin)
     pub fn update_fee(ctx: Context<UpdateFee>, new_fee_basis_points: u16) -> Result<()> {
-        require!(new_fee_basis_points <= 1000, MarketplaceError::FeeTooHigh);
+        require!(new_fee_basis_points <= MAX_FEE_BASIS_POINTS, MarketplaceError::FeeTooHigh);
         
         let marketplace = &mut ctx.accounts.marketplace;
         let old_fee = marketplace.fee_basis_points;
@@ -53,6 +138,40 @@ pub mod marketplace {
         Ok(())
     }
 
+    /// Update the duration and anti-sniping bounds auctions are validated against (only admin)
+    pub fn update_auction_bounds(
+        ctx: Context<UpdateAuctionBounds>,
+        min_auction_duration: i64,
+        max_auction_duration: i64,
+        max_extension_window: i64,
+        max_extension_amount: i64,
+    ) -> Result<()> {
+        require!(
+            min_auction_duration > 0 && min_auction_duration <= max_auction_duration,
+            MarketplaceError::InvalidAuctionBounds
+        );
+        require!(
+            max_extension_window >= 0 && max_extension_amount >= 0,
+            MarketplaceError::InvalidAuctionBounds
+        );
+
+        let marketplace = &mut ctx.accounts.marketplace;
+        marketplace.min_auction_duration = min_auction_duration;
+        marketplace.max_auction_duration = max_auction_duration;
+        marketplace.max_extension_window = max_extension_window;
+        marketplace.max_extension_amount = max_extension_amount;
+
+        emit!(AuctionBoundsUpdated {
+            min_auction_duration,
+            max_auction_duration,
+            max_extension_window,
+            max_extension_amount,
+            authority: ctx.accounts.authority.key(),
+        });
+
+        Ok(())
+    }
+
     /// Update marketplace authority (only current admin)
     pub fn update_authority(ctx: Context<UpdateAuthority>, new_authority: Pubkey) -> Result<()> {
         let marketplace = &mut ctx.accounts.marketplace;
@@ -69,7 +188,6 @@ pub mod marketplace {
 
     /// Withdraw accumulated fees (only admin)
     pub fn withdraw_fees(ctx: Context<WithdrawFees>, amount: u64) -> Result<()> {
-        let marketplace = &ctx.accounts.marketplace;
         let treasury = &mut ctx.accounts.treasury;
         let authority = &ctx.accounts.authority;
 
@@ -87,6 +205,36 @@ pub mod marketplace {
         Ok(())
     }
 
+    /// Withdraw accumulated SPL token fees from a treasury ATA (only admin)
+    pub fn withdraw_token_fees(ctx: Context<WithdrawTokenFees>, amount: u64) -> Result<()> {
+        require!(
+            ctx.accounts.treasury_token_account.amount >= amount,
+            MarketplaceError::InsufficientFunds
+        );
+
+        let treasury_seeds = &[TREASURY_SEED, &[ctx.accounts.marketplace.treasury_bump]];
+        let signer = &[&treasury_seeds[..]];
+
+        let transfer_ctx = CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            Transfer {
+                from: ctx.accounts.treasury_token_account.to_account_info(),
+                to: ctx.accounts.authority_token_account.to_account_info(),
+                authority: ctx.accounts.treasury.to_account_info(),
+            },
+            signer,
+        );
+        token::transfer(transfer_ctx, amount)?;
+
+        emit!(TokenFeesWithdrawn {
+            mint: ctx.accounts.payment_mint.key(),
+            amount,
+            authority: ctx.accounts.authority.key(),
+        });
+
+        Ok(())
+    }
+
     /// Pause/unpause marketplace (only admin)
     pub fn pause_marketplace(ctx: Context<PauseMarketplace>, pause: bool) -> Result<()> {
         let marketplace = &mut ctx.accounts.marketplace;
@@ -100,7 +248,93 @@ pub mod marketplace {
         Ok(())
     }
 
-    /// Update marketplace stats (internal use by other contracts)
+    /// Allow a trading program to report sales (only admin)
+    pub fn register_trading_program(
+        ctx: Context<ManageTradingPrograms>,
+        program_id: Pubkey,
+    ) -> Result<()> {
+        let marketplace = &mut ctx.accounts.marketplace;
+        require!(
+            !marketplace.is_registered_program(&program_id),
+            MarketplaceError::ProgramAlreadyRegistered
+        );
+        require!(
+            marketplace.registered_programs.len() < MAX_REGISTERED_PROGRAMS,
+            MarketplaceError::TooManyRegisteredPrograms
+        );
+
+        marketplace.registered_programs.push(program_id);
+
+        emit!(TradingProgramRegistered {
+            program_id,
+            authority: ctx.accounts.authority.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Revoke a trading program's permission to report sales (only admin)
+    pub fn unregister_trading_program(
+        ctx: Context<ManageTradingPrograms>,
+        program_id: Pubkey,
+    ) -> Result<()> {
+        let marketplace = &mut ctx.accounts.marketplace;
+        require!(
+            marketplace.is_registered_program(&program_id),
+            MarketplaceError::ProgramNotRegistered
+        );
+
+        marketplace.registered_programs.retain(|registered| registered != &program_id);
+
+        emit!(TradingProgramUnregistered {
+            program_id,
+            authority: ctx.accounts.authority.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Accept an SPL mint as listing currency (only admin)
+    pub fn add_payment_mint(ctx: Context<ManagePaymentMints>, mint: Pubkey) -> Result<()> {
+        let marketplace = &mut ctx.accounts.marketplace;
+        require!(
+            !marketplace.is_accepted_payment_mint(&mint),
+            MarketplaceError::PaymentMintAlreadyAccepted
+        );
+        require!(
+            marketplace.accepted_payment_mints.len() < MAX_PAYMENT_MINTS,
+            MarketplaceError::TooManyPaymentMints
+        );
+
+        marketplace.accepted_payment_mints.push(mint);
+
+        emit!(PaymentMintAdded {
+            mint,
+            authority: ctx.accounts.authority.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Stop accepting an SPL mint for new listings (only admin)
+    pub fn remove_payment_mint(ctx: Context<ManagePaymentMints>, mint: Pubkey) -> Result<()> {
+        let marketplace = &mut ctx.accounts.marketplace;
+        require!(
+            marketplace.is_accepted_payment_mint(&mint),
+            MarketplaceError::PaymentMintNotAccepted
+        );
+
+        marketplace.accepted_payment_mints.retain(|accepted| accepted != &mint);
+
+        emit!(PaymentMintRemoved {
+            mint,
+            authority: ctx.accounts.authority.key(),
+        });
+
+        Ok(())
+    }
+
+    /// Update marketplace stats (CPI only, signed by a registered trading program)
     pub fn update_stats(ctx: Context<UpdateStats>, sale_amount: u64) -> Result<()> {
         let marketplace = &mut ctx.accounts.marketplace;
         marketplace.total_volume = marketplace.total_volume.checked_add(sale_amount)
@@ -113,14 +347,7 @@ pub mod marketplace {
 
     /// Calculate platform fee for a given sale amount
     pub fn calculate_fee(ctx: Context<CalculateFee>, sale_amount: u64) -> Result<u64> {
-        let marketplace = &ctx.accounts.marketplace;
-        let fee = sale_amount
-            .checked_mul(marketplace.fee_basis_points as u64)
-            .ok_or(MarketplaceError::MathOverflow)?
-            .checked_div(10000)
-            .ok_or(MarketplaceError::MathOverflow)?;
-        
-        Ok(fee)
+        ctx.accounts.marketplace.calculate_platform_fee(sale_amount)
     }
 }
 
@@ -130,7 +357,7 @@ pub struct InitializeMarketplace<'info> {
         init,
         payer = authority,
         space = 8 + MarketplaceState::INIT_SPACE,
-        seeds = [b"marketplace"],
+        seeds = [MARKETPLACE_SEED],
         bump
     )]
     pub marketplace: Account<'info, MarketplaceState>,
@@ -141,7 +368,7 @@ pub struct InitializeMarketplace<'info> {
     /// CHECK: Treasury account for collecting fees
     #[account(
         mut,
-        seeds = [b"treasury"],
+        seeds = [TREASURY_SEED],
         bump
     )]
     pub treasury: AccountInfo<'info>,
@@ -149,11 +376,41 @@ pub struct InitializeMarketplace<'info> {
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct MigrateMarketplace<'info> {
+    /// CHECK: Marketplace under an older layout that `Account` cannot decode, its authority is
+    /// checked in the handler
+    #[account(
+        mut,
+        seeds = [MARKETPLACE_SEED],
+        bump,
+        owner = crate::ID
+    )]
+    pub marketplace: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
 #[derive(Accounts)]
 pub struct UpdateFee<'info> {
     #[account(
         mut,
-        seeds = [b"marketplace"],
+        seeds = [MARKETPLACE_SEED],
+        bump = marketplace.bump,
+        has_one = authority
+    )]
+    pub marketplace: Account<'info, MarketplaceState>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateAuctionBounds<'info> {
+    #[account(
+        mut,
+        seeds = [MARKETPLACE_SEED],
         bump = marketplace.bump,
         has_one = authority
     )]
@@ -165,7 +422,7 @@ pub struct UpdateFee<'info> {
 pub struct UpdateAuthority<'info> {
     #[account(
         mut,
-        seeds = [b"marketplace"],
+        seeds = [MARKETPLACE_SEED],
         bump = marketplace.bump,
         has_one = authority
     )]
@@ -176,7 +433,7 @@ pub struct UpdateAuthority<'info> {
 #[derive(Accounts)]
 pub struct WithdrawFees<'info> {
     #[account(
-        seeds = [b"marketplace"],
+        seeds = [MARKETPLACE_SEED],
         bump = marketplace.bump,
         has_one = authority,
         has_one = treasury
@@ -189,17 +446,79 @@ pub struct WithdrawFees<'info> {
     /// CHECK: Treasury account validated by marketplace state
     #[account(
         mut,
-        seeds = [b"treasury"],
+        seeds = [TREASURY_SEED],
+        bump = marketplace.treasury_bump
+    )]
+    pub treasury: AccountInfo<'info>,
+}
+
+#[derive(Accounts)]
+pub struct WithdrawTokenFees<'info> {
+    #[account(
+        seeds = [MARKETPLACE_SEED],
+        bump = marketplace.bump,
+        has_one = authority,
+        has_one = treasury
+    )]
+    pub marketplace: Account<'info, MarketplaceState>,
+    
+    pub authority: Signer<'info>,
+    
+    /// CHECK: Treasury PDA owning the fee token accounts
+    #[account(
+        seeds = [TREASURY_SEED],
         bump = marketplace.treasury_bump
     )]
     pub treasury: AccountInfo<'info>,
+    
+    pub payment_mint: Account<'info, Mint>,
+    
+    #[account(
+        mut,
+        associated_token::mint = payment_mint,
+        associated_token::authority = treasury
+    )]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+    
+    #[account(
+        mut,
+        token::mint = payment_mint
+    )]
+    pub authority_token_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
 #[derive(Accounts)]
 pub struct PauseMarketplace<'info> {
     #[account(
         mut,
-        seeds = [b"marketplace"],
+        seeds = [MARKETPLACE_SEED],
+        bump = marketplace.bump,
+        has_one = authority
+    )]
+    pub marketplace: Account<'info, MarketplaceState>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ManageTradingPrograms<'info> {
+    #[account(
+        mut,
+        seeds = [MARKETPLACE_SEED],
+        bump = marketplace.bump,
+        has_one = authority
+    )]
+    pub marketplace: Account<'info, MarketplaceState>,
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ManagePaymentMints<'info> {
+    #[account(
+        mut,
+        seeds = [MARKETPLACE_SEED],
         bump = marketplace.bump,
         has_one = authority
     )]
@@ -211,34 +530,34 @@ pub struct PauseMarketplace<'info> {
 pub struct UpdateStats<'info> {
     #[account(
         mut,
-        seeds = [b"marketplace"],
-        bump = marketplace.bump
+        seeds = [MARKETPLACE_SEED],
+        bump = marketplace.bump,
+        constraint = marketplace.is_registered_program(&caller_program.key()) @ MarketplaceError::ProgramNotRegistered
     )]
     pub marketplace: Account<'info, MarketplaceState>,
+    
+    /// Sale authority PDA only the calling program can sign for
+    #[account(
+        seeds = [SALE_AUTHORITY_SEED],
+        bump,
+        seeds::program = caller_program.key()
+    )]
+    pub sale_authority: Signer<'info>,
+    
+    /// CHECK: Trading program that settled the sale, checked against the registry
+    #[account(executable)]
+    pub caller_program: UncheckedAccount<'info>,
 }
 
 #[derive(Accounts)]
 pub struct CalculateFee<'info> {
     #[account(
-        seeds = [b"marketplace"],
+        seeds = [MARKETPLACE_SEED],
         bump = marketplace.bump
     )]
     pub marketplace: Account<'info, MarketplaceState>,
 }
 
-#[account]
-#[derive(InitSpace)]
-pub struct MarketplaceState {
-    pub authority: Pubkey,           // 32
-    pub fee_basis_points: u16,       // 2 (e.g., 250 = 2.5%)
-    pub treasury: Pubkey,            // 32
-    pub treasury_bump: u8,           // 1
-    pub is_paused: bool,             // 1
-    pub total_volume: u64,           // 8
-    pub total_sales: u64,            // 8
-    pub bump: u8,                    // 1
-}
-
 #[event]
 pub struct MarketplaceInitialized {
     pub authority: Pubkey,
@@ -246,6 +565,14 @@ pub struct MarketplaceInitialized {
     pub treasury: Pubkey,
 }
 
+#[event]
+pub struct MarketplaceMigrated {
+    pub old_version: u8,
+    pub new_version: u8,
+    pub old_len: u32,
+    pub new_len: u32,
+}
+
 #[event]
 pub struct FeeUpdated {
     pub old_fee: u16,
@@ -253,6 +580,15 @@ pub struct FeeUpdated {
     pub authority: Pubkey,
 }
 
+#[event]
+pub struct AuctionBoundsUpdated {
+    pub min_auction_duration: i64,
+    pub max_auction_duration: i64,
+    pub max_extension_window: i64,
+    pub max_extension_amount: i64,
+    pub authority: Pubkey,
+}
+
 #[event]
 pub struct AuthorityUpdated {
     pub old_authority: Pubkey,
@@ -265,54 +601,39 @@ pub struct FeesWithdrawn {
     pub authority: Pubkey,
 }
 
+#[event]
+pub struct TokenFeesWithdrawn {
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub authority: Pubkey,
+}
+
 #[event]
 pub struct MarketplacePaused {
     pub is_paused: bool,
     pub authority: Pubkey,
 }
 
-#[error_code]
-pub enum MarketplaceError {
-    #[msg("Fee basis points cannot exceed 1000 (10%)")]
-    FeeTooHigh,
-    #[msg("Insufficient funds in treasury")]
-    InsufficientFunds,
-    #[msg("Marketplace is currently paused")]
-    MarketplacePaused,
-    #[msg("Math overflow occurred")]
-    MathOverflow,
-    #[msg("Unauthorized access")]
-    Unauthorized,
+#[event]
+pub struct TradingProgramRegistered {
+    pub program_id: Pubkey,
+    pub authority: Pubkey,
 }
 
-// Helper functions for other contracts to use
-impl MarketplaceState {
-    pub const INIT_SPACE: usize = 32 + 2 + 32 + 1 + 1 + 8 + 8 + 1; // 85 bytes
-    
-    pub fn is_paused(&self) -> bool {
-        self.is_paused
-    }
-    
-    pub fn get_fee_basis_points(&self) -> u16 {
-        self.fee_basis_points
-    }
-    
-    pub fn get_treasury(&self) -> Pubkey {
-        self.treasury
-    }
-    
-    pub fn calculate_platform_fee(&self, sale_amount: u64) -> Result<u64> {
-        sale_amount
-            .checked_mul(self.fee_basis_points as u64)
-            .ok_or(MarketplaceError::MathOverflow)?
-            .checked_div(10000)
-            .ok_or(MarketplaceError::MathOverflow)
-            .map_err(|e| e.into())
-    }
+#[event]
+pub struct TradingProgramUnregistered {
+    pub program_id: Pubkey,
+    pub authority: Pubkey,
+}
+
+#[event]
+pub struct PaymentMintAdded {
+    pub mint: Pubkey,
+    pub authority: Pubkey,
 }
 
-// Cross-program invocation helper for other contracts
-pub fn check_marketplace_active(marketplace: &Account<MarketplaceState>) -> Result<()> {
-    require!(!marketplace.is_paused, MarketplaceError::MarketplacePaused);
-    Ok(())
-}
\ No newline at end of file
+#[event]
+pub struct PaymentMintRemoved {
+    pub mint: Pubkey,
+    pub authority: Pubkey,
+}
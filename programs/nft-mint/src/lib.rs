@@ -1,6 +1,6 @@
 #![allow(unexpected_cfgs)]
 #![allow(deprecated)]
-use anchor_lang::{prelude::*, solana_program};
+use anchor_lang::prelude::*;
 use anchor_spl::{
     associated_token::AssociatedToken,
     token::{mint_to, Mint, MintTo, Token, TokenAccount},
@@ -14,9 +14,9 @@ use mpl_token_metadata::{
     },
     types::{Creator, DataV2, Collection, CollectionDetails},
 };
-use anchor_lang::solana_program::program::invoke;
+use anchor_lang::solana_program::program::{invoke, invoke_signed};
 
-declare_id!("11111111111111111111111111111111");
+declare_id!("5ChAKGGQf6uv55gp82YHFt78mRz27jUEWUu9ZQTTKFa3");
 
 #[program]
 pub mod nft_mint {
@@ -203,12 +203,14 @@ pub mod nft_mint {
             new_update_authority: None,
         };
 
-        invoke(
+        // The mint authority PDA is the metadata update authority and can only sign via its seeds
+        invoke_signed(
             &update_metadata_ix.instruction(update_metadata_args),
             &[
                 ctx.accounts.metadata.to_account_info(),
                 mint_authority.to_account_info(),
             ],
+            signer,
         )?;
 
         msg!("Metadata updated successfully");
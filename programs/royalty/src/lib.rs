@@ -3,9 +3,8 @@
 use anchor_lang::prelude::*;
 use anchor_spl::token::{self, Token, TokenAccount, Transfer};
 use mpl_token_metadata::accounts::Metadata;
-use mpl_token_metadata::types::DataV2;
 
-declare_id!("11111111111111111111111111111111");
+declare_id!("4iHri3AdfcHvqg6KHv4dNvZHHHfzEFQ7JbbuJ5vVf1ih");
 
 #[program]
 pub mod royalty {
@@ -20,7 +19,8 @@ pub mod royalty {
         royalty_config.authority = ctx.accounts.authority.key();
         royalty_config.max_royalty_basis_points = max_royalty_basis_points;
         royalty_config.platform_fee_basis_points = platform_fee_basis_points;
-        royalty_config.total_fees_collected = 0;
+        royalty_config.total_token_fees_collected = 0;
+        royalty_config.total_sol_fees_collected = 0;
         royalty_config.bump = ctx.bumps.royalty_config;
         
         Ok(())
@@ -37,24 +37,9 @@ pub mod royalty {
         let platform_fee = (sale_price as u128 * royalty_config.platform_fee_basis_points as u128 / 10000) as u64;
         
         // Get metadata and calculate creator royalties
-        let metadata_account = metadata.to_account_info();
-        let metadata_data = Metadata::try_from(&metadata_account)?;
-        
-        let mut total_royalty_fee = 0u64;
-        let mut creator_fees = Vec::new();
-        
-        if let Some(creators) = &metadata_data.creators {
-            let seller_fee_basis_points = metadata_data.seller_fee_basis_points;
-            let total_royalty_amount = (sale_price as u128 * seller_fee_basis_points as u128 / 10000) as u64;
-            
-            for creator in creators {
-                if creator.verified {
-                    let creator_fee = (total_royalty_amount as u128 * creator.share as u128 / 100) as u64;
-                    creator_fees.push((creator.address, creator_fee));
-                    total_royalty_fee += creator_fee;
-                }
-            }
-        }
+        let metadata_data = load_metadata(&metadata.to_account_info(), &ctx.accounts.mint.key())?;
+        let creator_fees = calculate_creator_royalties(royalty_config, &metadata_data, sale_price);
+        let total_royalty_fee = total_royalties(&creator_fees)?;
         
         // Calculate seller amount (total - platform fee - royalty fees)
         let seller_amount = sale_price
@@ -77,7 +62,7 @@ pub mod royalty {
         }
         
         // Transfer royalties to creators
-        for (creator_address, creator_fee) in creator_fees {
+        for CreatorRoyalty { address: creator_address, amount: creator_fee, .. } in creator_fees {
             if creator_fee > 0 {
                 // Find the creator's token account in remaining accounts
                 let creator_token_account = ctx.remaining_accounts
@@ -110,9 +95,99 @@ pub mod royalty {
             )?;
         }
         
-        // Update total fees collected
+        // Update total token fees collected
+        let royalty_config = &mut ctx.accounts.royalty_config;
+        royalty_config.total_token_fees_collected += platform_fee;
+        
+        emit!(PaymentDistributed {
+            sale_price,
+            platform_fee,
+            total_royalty_fee,
+            seller_amount,
+            mint: ctx.accounts.mint.key(),
+        });
+        
+        Ok(())
+    }
+
+    /// Lamport variant of `distribute_payment`. The sale is paid by `payer` directly, or, when
+    /// `vault` is passed, out of a payment vault `payer` owns (a calling program signs for its
+    /// PDA owner). Creator wallets are passed as remaining accounts.
+    pub fn distribute_payment_sol<'info>(
+        ctx: Context<'_, '_, '_, 'info, DistributePaymentSol<'info>>,
+        sale_price: u64,
+    ) -> Result<()> {
+        let royalty_config = &ctx.accounts.royalty_config;
+        
+        // Calculate platform fee
+        let platform_fee = (sale_price as u128 * royalty_config.platform_fee_basis_points as u128 / 10000) as u64;
+        
+        // Get metadata and calculate creator royalties
+        let metadata_data = load_metadata(&ctx.accounts.metadata.to_account_info(), &ctx.accounts.mint.key())?;
+        let creator_fees = calculate_creator_royalties(royalty_config, &metadata_data, sale_price);
+        let total_royalty_fee = total_royalties(&creator_fees)?;
+        
+        // Every remaining account must be one of the verified creators
+        for account in ctx.remaining_accounts.iter() {
+            require!(
+                creator_fees.iter().any(|creator| creator.address == account.key()),
+                ErrorCode::UnknownCreatorAccount
+            );
+        }
+        
+        // Calculate seller amount (total - platform fee - royalty fees)
+        let seller_amount = sale_price
+            .checked_sub(platform_fee)
+            .ok_or(ErrorCode::ArithmeticError)?
+            .checked_sub(total_royalty_fee)
+            .ok_or(ErrorCode::ArithmeticError)?;
+        
+        // A vault only pays out what it holds above its own rent
+        let source = match &ctx.accounts.vault {
+            Some(vault) => {
+                let vault_info = vault.to_account_info();
+                let rent = Rent::get()?.minimum_balance(vault_info.data_len());
+                require!(
+                    vault_info.lamports().saturating_sub(rent) >= sale_price,
+                    ErrorCode::InsufficientFunds
+                );
+                SolSource::Vault(vault_info)
+            }
+            None => {
+                require!(ctx.accounts.payer.lamports() >= sale_price, ErrorCode::InsufficientFunds);
+                SolSource::Payer {
+                    payer: ctx.accounts.payer.to_account_info(),
+                    system_program: ctx.accounts.system_program.to_account_info(),
+                }
+            }
+        };
+        
+        // Transfer platform fee to treasury
+        if platform_fee > 0 {
+            source.pay(&ctx.accounts.platform_sol_treasury, platform_fee)?;
+        }
+        
+        // Transfer royalties to creators
+        for CreatorRoyalty { address: creator_address, amount: creator_fee, .. } in creator_fees {
+            if creator_fee > 0 {
+                let creator_account = ctx.remaining_accounts
+                    .iter()
+                    .find(|acc| acc.key() == creator_address)
+                    .ok_or(ErrorCode::CreatorAccountNotFound)?;
+                source.pay(creator_account, creator_fee)?;
+            }
+        }
+        
+        // Transfer remaining amount to seller
+        if seller_amount > 0 {
+            source.pay(&ctx.accounts.seller, seller_amount)?;
+        }
+        
+        // Update total SOL fees collected
         let royalty_config = &mut ctx.accounts.royalty_config;
-        royalty_config.total_fees_collected += platform_fee;
+        royalty_config.total_sol_fees_collected = royalty_config.total_sol_fees_collected
+            .checked_add(platform_fee)
+            .ok_or(ErrorCode::ArithmeticError)?;
         
         emit!(PaymentDistributed {
             sale_price,
@@ -125,6 +200,15 @@ pub mod royalty {
         Ok(())
     }
 
+    /// Create the lamport payment vault owned by `owner`, usually a calling program's PDA
+    pub fn create_payment_vault(ctx: Context<CreatePaymentVault>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.owner = ctx.accounts.owner.key();
+        vault.bump = ctx.bumps.vault;
+        
+        Ok(())
+    }
+
     pub fn calculate_royalties(
         ctx: Context<CalculateRoyalties>,
         sale_price: u64,
@@ -138,26 +222,8 @@ pub mod royalty {
         // Get metadata and calculate creator royalties
         let metadata_account = metadata.to_account_info();
         let metadata_data = Metadata::try_from(&metadata_account)?;
-        
-        let mut total_royalty_fee = 0u64;
-        let mut creator_breakdown = Vec::new();
-        
-        if let Some(creators) = &metadata_data.creators {
-            let seller_fee_basis_points = metadata_data.seller_fee_basis_points;
-            let total_royalty_amount = (sale_price as u128 * seller_fee_basis_points as u128 / 10000) as u64;
-            
-            for creator in creators {
-                if creator.verified {
-                    let creator_fee = (total_royalty_amount as u128 * creator.share as u128 / 100) as u64;
-                    creator_breakdown.push(CreatorRoyalty {
-                        address: creator.address,
-                        share: creator.share,
-                        amount: creator_fee,
-                    });
-                    total_royalty_fee += creator_fee;
-                }
-            }
-        }
+        let creator_breakdown = calculate_creator_royalties(royalty_config, &metadata_data, sale_price);
+        let total_royalty_fee = total_royalties(&creator_breakdown)?;
         
         // Calculate seller amount
         let seller_amount = sale_price
@@ -199,8 +265,6 @@ pub mod royalty {
         ctx: Context<WithdrawPlatformFees>,
         amount: u64,
     ) -> Result<()> {
-        let royalty_config = &ctx.accounts.royalty_config;
-        
         // Transfer from platform treasury to authority
         let transfer_to_authority = Transfer {
             from: ctx.accounts.platform_treasury.to_account_info(),
@@ -225,6 +289,81 @@ pub mod royalty {
         
         Ok(())
     }
+
+    pub fn withdraw_platform_sol_fees(
+        ctx: Context<WithdrawPlatformSolFees>,
+        amount: u64,
+    ) -> Result<()> {
+        require!(
+            ctx.accounts.platform_sol_treasury.lamports() >= amount,
+            ErrorCode::InsufficientFunds
+        );
+        
+        // Transfer from SOL treasury to authority
+        let transfer_to_authority = anchor_lang::system_program::Transfer {
+            from: ctx.accounts.platform_sol_treasury.to_account_info(),
+            to: ctx.accounts.authority.to_account_info(),
+        };
+        
+        let seeds = &[
+            b"platform_sol_treasury".as_ref(),
+            &[ctx.bumps.platform_sol_treasury],
+        ];
+        let signer_seeds = &[&seeds[..]];
+        
+        anchor_lang::system_program::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.system_program.to_account_info(),
+                transfer_to_authority,
+                signer_seeds,
+            ),
+            amount,
+        )?;
+        
+        Ok(())
+    }
+
+    /// Grow a royalty config created before SOL and token fees were counted separately. The
+    /// SOL counter is appended and starts at zero, earlier fees stay in the token counter
+    pub fn migrate_royalty_config(ctx: Context<MigrateRoyaltyConfig>) -> Result<()> {
+        let config_info = ctx.accounts.royalty_config.to_account_info();
+        let new_len = 8 + RoyaltyConfig::LEN;
+        require!(config_info.data_len() < new_len, ErrorCode::AlreadyMigrated);
+
+        // The authority is the first field of both layouts
+        {
+            let data = config_info.try_borrow_data()?;
+            require!(
+                data.len() >= 8 + 32
+                    && data[..8] == <RoyaltyConfig as anchor_lang::Discriminator>::DISCRIMINATOR,
+                ErrorCode::Unauthorized
+            );
+            require_keys_eq!(
+                Pubkey::try_from(&data[8..40]).map_err(|_| ErrorCode::Unauthorized)?,
+                ctx.accounts.authority.key(),
+                ErrorCode::Unauthorized
+            );
+        }
+
+        let rent_due = Rent::get()?.minimum_balance(new_len)
+            .saturating_sub(config_info.lamports());
+        if rent_due > 0 {
+            anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    anchor_lang::system_program::Transfer {
+                        from: ctx.accounts.authority.to_account_info(),
+                        to: config_info.clone(),
+                    },
+                ),
+                rent_due,
+            )?;
+        }
+        config_info.realloc(new_len, true)?;
+        
+        Ok(())
+    }
+
 }
 
 #[derive(Accounts)]
@@ -284,6 +423,50 @@ pub struct DistributePayment<'info> {
     // Creator token accounts are passed as remaining_accounts
 }
 
+#[derive(Accounts)]
+pub struct DistributePaymentSol<'info> {
+    #[account(
+        mut,
+        seeds = [b"royalty_config"],
+        bump = royalty_config.bump
+    )]
+    pub royalty_config: Account<'info, RoyaltyConfig>,
+    
+    /// Buyer wallet, or the owner of `vault` when the sale is paid out of a vault
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    /// Royalty-owned vault holding the sale lamports, debited directly
+    #[account(
+        mut,
+        seeds = [b"payment_vault", payer.key().as_ref()],
+        bump = vault.bump,
+        constraint = vault.owner == payer.key() @ ErrorCode::InvalidPaymentVault
+    )]
+    pub vault: Option<Account<'info, PaymentVault>>,
+    
+    /// CHECK: Seller wallet receiving the proceeds
+    #[account(mut)]
+    pub seller: AccountInfo<'info>,
+    
+    /// CHECK: System-owned PDA collecting SOL platform fees
+    #[account(
+        mut,
+        seeds = [b"platform_sol_treasury"],
+        bump
+    )]
+    pub platform_sol_treasury: AccountInfo<'info>,
+    
+    pub mint: Account<'info, anchor_spl::token::Mint>,
+    
+    /// CHECK: Validated by `load_metadata`
+    pub metadata: AccountInfo<'info>,
+    
+    pub system_program: Program<'info, System>,
+    
+    // Creator wallets are passed as remaining_accounts
+}
+
 #[derive(Accounts)]
 pub struct CalculateRoyalties<'info> {
     pub royalty_config: Account<'info, RoyaltyConfig>,
@@ -329,17 +512,174 @@ pub struct WithdrawPlatformFees<'info> {
     pub token_program: Program<'info, Token>,
 }
 
+#[derive(Accounts)]
+pub struct WithdrawPlatformSolFees<'info> {
+    #[account(
+        has_one = authority,
+        seeds = [b"royalty_config"],
+        bump = royalty_config.bump
+    )]
+    pub royalty_config: Account<'info, RoyaltyConfig>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    /// CHECK: System-owned PDA collecting SOL platform fees
+    #[account(
+        mut,
+        seeds = [b"platform_sol_treasury"],
+        bump
+    )]
+    pub platform_sol_treasury: AccountInfo<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateRoyaltyConfig<'info> {
+    /// CHECK: Royalty config under the older layout that `Account` cannot decode, its
+    /// authority is checked in the handler
+    #[account(
+        mut,
+        seeds = [b"royalty_config"],
+        bump,
+        owner = crate::ID
+    )]
+    pub royalty_config: UncheckedAccount<'info>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct CreatePaymentVault<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + PaymentVault::LEN,
+        seeds = [b"payment_vault", owner.key().as_ref()],
+        bump
+    )]
+    pub vault: Account<'info, PaymentVault>,
+    
+    pub owner: Signer<'info>,
+    
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    
+    pub system_program: Program<'info, System>,
+}
+
 #[account]
 pub struct RoyaltyConfig {
     pub authority: Pubkey,
     pub max_royalty_basis_points: u16,
     pub platform_fee_basis_points: u16,
-    pub total_fees_collected: u64,
+    // Fees collected in SPL tokens, across every payment mint
+    pub total_token_fees_collected: u64,
     pub bump: u8,
+    // Fees collected in lamports, appended after `bump` so older configs can be grown in place
+    pub total_sol_fees_collected: u64,
 }
 
 impl RoyaltyConfig {
-    pub const LEN: usize = 32 + 2 + 2 + 8 + 1;
+    pub const LEN: usize = 32 + 2 + 2 + 8 + 1 + 8;
+}
+
+/// Royalty-owned account whose lamports above rent fund `distribute_payment_sol`
+#[account]
+pub struct PaymentVault {
+    pub owner: Pubkey,
+    pub bump: u8,
+}
+
+impl PaymentVault {
+    pub const LEN: usize = 32 + 1;
+}
+
+/// Where `distribute_payment_sol` draws lamports from
+enum SolSource<'info> {
+    Payer {
+        payer: AccountInfo<'info>,
+        system_program: AccountInfo<'info>,
+    },
+    // The vault carries data, so it is debited directly rather than through the system program
+    Vault(AccountInfo<'info>),
+}
+
+impl<'info> SolSource<'info> {
+    fn pay(&self, to: &AccountInfo<'info>, amount: u64) -> Result<()> {
+        match self {
+            SolSource::Payer { payer, system_program } => anchor_lang::system_program::transfer(
+                CpiContext::new(
+                    system_program.clone(),
+                    anchor_lang::system_program::Transfer {
+                        from: payer.clone(),
+                        to: to.clone(),
+                    },
+                ),
+                amount,
+            ),
+            SolSource::Vault(vault) => {
+                let remaining = vault.lamports().checked_sub(amount)
+                    .ok_or(ErrorCode::InsufficientFunds)?;
+                let received = to.lamports().checked_add(amount)
+                    .ok_or(ErrorCode::ArithmeticError)?;
+                **vault.lamports.borrow_mut() = remaining;
+                **to.lamports.borrow_mut() = received;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Deserialize a Metaplex metadata account after checking it belongs to `mint`
+pub fn load_metadata(metadata: &AccountInfo, mint: &Pubkey) -> Result<Metadata> {
+    require_keys_eq!(*metadata.owner, mpl_token_metadata::ID, ErrorCode::InvalidMetadataAccount);
+    let metadata_data = Metadata::try_from(metadata)?;
+    require_keys_eq!(metadata_data.mint, *mint, ErrorCode::InvalidMetadataAccount);
+    Ok(metadata_data)
+}
+
+/// Royalty owed to each verified creator, with the metadata's
+/// `seller_fee_basis_points` capped at `max_royalty_basis_points`
+pub fn calculate_creator_royalties(
+    royalty_config: &RoyaltyConfig,
+    metadata: &Metadata,
+    sale_price: u64,
+) -> Vec<CreatorRoyalty> {
+    let mut creator_royalties = Vec::new();
+
+    if let Some(creators) = &metadata.creators {
+        let royalty_basis_points = metadata
+            .seller_fee_basis_points
+            .min(royalty_config.max_royalty_basis_points);
+        let total_royalty_amount = (sale_price as u128 * royalty_basis_points as u128 / 10000) as u64;
+
+        for creator in creators {
+            if creator.verified {
+                let creator_fee = (total_royalty_amount as u128 * creator.share as u128 / 100) as u64;
+                creator_royalties.push(CreatorRoyalty {
+                    address: creator.address,
+                    share: creator.share,
+                    amount: creator_fee,
+                });
+            }
+        }
+    }
+
+    creator_royalties
+}
+
+/// Sum of all creator payouts
+pub fn total_royalties(creator_royalties: &[CreatorRoyalty]) -> Result<u64> {
+    creator_royalties.iter().try_fold(0u64, |total, creator| {
+        total
+            .checked_add(creator.amount)
+            .ok_or_else(|| ErrorCode::ArithmeticError.into())
+    })
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone)]
@@ -381,4 +721,12 @@ pub enum ErrorCode {
     InvalidMetadataAccount,
     #[msg("Insufficient funds for payment")]
     InsufficientFunds,
+    #[msg("Account is not a verified creator of this NFT")]
+    UnknownCreatorAccount,
+    #[msg("Payment vault does not belong to the payer")]
+    InvalidPaymentVault,
+    #[msg("Unauthorized")]
+    Unauthorized,
+    #[msg("Royalty config is already on the current layout")]
+    AlreadyMigrated,
 }
\ No newline at end of file
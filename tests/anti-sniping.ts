@@ -0,0 +1,83 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import {
+  SOL,
+  createAuction,
+  expectError,
+  fundedWallet,
+  marketplacePda,
+  mintNft,
+  placeBid,
+  programs,
+  setupMarketplace,
+  useShortAuctions,
+  waitUntil,
+} from "./helpers";
+
+describe("anti-sniping", () => {
+  let bidder: anchor.web3.Keypair;
+
+  before(async () => {
+    await setupMarketplace();
+    await useShortAuctions();
+    bidder = await fundedWallet();
+  });
+
+  it("extends a sniped auction no further than its hard cap", async () => {
+    const mint = await mintNft();
+    // Every bid lands inside the window and would push the end an hour out, but the cap
+    // stops it one second past the original end
+    const { auction, startTime, endTime } = await createAuction(mint, {
+      duration: 60,
+      antiSniping: { extensionWindow: 60, extensionAmount: 3600, maxExtension: 1 },
+    });
+    await waitUntil(startTime);
+    await placeBid(auction, bidder, SOL);
+
+    const extended = await programs.auction.account.auctionState.fetch(auction);
+    assert.equal(extended.endTime.toNumber(), endTime + 1);
+  });
+
+  it("rejects an extension window beyond the marketplace bound", async () => {
+    const { maxExtensionWindow } = await programs.marketplace.account.marketplaceState.fetch(
+      marketplacePda
+    );
+    const mint = await mintNft();
+    await expectError(
+      createAuction(mint, {
+        antiSniping: { extensionWindow: maxExtensionWindow.toNumber() + 1, extensionAmount: 0 },
+      }),
+      "InvalidExtensionWindow"
+    );
+  });
+
+  it("rejects an auction longer than the marketplace bound", async () => {
+    const { maxAuctionDuration } = await programs.marketplace.account.marketplaceState.fetch(
+      marketplacePda
+    );
+    const mint = await mintNft();
+    await expectError(
+      createAuction(mint, { duration: maxAuctionDuration.toNumber() + 1 }),
+      "AuctionTooLong"
+    );
+  });
+
+  it("rejects a hard cap past the marketplace's longest duration", async () => {
+    const { maxAuctionDuration } = await programs.marketplace.account.marketplaceState.fetch(
+      marketplacePda
+    );
+    const mint = await mintNft();
+    // The auction itself fits, but extensions could run it past the bound
+    await expectError(
+      createAuction(mint, {
+        duration: 60,
+        antiSniping: {
+          extensionWindow: 60,
+          extensionAmount: 60,
+          maxExtension: maxAuctionDuration.toNumber(),
+        },
+      }),
+      "InvalidMaxEndTime"
+    );
+  });
+});
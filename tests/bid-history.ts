@@ -0,0 +1,64 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import {
+  SOL,
+  bidHistoryPda,
+  buyNow,
+  createAuction,
+  exists,
+  expectError,
+  fundedWallet,
+  mintNft,
+  placeBid,
+  programs,
+  setupMarketplace,
+  waitUntil,
+  wallet,
+} from "./helpers";
+
+describe("bid-history", () => {
+  let first: anchor.web3.Keypair;
+  let second: anchor.web3.Keypair;
+
+  const closeBidHistory = (auction: anchor.web3.PublicKey) =>
+    programs.auction.methods
+      .closeBidHistory()
+      .accountsPartial({ auction, bidHistory: bidHistoryPda(auction), seller: wallet })
+      .rpc();
+
+  before(async () => {
+    await setupMarketplace();
+    first = await fundedWallet();
+    second = await fundedWallet();
+  });
+
+  it("records bids in order and closes the history once the auction settles", async () => {
+    const mint = await mintNft();
+    const { auction, startTime } = await createAuction(mint, { buyNowPrice: 5 * SOL });
+    await waitUntil(startTime);
+    await placeBid(auction, first, SOL);
+    await placeBid(auction, second, 1.2 * SOL, { previousBidder: first.publicKey });
+    await placeBid(auction, first, 1.5 * SOL, { previousBidder: second.publicKey });
+
+    const history = await programs.auction.account.bidHistory.fetch(bidHistoryPda(auction));
+    assert.deepEqual(
+      history.bids.map((bid) => [bid.bidder.toBase58(), bid.amount.toNumber()]),
+      [
+        [first.publicKey.toBase58(), SOL],
+        [second.publicKey.toBase58(), 1.2 * SOL],
+        [first.publicKey.toBase58(), 1.5 * SOL],
+      ]
+    );
+
+    await buyNow(mint, wallet, second, { previousBidder: first.publicKey });
+    await closeBidHistory(auction);
+    assert.isFalse(await exists(bidHistoryPda(auction)));
+  });
+
+  it("keeps the history of a running auction open", async () => {
+    const mint = await mintNft();
+    const { auction } = await createAuction(mint);
+
+    await expectError(closeBidHistory(auction), "AuctionNotSettled");
+  });
+});
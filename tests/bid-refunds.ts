@@ -0,0 +1,128 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import {
+  SOL,
+  ata,
+  bidHistoryPda,
+  bidRefundPda,
+  claimAuction,
+  createAuction,
+  exists,
+  expectError,
+  fundedWallet,
+  lamports,
+  mintNft,
+  placeBid,
+  programs,
+  setupMarketplace,
+  useShortAuctions,
+  waitUntil,
+  wallet,
+} from "./helpers";
+
+describe("bid-refunds", () => {
+  let outbid: anchor.web3.Keypair;
+  let leader: anchor.web3.Keypair;
+
+  const withdrawRefund = (auction: anchor.web3.PublicKey, bidder: anchor.web3.PublicKey) =>
+    programs.auction.methods
+      .withdrawRefund()
+      .accountsPartial({
+        auction,
+        bidRefund: bidRefundPda(auction, bidder),
+        bidder,
+        bidderPaymentAccount: null,
+        auctionPaymentAccount: null,
+      })
+      .rpc();
+
+  before(async () => {
+    await setupMarketplace();
+    await useShortAuctions();
+    outbid = await fundedWallet();
+    leader = await fundedWallet();
+  });
+
+  it("lets anyone pay an outbid bidder's refund out of escrow", async () => {
+    const mint = await mintNft();
+    const { auction, startTime } = await createAuction(mint);
+    await waitUntil(startTime);
+    await placeBid(auction, outbid, SOL);
+    await placeBid(auction, leader, 1.1 * SOL, { previousBidder: outbid.publicKey });
+
+    const ledger = bidRefundPda(auction, outbid.publicKey);
+    const ledgerRent = await lamports(ledger);
+    const before = await lamports(outbid.publicKey);
+    // The provider wallet, not the bidder, triggers the withdrawal
+    await withdrawRefund(auction, outbid.publicKey);
+
+    assert.equal((await lamports(outbid.publicKey)) - before, SOL + ledgerRent);
+    assert.isFalse(await exists(ledger));
+    const state = await programs.auction.account.auctionState.fetch(auction);
+    assert.ok(state.pendingRefunds.isZero());
+  });
+
+  it("keeps the ledger of a bidder who retook the lead open for the next outbid", async () => {
+    const mint = await mintNft();
+    const { auction, startTime } = await createAuction(mint);
+    await waitUntil(startTime);
+    await placeBid(auction, outbid, SOL);
+    await placeBid(auction, leader, 1.1 * SOL, { previousBidder: outbid.publicKey });
+    await placeBid(auction, outbid, 1.2 * SOL, { previousBidder: leader.publicKey });
+
+    const ledger = bidRefundPda(auction, outbid.publicKey);
+    const before = await lamports(outbid.publicKey);
+    await withdrawRefund(auction, outbid.publicKey);
+    assert.equal((await lamports(outbid.publicKey)) - before, SOL);
+    assert.isTrue(await exists(ledger));
+
+    // Outbidding the current leader credits the ledger that was just withdrawn from
+    await placeBid(auction, leader, 1.3 * SOL, { previousBidder: outbid.publicKey });
+    const { refundable } = await programs.auction.account.bidRefundLedger.fetch(ledger);
+    assert.equal(refundable.toNumber(), 1.2 * SOL);
+  });
+
+  it("closes the winner's emptied ledger once settled, and only then the auction", async () => {
+    const mint = await mintNft();
+    const { auction, startTime, endTime } = await createAuction(mint, { duration: 4 });
+    await waitUntil(startTime);
+    await placeBid(auction, outbid, SOL);
+    await placeBid(auction, leader, 1.1 * SOL, { previousBidder: outbid.publicKey });
+    await waitUntil(endTime);
+    await claimAuction(mint, wallet, leader.publicKey, [wallet]);
+
+    const closeAuction = () =>
+      programs.auction.methods
+        .closeAuction()
+        .accountsPartial({
+          auction,
+          seller: wallet,
+          auctionTokenAccount: ata(mint, auction),
+          auctionPaymentAccount: null,
+          bidHistory: bidHistoryPda(auction),
+        })
+        .rpc();
+    await expectError(closeAuction(), "RefundLedgersOpen");
+
+    await withdrawRefund(auction, outbid.publicKey);
+    const winnerLedger = bidRefundPda(auction, leader.publicKey);
+    const ledgerRent = await lamports(winnerLedger);
+    const before = await lamports(leader.publicKey);
+    await withdrawRefund(auction, leader.publicKey);
+    assert.equal((await lamports(leader.publicKey)) - before, ledgerRent);
+    assert.isFalse(await exists(winnerLedger));
+
+    await closeAuction();
+    assert.isFalse(await exists(auction));
+    assert.isFalse(await exists(bidHistoryPda(auction)));
+  });
+
+  it("rejects a withdrawal with nothing credited", async () => {
+    const mint = await mintNft();
+    const { auction, startTime } = await createAuction(mint);
+    await waitUntil(startTime);
+    await placeBid(auction, leader, SOL);
+
+    await expectError(withdrawRefund(auction, leader.publicKey), "NothingToRefund");
+  });
+});
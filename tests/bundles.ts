@@ -0,0 +1,143 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import {
+  SOL,
+  ata,
+  chainTime,
+  exists,
+  expectError,
+  fundTokens,
+  fundedWallet,
+  marketplacePda,
+  metadataPda,
+  mintNft,
+  programs,
+  royaltyConfigPda,
+  setupMarketplace,
+  tokenBalance,
+  waitUntil,
+  wallet,
+} from "./helpers";
+
+describe("bundles", () => {
+  let buyer: anchor.web3.Keypair;
+  let bundleId = Date.now();
+
+  const bundlePda = (id: number) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("bundle"), wallet.toBuffer(), new anchor.BN(id).toArrayLike(Buffer, "le", 8)],
+      programs.listing.programId
+    )[0];
+
+  const account = (pubkey: anchor.web3.PublicKey, isWritable: boolean) => ({
+    pubkey,
+    isSigner: false,
+    isWritable,
+  });
+
+  const createBundle = async (mints: anchor.web3.PublicKey[], expiry?: number) => {
+    const id = bundleId++;
+    const bundle = bundlePda(id);
+    await programs.listing.methods
+      .createBundleListing(
+        new anchor.BN(id),
+        new anchor.BN(2 * SOL),
+        expiry === undefined ? null : new anchor.BN(expiry)
+      )
+      .accountsPartial({ bundle, seller: wallet, marketplace: marketplacePda })
+      .remainingAccounts(
+        mints.flatMap((mint) => [
+          account(mint, false),
+          account(ata(mint, wallet), true),
+          account(ata(mint, bundle), true),
+        ])
+      )
+      .rpc();
+    return bundle;
+  };
+
+  const returnAccounts = (bundle: anchor.web3.PublicKey, mints: anchor.web3.PublicKey[]) =>
+    mints.flatMap((mint) => [account(ata(mint, bundle), true), account(ata(mint, wallet), true)]);
+
+  before(async () => {
+    await setupMarketplace();
+    buyer = await fundedWallet();
+  });
+
+  it("sells a bundle atomically, creating the buyer's token accounts", async () => {
+    const mints = [await mintNft(), await mintNft()];
+    // A token account created ahead of time for the bundle PDA does not block listing
+    await fundTokens(mints[0], bundlePda(bundleId));
+    const bundle = await createBundle(mints);
+    const { treasury } = await programs.marketplace.account.marketplaceState.fetch(
+      marketplacePda
+    );
+
+    await programs.listing.methods
+      .buyBundle()
+      .accountsPartial({
+        bundle,
+        buyer: buyer.publicKey,
+        seller: wallet,
+        royaltyConfig: royaltyConfigPda,
+        marketplace: marketplacePda,
+        treasury,
+      })
+      .remainingAccounts([
+        ...mints.flatMap((mint) => [
+          account(mint, false),
+          account(ata(mint, bundle), true),
+          account(ata(mint, buyer.publicKey), true),
+          account(metadataPda(mint), false),
+        ]),
+        // The seller is every NFT's only creator and receives their royalties
+        account(wallet, true),
+      ])
+      .signers([buyer])
+      .rpc();
+
+    for (const mint of mints) {
+      assert.equal(await tokenBalance(ata(mint, buyer.publicKey)), "1");
+      assert.isFalse(await exists(ata(mint, bundle)));
+    }
+    assert.isFalse(await exists(bundle));
+  });
+
+  it("lets anyone return an expired bundle to its seller", async () => {
+    const mints = [await mintNft(), await mintNft()];
+    const expiry = (await chainTime()) + 2;
+    const bundle = await createBundle(mints, expiry);
+    await waitUntil(expiry + 1);
+
+    await programs.listing.methods
+      .recoverExpiredBundle()
+      .accountsPartial({ bundle, caller: buyer.publicKey, seller: wallet })
+      .remainingAccounts(returnAccounts(bundle, mints))
+      .signers([buyer])
+      .rpc();
+
+    for (const mint of mints) {
+      assert.equal(await tokenBalance(ata(mint, wallet)), "1");
+    }
+    assert.isFalse(await exists(bundle));
+  });
+
+  it("rejects recovering a bundle that has not expired", async () => {
+    const mints = [await mintNft(), await mintNft()];
+    const bundle = await createBundle(mints, (await chainTime()) + 3600);
+
+    await expectError(
+      programs.listing.methods
+        .recoverExpiredBundle()
+        .accountsPartial({ bundle, caller: buyer.publicKey, seller: wallet })
+        .remainingAccounts(returnAccounts(bundle, mints))
+        .signers([buyer])
+        .rpc(),
+      "ListingNotExpired"
+    );
+  });
+
+  it("rejects a bundle of a single NFT", async () => {
+    await expectError(createBundle([await mintNft()]), "InvalidBundleSize");
+  });
+});
@@ -0,0 +1,68 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import {
+  SOL,
+  ata,
+  bidRefundPda,
+  buyNow,
+  createAuction,
+  expectError,
+  fundedWallet,
+  mintNft,
+  placeBid,
+  programs,
+  setupMarketplace,
+  tokenBalance,
+  waitUntil,
+  wallet,
+} from "./helpers";
+
+describe("buy-now", () => {
+  let bidder: anchor.web3.Keypair;
+  let buyer: anchor.web3.Keypair;
+
+  before(async () => {
+    await setupMarketplace();
+    bidder = await fundedWallet();
+    buyer = await fundedWallet();
+  });
+
+  it("ends an English auction at its buy-now price and credits the outbid bidder", async () => {
+    const mint = await mintNft();
+    const { auction, startTime } = await createAuction(mint, { buyNowPrice: 3 * SOL });
+    await waitUntil(startTime);
+    await placeBid(auction, bidder, SOL);
+
+    await buyNow(mint, wallet, buyer, { previousBidder: bidder.publicKey });
+
+    const settled = await programs.auction.account.auctionState.fetch(auction);
+    assert.isTrue(settled.isSettled);
+    assert.ok(settled.winner.equals(buyer.publicKey));
+    assert.equal(await tokenBalance(ata(mint, buyer.publicKey)), "1");
+
+    const ledger = await programs.auction.account.bidRefundLedger.fetch(
+      bidRefundPda(auction, bidder.publicKey)
+    );
+    assert.ok(ledger.refundable.eqn(SOL));
+  });
+
+  it("rejects a buy-now price below the reserve", async () => {
+    const mint = await mintNft();
+    await expectError(
+      createAuction(mint, { reservePrice: 2 * SOL, buyNowPrice: SOL }),
+      "InvalidBuyNowPrice"
+    );
+  });
+
+  it("withdraws buy-now once bidding reaches it", async () => {
+    const mint = await mintNft();
+    const { auction, startTime } = await createAuction(mint, { buyNowPrice: 2 * SOL });
+    await waitUntil(startTime);
+    await placeBid(auction, bidder, 2 * SOL);
+
+    await expectError(
+      buyNow(mint, wallet, buyer, { previousBidder: bidder.publicKey }),
+      "BuyNowNotAvailable"
+    );
+  });
+});
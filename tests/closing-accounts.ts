@@ -0,0 +1,117 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import {
+  SOL,
+  accountMeta,
+  ata,
+  buyNft,
+  createDirectSale,
+  depositNft,
+  depositRecordPda,
+  depositSol,
+  exists,
+  expectError,
+  fundTokens,
+  fundedWallet,
+  lamports,
+  listNft,
+  listingPda,
+  mintNft,
+  programs,
+  releaseEscrow,
+  setupMarketplace,
+  wallet,
+} from "./helpers";
+
+describe("closing-accounts", () => {
+  let seller: anchor.web3.Keypair;
+  let buyer: anchor.web3.Keypair;
+
+  const closeEscrow = (
+    escrow: anchor.web3.PublicKey,
+    remainingAccounts: ReturnType<typeof accountMeta>[]
+  ) =>
+    programs.escrow.methods
+      .closeEscrow()
+      .accountsPartial({
+        escrow,
+        authority: seller.publicKey,
+        swap: null,
+        schedule: null,
+        caller: wallet,
+      })
+      .remainingAccounts(remainingAccounts)
+      .rpc();
+
+  const closeListing = (mint: anchor.web3.PublicKey) =>
+    programs.listing.methods
+      .closeListing()
+      .accountsPartial({ listing: listingPda(mint, wallet), seller: wallet })
+      .rpc();
+
+  before(async () => {
+    await setupMarketplace();
+    seller = await fundedWallet();
+    buyer = await fundedWallet();
+  });
+
+  it("returns every rent deposit of a released escrow to whoever paid it", async () => {
+    const mint = await mintNft(seller);
+    const escrow = await createDirectSale(seller, buyer.publicKey, SOL);
+    await depositNft(escrow, seller, mint);
+    await depositSol(escrow, buyer, SOL);
+    await fundTokens(mint, buyer.publicKey);
+    await releaseEscrow(escrow, buyer, {
+      nft: { mint, recipient: buyer.publicKey },
+      solRecipient: seller.publicKey,
+    });
+
+    const sellerRecord = depositRecordPda(escrow, seller.publicKey);
+    const buyerRecord = depositRecordPda(escrow, buyer.publicKey);
+    const buyerRecordRent = await lamports(buyerRecord);
+    const buyerBefore = await lamports(buyer.publicKey);
+    // The provider wallet cleans up an escrow it had no part in
+    await closeEscrow(escrow, [
+      accountMeta(sellerRecord),
+      accountMeta(seller.publicKey),
+      accountMeta(ata(mint, escrow)),
+      accountMeta(buyerRecord),
+      accountMeta(buyer.publicKey),
+    ]);
+
+    for (const account of [escrow, ata(mint, escrow), sellerRecord, buyerRecord]) {
+      assert.isFalse(await exists(account));
+    }
+    assert.equal((await lamports(buyer.publicKey)) - buyerBefore, buyerRecordRent);
+  });
+
+  it("closes a sold listing kept as a record", async () => {
+    const mint = await mintNft();
+    await listNft(mint, SOL);
+    await buyNft(mint, wallet, buyer);
+
+    await closeListing(mint);
+
+    assert.isFalse(await exists(listingPda(mint, wallet)));
+  });
+
+  it("rejects closing an escrow that has not been released", async () => {
+    const escrow = await createDirectSale(seller, buyer.publicKey, SOL);
+    await depositSol(escrow, buyer, SOL);
+
+    await expectError(
+      closeEscrow(escrow, [
+        accountMeta(depositRecordPda(escrow, buyer.publicKey)),
+        accountMeta(buyer.publicKey),
+      ]),
+      "EscrowNotFinished"
+    );
+  });
+
+  it("rejects closing a listing that is still for sale", async () => {
+    const mint = await mintNft();
+    await listNft(mint, SOL);
+
+    await expectError(closeListing(mint), "ListingStillActive");
+  });
+});
@@ -0,0 +1,105 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import {
+  SOL,
+  ata,
+  exists,
+  expectError,
+  fundedWallet,
+  lamports,
+  marketplacePda,
+  metadataPda,
+  mintNft,
+  programs,
+  provider,
+  royaltyConfigPda,
+  setupMarketplace,
+  wallet,
+} from "./helpers";
+
+describe("collection-offers", () => {
+  let buyer: anchor.web3.Keypair;
+  const price = SOL / 2;
+
+  const offerPda = (collection: anchor.web3.PublicKey) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("collection_offer"), collection.toBuffer(), buyer.publicKey.toBuffer()],
+      programs.listing.programId
+    )[0];
+
+  const makeOffer = (collection: anchor.web3.PublicKey, quantity: number) =>
+    programs.listing.methods
+      .makeCollectionOffer(collection, new anchor.BN(price), quantity, null)
+      .accountsPartial({
+        offer: offerPda(collection),
+        buyer: buyer.publicKey,
+        marketplace: marketplacePda,
+      })
+      .signers([buyer])
+      .rpc();
+
+  before(async () => {
+    await setupMarketplace();
+    buyer = await fundedWallet();
+  });
+
+  it("escrows a collection offer and refunds it on cancel", async () => {
+    const collection = anchor.web3.Keypair.generate().publicKey;
+    const offer = offerPda(collection);
+
+    await makeOffer(collection, 2);
+    const state = await programs.listing.account.collectionOfferState.fetch(offer);
+    assert.equal(state.quantity, 2);
+    const rent = await provider.connection.getMinimumBalanceForRentExemption(
+      (await provider.connection.getAccountInfo(offer)).data.length
+    );
+    assert.equal(await lamports(offer), rent + 2 * price);
+
+    const buyerBefore = await lamports(buyer.publicKey);
+    await programs.listing.methods
+      .cancelCollectionOffer()
+      .accountsPartial({ offer, buyer: buyer.publicKey })
+      .signers([buyer])
+      .rpc();
+
+    assert.isFalse(await exists(offer));
+    // The buyer paid the transaction fee out of the refund
+    assert.isAbove((await lamports(buyer.publicKey)) - buyerBefore, 2 * price);
+  });
+
+  it("rejects an empty collection offer", async () => {
+    await expectError(
+      makeOffer(anchor.web3.Keypair.generate().publicKey, 0),
+      "InvalidQuantity"
+    );
+  });
+
+  it("rejects selling an NFT outside a verified collection into an offer", async () => {
+    const collection = anchor.web3.Keypair.generate().publicKey;
+    await makeOffer(collection, 1);
+
+    const mint = await mintNft();
+    const { treasury } = await programs.marketplace.account.marketplaceState.fetch(
+      marketplacePda
+    );
+    await expectError(
+      programs.listing.methods
+        .acceptCollectionOffer()
+        .accountsPartial({
+          offer: offerPda(collection),
+          seller: wallet,
+          buyer: buyer.publicKey,
+          mint,
+          metadata: metadataPda(mint),
+          sellerTokenAccount: ata(mint, wallet),
+          buyerTokenAccount: ata(mint, buyer.publicKey),
+          royaltyConfig: royaltyConfigPda,
+          marketplace: marketplacePda,
+          treasury,
+        })
+        .remainingAccounts([{ pubkey: wallet, isSigner: false, isWritable: true }])
+        .rpc(),
+      "CollectionNotVerified"
+    );
+  });
+});
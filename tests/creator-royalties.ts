@@ -0,0 +1,102 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import {
+  SOL,
+  buyNft,
+  claimAuction,
+  createAuction,
+  expectError,
+  fundedWallet,
+  lamports,
+  listNft,
+  marketplacePda,
+  mintNft,
+  placeBid,
+  programs,
+  setupMarketplace,
+  useShortAuctions,
+  waitUntil,
+} from "./helpers";
+
+describe("creator-royalties", () => {
+  let creator: anchor.web3.Keypair;
+  let seller: anchor.web3.Keypair;
+  let buyer: anchor.web3.Keypair;
+
+  // Minted NFTs carry a 5% royalty, under the configured 10% cap
+  const royalty = (price: number) => (price * 500) / 10_000;
+  const platformFee = async (price: number) => {
+    const { feeBasisPoints } = await programs.marketplace.account.marketplaceState.fetch(
+      marketplacePda
+    );
+    return (price * feeBasisPoints) / 10_000;
+  };
+
+  // An NFT whose verified creator sold it on to `seller`
+  const resoldNft = async () => {
+    const mint = await mintNft(creator);
+    await listNft(mint, SOL, creator);
+    await buyNft(mint, creator.publicKey, seller);
+    return mint;
+  };
+
+  before(async () => {
+    await setupMarketplace();
+    await useShortAuctions();
+    creator = await fundedWallet();
+    seller = await fundedWallet();
+    buyer = await fundedWallet();
+  });
+
+  it("pays the creator royalty out of a listing sale", async () => {
+    const mint = await resoldNft();
+    const price = 2 * SOL;
+    await listNft(mint, price, seller);
+
+    const creatorBefore = await lamports(creator.publicKey);
+    const sellerBefore = await lamports(seller.publicKey);
+    await buyNft(mint, seller.publicKey, buyer, [creator.publicKey]);
+
+    assert.equal((await lamports(creator.publicKey)) - creatorBefore, royalty(price));
+    assert.equal(
+      (await lamports(seller.publicKey)) - sellerBefore,
+      price - (await platformFee(price)) - royalty(price)
+    );
+  });
+
+  it("rejects a listing sale that leaves out a verified creator", async () => {
+    const mint = await resoldNft();
+    await listNft(mint, SOL, seller);
+
+    await expectError(buyNft(mint, seller.publicKey, buyer, []), "CreatorAccountNotFound");
+  });
+
+  it("pays the creator royalty when an auction settles", async () => {
+    const mint = await resoldNft();
+    const price = SOL;
+    const { auction, startTime, endTime } = await createAuction(mint, {
+      seller,
+      duration: 4,
+      reservePrice: price,
+    });
+    await waitUntil(startTime);
+    await placeBid(auction, buyer, price);
+    await waitUntil(endTime);
+
+    // Settling without the creator's account is rejected
+    await expectError(
+      claimAuction(mint, seller.publicKey, buyer.publicKey, []),
+      "CreatorAccountNotFound"
+    );
+
+    const creatorBefore = await lamports(creator.publicKey);
+    const sellerBefore = await lamports(seller.publicKey);
+    await claimAuction(mint, seller.publicKey, buyer.publicKey, [creator.publicKey]);
+
+    assert.equal((await lamports(creator.publicKey)) - creatorBefore, royalty(price));
+    assert.equal(
+      (await lamports(seller.publicKey)) - sellerBefore,
+      price - (await platformFee(price)) - royalty(price)
+    );
+  });
+});
@@ -0,0 +1,60 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import {
+  SOL,
+  ata,
+  createDirectSale,
+  depositNft,
+  depositSol,
+  expectError,
+  fundTokens,
+  fundedWallet,
+  lamports,
+  mintNft,
+  programs,
+  releaseEscrow,
+  setupMarketplace,
+  tokenBalance,
+} from "./helpers";
+
+describe("direct-sales", () => {
+  let seller: anchor.web3.Keypair;
+  let buyer: anchor.web3.Keypair;
+
+  before(async () => {
+    await setupMarketplace();
+    seller = await fundedWallet();
+    buyer = await fundedWallet();
+  });
+
+  it("routes the NFT to the buyer and the payment to the seller", async () => {
+    const mint = await mintNft(seller);
+    const escrow = await createDirectSale(seller, buyer.publicKey, SOL);
+    await depositNft(escrow, seller, mint);
+    await depositSol(escrow, buyer, SOL);
+    await fundTokens(mint, buyer.publicKey);
+
+    // The buyer may release, but only along the recorded routes
+    await expectError(
+      releaseEscrow(escrow, buyer, { nft: { mint, recipient: buyer.publicKey } }),
+      "InvalidRecipient"
+    );
+
+    const before = await lamports(seller.publicKey);
+    await releaseEscrow(escrow, buyer, {
+      nft: { mint, recipient: buyer.publicKey },
+      solRecipient: seller.publicKey,
+    });
+
+    assert.equal(await tokenBalance(ata(mint, buyer.publicKey)), "1");
+    assert.equal((await lamports(seller.publicKey)) - before, SOL);
+    const state = await programs.escrow.account.escrowState.fetch(escrow);
+    assert.isTrue(state.isReleased);
+  });
+
+  it("rejects paying more than the price", async () => {
+    const escrow = await createDirectSale(seller, buyer.publicKey, SOL);
+
+    await expectError(depositSol(escrow, buyer, 2 * SOL), "ExceedsPrice");
+  });
+});
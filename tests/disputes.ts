@@ -0,0 +1,103 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import {
+  SOL,
+  ata,
+  createDirectSale,
+  createEscrow,
+  depositNft,
+  depositSol,
+  expectError,
+  fundTokens,
+  fundedWallet,
+  lamports,
+  mintNft,
+  programs,
+  releaseEscrow,
+  setupMarketplace,
+  tokenBalance,
+} from "./helpers";
+
+describe("disputes", () => {
+  let seller: anchor.web3.Keypair;
+  let buyer: anchor.web3.Keypair;
+  let arbiter: anchor.web3.Keypair;
+
+  const raiseDispute = (escrow: anchor.web3.PublicKey, party: anchor.web3.Keypair) =>
+    programs.escrow.methods
+      .raiseDispute()
+      .accountsPartial({ escrow, party: party.publicKey })
+      .signers([party])
+      .rpc();
+
+  before(async () => {
+    await setupMarketplace();
+    seller = await fundedWallet();
+    buyer = await fundedWallet();
+    arbiter = await fundedWallet(1);
+  });
+
+  it("freezes a disputed sale until the arbiter splits it", async () => {
+    const mint = await mintNft(seller);
+    const escrow = await createDirectSale(seller, buyer.publicKey, SOL);
+    await programs.escrow.methods
+      .setArbiter(arbiter.publicKey)
+      .accountsPartial({ escrow, authority: seller.publicKey })
+      .signers([seller])
+      .rpc();
+    await depositNft(escrow, seller, mint);
+    await depositSol(escrow, buyer, SOL);
+    await raiseDispute(escrow, buyer);
+
+    await fundTokens(mint, buyer.publicKey);
+    await expectError(
+      releaseEscrow(escrow, buyer, {
+        nft: { mint, recipient: buyer.publicKey },
+        solRecipient: seller.publicKey,
+      }),
+      "EscrowDisputed"
+    );
+
+    const sellerBefore = await lamports(seller.publicKey);
+    const buyerBefore = await lamports(buyer.publicKey);
+    // The seller keeps the NFT and 40% of the payment, the buyer gets the rest back
+    await programs.escrow.methods
+      .resolveDispute(new anchor.BN(0.4 * SOL), { seller: {} })
+      .accountsPartial({
+        escrow,
+        arbiter: arbiter.publicKey,
+        seller: seller.publicKey,
+        buyer: buyer.publicKey,
+        escrowTokenAccount: ata(mint, escrow),
+        recipientTokenAccount: ata(mint, seller.publicKey),
+      })
+      .signers([arbiter])
+      .rpc();
+
+    assert.equal(await tokenBalance(ata(mint, seller.publicKey)), "1");
+    assert.equal((await lamports(seller.publicKey)) - sellerBefore, 0.4 * SOL);
+    assert.equal((await lamports(buyer.publicKey)) - buyerBefore, 0.6 * SOL);
+    const state = await programs.escrow.account.escrowState.fetch(escrow);
+    assert.isTrue(state.isReleased);
+    assert.isFalse(state.isDisputed);
+  });
+
+  it("rejects an arbiter on an escrow that is not a direct sale", async () => {
+    const escrow = await createEscrow(seller, { listing: {} });
+
+    await expectError(
+      programs.escrow.methods
+        .setArbiter(arbiter.publicKey)
+        .accountsPartial({ escrow, authority: seller.publicKey })
+        .signers([seller])
+        .rpc(),
+      "NotDirectSale"
+    );
+  });
+
+  it("rejects a dispute on an escrow without an arbiter", async () => {
+    const escrow = await createDirectSale(seller, buyer.publicKey, SOL);
+
+    await expectError(raiseDispute(escrow, seller), "NoArbiter");
+  });
+});
@@ -0,0 +1,103 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import {
+  SOL,
+  ata,
+  auctionPda,
+  buyNow,
+  chainTime,
+  expectError,
+  fundedWallet,
+  lamports,
+  marketplacePda,
+  metadataPda,
+  mintNft,
+  programs,
+  setupMarketplace,
+  tokenBalance,
+  waitUntil,
+  wallet,
+} from "./helpers";
+
+describe("dutch-auctions", () => {
+  let buyer: anchor.web3.Keypair;
+  const startPrice = 2 * SOL;
+  const floorPrice = SOL;
+
+  const createDutchAuction = async (
+    mint: anchor.web3.PublicKey,
+    start: number,
+    floor: number,
+    priceDecay: { linear: {} } | { stepwise: { stepInterval: anchor.BN } },
+    startDelay = 2
+  ) => {
+    const auction = auctionPda(mint, wallet);
+    const startTime = (await chainTime()) + startDelay;
+    await programs.auction.methods
+      .createDutchAuction(
+        new anchor.BN(startTime),
+        new anchor.BN(startTime + 7200),
+        new anchor.BN(start),
+        new anchor.BN(floor),
+        priceDecay,
+        null
+      )
+      .accountsPartial({
+        auction,
+        seller: wallet,
+        mint,
+        sellerTokenAccount: ata(mint, wallet),
+        auctionTokenAccount: ata(mint, auction),
+        metadata: metadataPda(mint),
+        paymentMint: null,
+        auctionPaymentAccount: null,
+        bidHistory: null,
+        marketplace: marketplacePda,
+      })
+      .rpc();
+    return { auction, startTime };
+  };
+
+  before(async () => {
+    await setupMarketplace();
+    buyer = await fundedWallet();
+  });
+
+  it("sells at the current step price through buy_now", async () => {
+    const mint = await mintNft();
+    // Hourly steps over two hours: the price holds at the start price for the first hour
+    const { auction, startTime } = await createDutchAuction(mint, startPrice, floorPrice, {
+      stepwise: { stepInterval: new anchor.BN(3600) },
+    });
+    await waitUntil(startTime);
+
+    const { treasury, feeBasisPoints } =
+      await programs.marketplace.account.marketplaceState.fetch(marketplacePda);
+    const treasuryBefore = await lamports(treasury);
+    await buyNow(mint, wallet, buyer);
+
+    const settled = await programs.auction.account.auctionState.fetch(auction);
+    assert.isTrue(settled.isSettled);
+    assert.ok(settled.winner.equals(buyer.publicKey));
+    assert.equal(await tokenBalance(ata(mint, buyer.publicKey)), "1");
+    assert.equal(
+      (await lamports(treasury)) - treasuryBefore,
+      (startPrice * feeBasisPoints) / 10_000
+    );
+  });
+
+  it("rejects a start price at or below the floor", async () => {
+    const mint = await mintNft();
+    await expectError(
+      createDutchAuction(mint, floorPrice, floorPrice, { linear: {} }),
+      "InvalidStartPrice"
+    );
+  });
+
+  it("rejects buying before the auction starts", async () => {
+    const mint = await mintNft();
+    await createDutchAuction(mint, startPrice, floorPrice, { linear: {} }, 60);
+
+    await expectError(buyNow(mint, wallet, buyer), "AuctionNotStarted");
+  });
+});
@@ -0,0 +1,85 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import {
+  SOL,
+  accountMeta,
+  ata,
+  createEscrow,
+  depositNft,
+  depositRecordPda,
+  depositSol,
+  exists,
+  expectError,
+  fundedWallet,
+  lamports,
+  mintNft,
+  programs,
+  setupMarketplace,
+  tokenBalance,
+  waitUntil,
+  wallet,
+} from "./helpers";
+
+describe("expired-escrows", () => {
+  let authority: anchor.web3.Keypair;
+  let nftDepositor: anchor.web3.Keypair;
+  let solDepositor: anchor.web3.Keypair;
+
+  const refundExpired = (
+    escrow: anchor.web3.PublicKey,
+    remainingAccounts: ReturnType<typeof accountMeta>[]
+  ) =>
+    programs.escrow.methods
+      .refundExpired()
+      .accountsPartial({ escrow, authority: authority.publicKey, caller: wallet })
+      .remainingAccounts(remainingAccounts)
+      .rpc();
+
+  before(async () => {
+    await setupMarketplace();
+    authority = await fundedWallet();
+    nftDepositor = await fundedWallet();
+    solDepositor = await fundedWallet();
+  });
+
+  it("returns every deposit to its depositor once expired", async () => {
+    const mint = await mintNft(nftDepositor);
+    const escrow = await createEscrow(authority, { listing: {} }, 5);
+    await depositNft(escrow, nftDepositor, mint);
+    await depositSol(escrow, solDepositor, SOL / 2);
+
+    const { expiresAt } = await programs.escrow.account.escrowState.fetch(escrow);
+    await waitUntil(expiresAt.toNumber());
+    const solRecord = depositRecordPda(escrow, solDepositor.publicKey);
+    const recordRent = await lamports(solRecord);
+    const before = await lamports(solDepositor.publicKey);
+
+    // The provider wallet, not a party to the escrow, triggers the refund
+    await refundExpired(escrow, [
+      accountMeta(depositRecordPda(escrow, nftDepositor.publicKey)),
+      accountMeta(nftDepositor.publicKey),
+      accountMeta(ata(mint, escrow)),
+      accountMeta(ata(mint, nftDepositor.publicKey)),
+      accountMeta(solRecord),
+      accountMeta(solDepositor.publicKey),
+    ]);
+
+    assert.equal(await tokenBalance(ata(mint, nftDepositor.publicKey)), "1");
+    assert.equal((await lamports(solDepositor.publicKey)) - before, SOL / 2 + recordRent);
+    assert.isFalse(await exists(escrow));
+    assert.isFalse(await exists(ata(mint, escrow)));
+  });
+
+  it("rejects refunding an escrow that has not expired", async () => {
+    const escrow = await createEscrow(authority, { listing: {} }, 3600);
+    await depositSol(escrow, solDepositor, SOL / 2);
+
+    await expectError(
+      refundExpired(escrow, [
+        accountMeta(depositRecordPda(escrow, solDepositor.publicKey)),
+        accountMeta(solDepositor.publicKey),
+      ]),
+      "EscrowNotExpired"
+    );
+  });
+});
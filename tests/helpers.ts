@@ -0,0 +1,568 @@
+import * as anchor from "@coral-xyz/anchor";
+import { IdlTypes, Program } from "@coral-xyz/anchor";
+import * as spl from "@solana/spl-token";
+import { assert } from "chai";
+import { Marketplace } from "../target/types/marketplace";
+import { Listing } from "../target/types/listing";
+import { Auction } from "../target/types/auction";
+import { Escrow } from "../target/types/escrow";
+import { NftMint } from "../target/types/nft_mint";
+import { Royalty } from "../target/types/royalty";
+
+export const TOKEN_METADATA_PROGRAM_ID = new anchor.web3.PublicKey(
+  "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s"
+);
+export const SOL = anchor.web3.LAMPORTS_PER_SOL;
+
+export const provider = anchor.AnchorProvider.env();
+anchor.setProvider(provider);
+
+export const programs = {
+  marketplace: anchor.workspace.marketplace as Program<Marketplace>,
+  listing: anchor.workspace.listing as Program<Listing>,
+  auction: anchor.workspace.auction as Program<Auction>,
+  escrow: anchor.workspace.escrow as Program<Escrow>,
+  nftMint: anchor.workspace.nftMint as Program<NftMint>,
+  royalty: anchor.workspace.royalty as Program<Royalty>,
+};
+
+export const wallet = provider.wallet.publicKey;
+const walletPayer = (provider.wallet as anchor.Wallet).payer;
+
+const pda = (seeds: (Buffer | Uint8Array)[], programId: anchor.web3.PublicKey) =>
+  anchor.web3.PublicKey.findProgramAddressSync(seeds, programId)[0];
+
+export const marketplacePda = pda([Buffer.from("marketplace")], programs.marketplace.programId);
+export const [treasuryPda, treasuryBump] = anchor.web3.PublicKey.findProgramAddressSync(
+  [Buffer.from("treasury")],
+  programs.marketplace.programId
+);
+export const mintAuthorityPda = pda(
+  [Buffer.from("mint_authority"), wallet.toBuffer()],
+  programs.nftMint.programId
+);
+export const royaltyConfigPda = pda([Buffer.from("royalty_config")], programs.royalty.programId);
+
+export const listingPda = (mint: anchor.web3.PublicKey, seller: anchor.web3.PublicKey) =>
+  pda([Buffer.from("listing"), mint.toBuffer(), seller.toBuffer()], programs.listing.programId);
+
+export const auctionPda = (mint: anchor.web3.PublicKey, seller: anchor.web3.PublicKey) =>
+  pda([Buffer.from("auction"), mint.toBuffer(), seller.toBuffer()], programs.auction.programId);
+
+export const bidHistoryPda = (auction: anchor.web3.PublicKey) =>
+  pda([Buffer.from("bid_history"), auction.toBuffer()], programs.auction.programId);
+
+export const metadataPda = (mint: anchor.web3.PublicKey) =>
+  pda(
+    [Buffer.from("metadata"), TOKEN_METADATA_PROGRAM_ID.toBuffer(), mint.toBuffer()],
+    TOKEN_METADATA_PROGRAM_ID
+  );
+
+export const masterEditionPda = (mint: anchor.web3.PublicKey) =>
+  pda(
+    [
+      Buffer.from("metadata"),
+      TOKEN_METADATA_PROGRAM_ID.toBuffer(),
+      mint.toBuffer(),
+      Buffer.from("edition"),
+    ],
+    TOKEN_METADATA_PROGRAM_ID
+  );
+
+export const ata = (mint: anchor.web3.PublicKey, owner: anchor.web3.PublicKey) =>
+  anchor.utils.token.associatedAddress({ mint, owner });
+
+export const lamports = (account: anchor.web3.PublicKey) =>
+  provider.connection.getBalance(account);
+
+export const tokenBalance = async (account: anchor.web3.PublicKey) =>
+  (await provider.connection.getTokenAccountBalance(account)).value.amount;
+
+export const exists = async (account: anchor.web3.PublicKey) =>
+  (await provider.connection.getAccountInfo(account)) !== null;
+
+export const chainTime = async () =>
+  (await provider.connection.getBlockTime(await provider.connection.getSlot())) as number;
+
+export const waitUntil = async (timestamp: number) => {
+  while ((await chainTime()) < timestamp) {
+    await new Promise((resolve) => setTimeout(resolve, 500));
+  }
+};
+
+export const fundedWallet = async (sol = 10) => {
+  const keypair = anchor.web3.Keypair.generate();
+  const signature = await provider.connection.requestAirdrop(keypair.publicKey, sol * SOL);
+  await provider.connection.confirmTransaction(signature);
+  return keypair;
+};
+
+// Runs `action` and checks it fails with the program error named `code`
+export const expectError = async (action: Promise<unknown>, code: string) => {
+  try {
+    await action;
+  } catch (err) {
+    assert.equal(err.error?.errorCode?.code, code, err.toString());
+    return;
+  }
+  assert.fail(`expected ${code}`);
+};
+
+// Mints a 1/1 NFT to `owner` (the provider wallet by default); the owner is its only,
+// verified creator unless `creators` says otherwise
+export const mintNft = async (
+  owner?: anchor.web3.Keypair,
+  creators?: { address: anchor.web3.PublicKey; share: number }[]
+) => {
+  const mint = anchor.web3.Keypair.generate();
+  const payer = owner ? owner.publicKey : wallet;
+  await programs.nftMint.methods
+    .mintNft(
+      {
+        name: "Test",
+        symbol: "TEST",
+        uri: "https://example.com/test.json",
+        sellerFeeBasisPoints: 500,
+        creators: creators ?? [{ address: payer, share: 100 }],
+      },
+      null
+    )
+    .accountsPartial({
+      mint: mint.publicKey,
+      tokenAccount: ata(mint.publicKey, payer),
+      metadata: metadataPda(mint.publicKey),
+      masterEdition: masterEditionPda(mint.publicKey),
+      mintAuthority: mintAuthorityPda,
+      payer,
+      tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+    })
+    .signers(owner ? [mint, owner] : [mint])
+    .rpc();
+  return mint.publicKey;
+};
+
+// Creates the marketplace, mint authority and royalty config once per validator, and registers
+// the trading programs that report sales
+export const setupMarketplace = async () => {
+  if (!(await exists(marketplacePda))) {
+    await programs.marketplace.methods
+      .initializeMarketplace(250, treasuryBump)
+      .accounts({ authority: wallet })
+      .rpc();
+  }
+  if (!(await exists(mintAuthorityPda))) {
+    await programs.nftMint.methods.initialize().accounts({ authority: wallet }).rpc();
+  }
+  if (!(await exists(royaltyConfigPda))) {
+    await programs.royalty.methods
+      .initializeRoyaltyConfig(1000, 250)
+      .accounts({ authority: wallet, mint: await mintNft() })
+      .rpc();
+  }
+
+  const { registeredPrograms } = await programs.marketplace.account.marketplaceState.fetch(
+    marketplacePda
+  );
+  for (const program of [programs.listing, programs.auction, programs.escrow]) {
+    if (!registeredPrograms.some((registered) => registered.equals(program.programId))) {
+      await programs.marketplace.methods
+        .registerTradingProgram(program.programId)
+        .accounts({ authority: wallet })
+        .rpc();
+    }
+  }
+};
+
+export const listNft = (
+  mint: anchor.web3.PublicKey,
+  price: number,
+  seller?: anchor.web3.Keypair,
+  options: { expiry?: number; paymentMint?: anchor.web3.PublicKey } = {}
+) => {
+  const sellerKey = seller ? seller.publicKey : wallet;
+  const listing = listingPda(mint, sellerKey);
+  return programs.listing.methods
+    .listNft(
+      new anchor.BN(price),
+      options.expiry === undefined ? null : new anchor.BN(options.expiry),
+      options.paymentMint ?? null
+    )
+    .accountsPartial({
+      listing,
+      seller: sellerKey,
+      mint,
+      sellerTokenAccount: ata(mint, sellerKey),
+      listingTokenAccount: ata(mint, listing),
+      metadata: metadataPda(mint),
+      marketplace: marketplacePda,
+    })
+    .signers(seller ? [seller] : [])
+    .rpc();
+};
+
+// Buys a listing, paying the royalty to `creators` (the seller by default), or to their payment
+// mint ATAs for SPL-priced listings
+export const buyNft = async (
+  mint: anchor.web3.PublicKey,
+  seller: anchor.web3.PublicKey,
+  buyer: anchor.web3.Keypair,
+  creators: anchor.web3.PublicKey[] = [seller],
+  paymentMint?: anchor.web3.PublicKey
+) => {
+  const listing = listingPda(mint, seller);
+  const { treasury } = await programs.marketplace.account.marketplaceState.fetch(marketplacePda);
+  return programs.listing.methods
+    .buyNft()
+    .accountsPartial({
+      listing,
+      buyer: buyer.publicKey,
+      seller,
+      listingTokenAccount: ata(mint, listing),
+      buyerTokenAccount: ata(mint, buyer.publicKey),
+      mint,
+      metadata: metadataPda(mint),
+      royaltyConfig: royaltyConfigPda,
+      marketplace: marketplacePda,
+      treasury,
+      buyerPaymentAccount: paymentMint ? ata(paymentMint, buyer.publicKey) : null,
+      sellerPaymentAccount: paymentMint ? ata(paymentMint, seller) : null,
+      treasuryPaymentAccount: paymentMint ? ata(paymentMint, treasury) : null,
+    })
+    .remainingAccounts(
+      creators.map((creator) => ({
+        pubkey: paymentMint ? ata(paymentMint, creator) : creator,
+        isSigner: false,
+        isWritable: true,
+      }))
+    )
+    .signers([buyer])
+    .rpc();
+};
+
+// Creates a 6-decimal SPL mint and adds it to the marketplace's accepted payment mints
+export const createPaymentMint = async () => {
+  const mint = await spl.createMint(provider.connection, walletPayer, wallet, null, 6);
+  await programs.marketplace.methods.addPaymentMint(mint).accounts({ authority: wallet }).rpc();
+  return mint;
+};
+
+// Frees the payment mint allowlist slot taken by `createPaymentMint`
+export const removePaymentMint = (mint: anchor.web3.PublicKey) =>
+  programs.marketplace.methods.removePaymentMint(mint).accounts({ authority: wallet }).rpc();
+
+// Creates `owner`'s ATA for `mint`, PDAs included, and mints `amount` into it
+export const fundTokens = async (
+  mint: anchor.web3.PublicKey,
+  owner: anchor.web3.PublicKey,
+  amount = 0
+) => {
+  const account = await spl.getOrCreateAssociatedTokenAccount(
+    provider.connection,
+    walletPayer,
+    mint,
+    owner,
+    true
+  );
+  if (amount > 0) {
+    await spl.mintTo(provider.connection, walletPayer, mint, account.address, walletPayer, amount);
+  }
+  return account.address;
+};
+
+export const bidRefundPda = (auction: anchor.web3.PublicKey, bidder: anchor.web3.PublicKey) =>
+  pda([Buffer.from("bid_refund"), auction.toBuffer(), bidder.toBuffer()], programs.auction.programId);
+
+// Lets auctions last only a few seconds so tests can settle them
+export const useShortAuctions = () =>
+  programs.marketplace.methods
+    .updateAuctionBounds(
+      new anchor.BN(1),
+      new anchor.BN(30 * 24 * 3600),
+      new anchor.BN(3600),
+      new anchor.BN(3600)
+    )
+    .accounts({ authority: wallet })
+    .rpc();
+
+export type AuctionOptions = {
+  seller?: anchor.web3.Keypair;
+  duration?: number;
+  reservePrice?: number;
+  minBidIncrement?: number;
+  buyNowPrice?: number;
+  // `maxExtension` caps anti-sniping extensions this many seconds past the end time
+  antiSniping?: { extensionWindow: number; extensionAmount: number; maxExtension?: number };
+  reserveMode?: { hard: {} } | { soft: { gracePeriod: anchor.BN } };
+  paymentMint?: anchor.web3.PublicKey;
+};
+
+// Creates an English auction opening two seconds from now
+export const createAuction = async (mint: anchor.web3.PublicKey, options: AuctionOptions = {}) => {
+  const seller = options.seller ? options.seller.publicKey : wallet;
+  const auction = auctionPda(mint, seller);
+  const startTime = (await chainTime()) + 2;
+  const endTime = startTime + (options.duration ?? 3600);
+  const antiSniping = options.antiSniping ?? { extensionWindow: 0, extensionAmount: 0 };
+  await programs.auction.methods
+    .createAuction(
+      new anchor.BN(startTime),
+      new anchor.BN(endTime),
+      new anchor.BN(options.reservePrice ?? SOL),
+      new anchor.BN(options.minBidIncrement ?? SOL / 10),
+      options.buyNowPrice === undefined ? null : new anchor.BN(options.buyNowPrice),
+      {
+        extensionWindow: new anchor.BN(antiSniping.extensionWindow),
+        extensionAmount: new anchor.BN(antiSniping.extensionAmount),
+        maxEndTime:
+          antiSniping.maxExtension === undefined
+            ? null
+            : new anchor.BN(endTime + antiSniping.maxExtension),
+      },
+      options.reserveMode ?? { hard: {} },
+      options.paymentMint ?? null
+    )
+    .accountsPartial({
+      auction,
+      seller,
+      mint,
+      sellerTokenAccount: ata(mint, seller),
+      auctionTokenAccount: ata(mint, auction),
+      metadata: metadataPda(mint),
+      paymentMint: options.paymentMint ?? null,
+      auctionPaymentAccount: options.paymentMint ? ata(options.paymentMint, auction) : null,
+      bidHistory: bidHistoryPda(auction),
+      marketplace: marketplacePda,
+    })
+    .signers(options.seller ? [options.seller] : [])
+    .rpc();
+  return { auction, startTime, endTime };
+};
+
+// Bids on an English auction, crediting `previousBidder`'s refund ledger when outbidding
+export const placeBid = (
+  auction: anchor.web3.PublicKey,
+  bidder: anchor.web3.Keypair,
+  amount: number,
+  options: { previousBidder?: anchor.web3.PublicKey; paymentMint?: anchor.web3.PublicKey } = {}
+) =>
+  programs.auction.methods
+    .placeBid(new anchor.BN(amount))
+    .accountsPartial({
+      auction,
+      bidder: bidder.publicKey,
+      bidderRefund: bidRefundPda(auction, bidder.publicKey),
+      previousBidderRefund: options.previousBidder
+        ? bidRefundPda(auction, options.previousBidder)
+        : null,
+      bidHistory: bidHistoryPda(auction),
+      bidderPaymentAccount: options.paymentMint
+        ? ata(options.paymentMint, bidder.publicKey)
+        : null,
+      auctionPaymentAccount: options.paymentMint ? ata(options.paymentMint, auction) : null,
+    })
+    .signers([bidder])
+    .rpc();
+
+// Settles an ended auction, paying royalties to `creators` (wallets, or their payment mint ATAs)
+export const claimAuction = async (
+  mint: anchor.web3.PublicKey,
+  seller: anchor.web3.PublicKey,
+  winner: anchor.web3.PublicKey,
+  creators: anchor.web3.PublicKey[],
+  options: { paymentMint?: anchor.web3.PublicKey; winnerRefund?: boolean } = {}
+) => {
+  const auction = auctionPda(mint, seller);
+  const { treasury } = await programs.marketplace.account.marketplaceState.fetch(marketplacePda);
+  const paymentMint = options.paymentMint;
+  return programs.auction.methods
+    .claimAuction()
+    .accountsPartial({
+      auction,
+      claimer: wallet,
+      seller,
+      winner,
+      auctionTokenAccount: ata(mint, auction),
+      sellerTokenAccount: ata(mint, seller),
+      mint,
+      metadata: metadataPda(mint),
+      royaltyConfig: royaltyConfigPda,
+      winnerTokenAccount: ata(mint, winner),
+      marketplace: marketplacePda,
+      treasury,
+      auctionPaymentAccount: paymentMint ? ata(paymentMint, auction) : null,
+      sellerPaymentAccount: paymentMint ? ata(paymentMint, seller) : null,
+      winnerRefund: options.winnerRefund ? bidRefundPda(auction, winner) : null,
+      treasuryPaymentAccount: paymentMint ? ata(paymentMint, treasury) : null,
+    })
+    .remainingAccounts(
+      creators.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true }))
+    )
+    .rpc();
+};
+
+// Buys a Dutch auction at its current price, or an English one at its buy-now price
+export const buyNow = async (
+  mint: anchor.web3.PublicKey,
+  seller: anchor.web3.PublicKey,
+  buyer: anchor.web3.Keypair,
+  options: { previousBidder?: anchor.web3.PublicKey } = {}
+) => {
+  const auction = auctionPda(mint, seller);
+  const { treasury } = await programs.marketplace.account.marketplaceState.fetch(marketplacePda);
+  return programs.auction.methods
+    .buyNow()
+    .accountsPartial({
+      auction,
+      buyer: buyer.publicKey,
+      seller,
+      auctionTokenAccount: ata(mint, auction),
+      buyerTokenAccount: ata(mint, buyer.publicKey),
+      mint,
+      metadata: metadataPda(mint),
+      royaltyConfig: royaltyConfigPda,
+      marketplace: marketplacePda,
+      treasury,
+      buyerPaymentAccount: null,
+      sellerPaymentAccount: null,
+      treasuryPaymentAccount: null,
+      previousBidderRefund: options.previousBidder
+        ? bidRefundPda(auction, options.previousBidder)
+        : null,
+    })
+    // NFTs minted by `mintNft` pay their royalty to the seller
+    .remainingAccounts([{ pubkey: seller, isSigner: false, isWritable: true }])
+    .signers([buyer])
+    .rpc();
+};
+
+export const accountMeta = (pubkey: anchor.web3.PublicKey, isWritable = true) => ({
+  pubkey,
+  isSigner: false,
+  isWritable,
+});
+
+export const escrowPda = (authority: anchor.web3.PublicKey, createdAt: number) =>
+  pda(
+    [Buffer.from("escrow"), authority.toBuffer(), new anchor.BN(createdAt).toArrayLike(Buffer, "le", 8)],
+    programs.escrow.programId
+  );
+
+export const swapPda = (escrow: anchor.web3.PublicKey) =>
+  pda([Buffer.from("swap"), escrow.toBuffer()], programs.escrow.programId);
+
+// Escrow addresses are seeded with the clock at creation, so `create` is retried until the
+// address guessed from the latest block time matches the slot it lands in
+const withEscrowPda = async (
+  authority: anchor.web3.PublicKey,
+  create: (escrow: anchor.web3.PublicKey) => Promise<unknown>
+) => {
+  for (let attempt = 1; ; attempt++) {
+    const escrow = escrowPda(authority, await chainTime());
+    try {
+      await create(escrow);
+      return escrow;
+    } catch (err) {
+      if (attempt === 5 || err.error?.errorCode?.code !== "ConstraintSeeds") {
+        throw err;
+      }
+    }
+  }
+};
+
+export const createEscrow = (
+  authority: anchor.web3.Keypair,
+  escrowType: IdlTypes<Escrow>["escrowType"],
+  duration?: number
+) =>
+  withEscrowPda(authority.publicKey, (escrow) =>
+    programs.escrow.methods
+      .createEscrow(escrowType, duration === undefined ? null : new anchor.BN(duration))
+      .accountsPartial({ escrow, authority: authority.publicKey })
+      .signers([authority])
+      .rpc()
+  );
+
+export const createDirectSale = (
+  seller: anchor.web3.Keypair,
+  buyer: anchor.web3.PublicKey,
+  price: number,
+  duration?: number
+) =>
+  withEscrowPda(seller.publicKey, (escrow) =>
+    programs.escrow.methods
+      .createDirectSale(
+        buyer,
+        new anchor.BN(price),
+        duration === undefined ? null : new anchor.BN(duration)
+      )
+      .accountsPartial({ escrow, authority: seller.publicKey })
+      .signers([seller])
+      .rpc()
+  );
+
+export const depositRecordPda = (escrow: anchor.web3.PublicKey, depositor: anchor.web3.PublicKey) =>
+  pda([Buffer.from("deposit"), escrow.toBuffer(), depositor.toBuffer()], programs.escrow.programId);
+
+export const depositNft = (
+  escrow: anchor.web3.PublicKey,
+  depositor: anchor.web3.Keypair,
+  mint: anchor.web3.PublicKey
+) =>
+  programs.escrow.methods
+    .depositNft()
+    .accountsPartial({
+      escrow,
+      authority: depositor.publicKey,
+      depositor: depositor.publicKey,
+      mint,
+      depositorTokenAccount: ata(mint, depositor.publicKey),
+      escrowTokenAccount: ata(mint, escrow),
+      depositRecord: depositRecordPda(escrow, depositor.publicKey),
+    })
+    .signers([depositor])
+    .rpc();
+
+export const depositSol = (
+  escrow: anchor.web3.PublicKey,
+  depositor: anchor.web3.Keypair,
+  amount: number
+) =>
+  programs.escrow.methods
+    .depositSol(new anchor.BN(amount))
+    .accountsPartial({
+      escrow,
+      authority: depositor.publicKey,
+      depositor: depositor.publicKey,
+      depositRecord: depositRecordPda(escrow, depositor.publicKey),
+    })
+    .signers([depositor])
+    .rpc();
+
+export type ReleaseOptions = {
+  // The first deposited NFT and the wallet it goes to
+  nft?: { mint: anchor.web3.PublicKey; recipient: anchor.web3.PublicKey };
+  swap?: anchor.web3.PublicKey;
+  solRecipient?: anchor.web3.PublicKey;
+  remainingAccounts?: ReturnType<typeof accountMeta>[];
+};
+
+// Releases an escrow signed by `authority`, who also receives the SOL unless told otherwise
+export const releaseEscrow = (
+  escrow: anchor.web3.PublicKey,
+  authority: anchor.web3.Keypair,
+  options: ReleaseOptions = {}
+) =>
+  programs.escrow.methods
+    .releaseAssets()
+    .accountsPartial({
+      escrow,
+      authority: authority.publicKey,
+      escrowTokenAccount: options.nft ? ata(options.nft.mint, escrow) : null,
+      recipientTokenAccount: options.nft ? ata(options.nft.mint, options.nft.recipient) : null,
+      swap: options.swap ?? null,
+      solRecipient: options.solRecipient ?? authority.publicKey,
+      marketplace: marketplacePda,
+    })
+    .remainingAccounts(options.remainingAccounts ?? [])
+    .signers([authority])
+    .rpc();
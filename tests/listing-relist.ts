@@ -0,0 +1,378 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { assert } from "chai";
+import { Marketplace } from "../target/types/marketplace";
+import { Listing } from "../target/types/listing";
+import { Auction } from "../target/types/auction";
+import { NftMint } from "../target/types/nft_mint";
+import { Royalty } from "../target/types/royalty";
+
+const TOKEN_METADATA_PROGRAM_ID = new anchor.web3.PublicKey(
+  "metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s"
+);
+
+describe("listing-relist", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const marketplaceProgram = anchor.workspace.marketplace as Program<Marketplace>;
+  const listingProgram = anchor.workspace.listing as Program<Listing>;
+  const auctionProgram = anchor.workspace.auction as Program<Auction>;
+  const nftMintProgram = anchor.workspace.nftMint as Program<NftMint>;
+  const royaltyProgram = anchor.workspace.royalty as Program<Royalty>;
+
+  const seller = provider.wallet.publicKey;
+  const buyer = anchor.web3.Keypair.generate();
+
+  const [marketplacePda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("marketplace")],
+    marketplaceProgram.programId
+  );
+  const [, treasuryBump] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("treasury")],
+    marketplaceProgram.programId
+  );
+  const [mintAuthorityPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("mint_authority"), seller.toBuffer()],
+    nftMintProgram.programId
+  );
+  const [royaltyConfigPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("royalty_config")],
+    royaltyProgram.programId
+  );
+
+  const listingPda = (mint: anchor.web3.PublicKey, owner: anchor.web3.PublicKey) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("listing"), mint.toBuffer(), owner.toBuffer()],
+      listingProgram.programId
+    )[0];
+
+  const auctionPda = (mint: anchor.web3.PublicKey, owner: anchor.web3.PublicKey) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("auction"), mint.toBuffer(), owner.toBuffer()],
+      auctionProgram.programId
+    )[0];
+
+  const bidHistoryPda = (auction: anchor.web3.PublicKey) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("bid_history"), auction.toBuffer()],
+      auctionProgram.programId
+    )[0];
+
+  const metadataPda = (mint: anchor.web3.PublicKey) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("metadata"), TOKEN_METADATA_PROGRAM_ID.toBuffer(), mint.toBuffer()],
+      TOKEN_METADATA_PROGRAM_ID
+    )[0];
+
+  const masterEditionPda = (mint: anchor.web3.PublicKey) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("metadata"),
+        TOKEN_METADATA_PROGRAM_ID.toBuffer(),
+        mint.toBuffer(),
+        Buffer.from("edition"),
+      ],
+      TOKEN_METADATA_PROGRAM_ID
+    )[0];
+
+  const ata = (mint: anchor.web3.PublicKey, owner: anchor.web3.PublicKey) =>
+    anchor.utils.token.associatedAddress({ mint, owner });
+
+  const mintNft = async () => {
+    const mint = anchor.web3.Keypair.generate();
+    await nftMintProgram.methods
+      .mintNft(
+        {
+          name: "Relist",
+          symbol: "RLST",
+          uri: "https://example.com/relist.json",
+          sellerFeeBasisPoints: 500,
+          creators: [{ address: seller, share: 100 }],
+        },
+        null
+      )
+      .accountsPartial({
+        mint: mint.publicKey,
+        tokenAccount: ata(mint.publicKey, seller),
+        metadata: metadataPda(mint.publicKey),
+        masterEdition: masterEditionPda(mint.publicKey),
+        mintAuthority: mintAuthorityPda,
+        payer: seller,
+        tokenMetadataProgram: TOKEN_METADATA_PROGRAM_ID,
+      })
+      .signers([mint])
+      .rpc();
+    return mint.publicKey;
+  };
+
+  const listNft = (
+    mint: anchor.web3.PublicKey,
+    owner: anchor.web3.PublicKey,
+    price: number,
+    signers: anchor.web3.Keypair[] = []
+  ) =>
+    listingProgram.methods
+      .listNft(new anchor.BN(price), null, null)
+      .accountsPartial({
+        listing: listingPda(mint, owner),
+        seller: owner,
+        mint,
+        sellerTokenAccount: ata(mint, owner),
+        listingTokenAccount: ata(mint, listingPda(mint, owner)),
+        metadata: metadataPda(mint),
+        marketplace: marketplacePda,
+      })
+      .signers(signers)
+      .rpc();
+
+  const chainTime = async () =>
+    (await provider.connection.getBlockTime(await provider.connection.getSlot())) as number;
+
+  // English auction opening a few seconds from now, for the marketplace's minimum duration
+  const createAuction = async (mint: anchor.web3.PublicKey, buyNowPrice: number | null) => {
+    const startTime = (await chainTime()) + 2;
+    const auction = auctionPda(mint, seller);
+    await auctionProgram.methods
+      .createAuction(
+        new anchor.BN(startTime),
+        new anchor.BN(startTime + 3600),
+        new anchor.BN(anchor.web3.LAMPORTS_PER_SOL),
+        new anchor.BN(anchor.web3.LAMPORTS_PER_SOL / 10),
+        buyNowPrice === null ? null : new anchor.BN(buyNowPrice),
+        { extensionWindow: new anchor.BN(0), extensionAmount: new anchor.BN(0), maxEndTime: null },
+        { hard: {} },
+        null
+      )
+      .accountsPartial({
+        auction,
+        seller,
+        mint,
+        sellerTokenAccount: ata(mint, seller),
+        auctionTokenAccount: ata(mint, auction),
+        metadata: metadataPda(mint),
+        paymentMint: null,
+        auctionPaymentAccount: null,
+        bidHistory: bidHistoryPda(auction),
+        marketplace: marketplacePda,
+      })
+      .rpc();
+    return startTime;
+  };
+
+  const closeAuction = (mint: anchor.web3.PublicKey) => {
+    const auction = auctionPda(mint, seller);
+    return auctionProgram.methods
+      .closeAuction()
+      .accountsPartial({
+        auction,
+        seller,
+        auctionTokenAccount: ata(mint, auction),
+        auctionPaymentAccount: null,
+        bidHistory: bidHistoryPda(auction),
+      })
+      .rpc();
+  };
+
+  const tokenBalance = async (account: anchor.web3.PublicKey) =>
+    (await provider.connection.getTokenAccountBalance(account)).value.amount;
+
+  before(async () => {
+    if ((await provider.connection.getAccountInfo(marketplacePda)) === null) {
+      await marketplaceProgram.methods
+        .initializeMarketplace(250, treasuryBump)
+        .accounts({ authority: seller })
+        .rpc();
+    }
+    // Sales report their stats to the marketplace, which only accepts registered programs
+    const { registeredPrograms } = await marketplaceProgram.account.marketplaceState.fetch(
+      marketplacePda
+    );
+    for (const programId of [listingProgram.programId, auctionProgram.programId]) {
+      if (!registeredPrograms.some((registered) => registered.equals(programId))) {
+        await marketplaceProgram.methods
+          .registerTradingProgram(programId)
+          .accounts({ authority: seller })
+          .rpc();
+      }
+    }
+    if ((await provider.connection.getAccountInfo(mintAuthorityPda)) === null) {
+      await nftMintProgram.methods.initialize().accounts({ authority: seller }).rpc();
+    }
+
+    const signature = await provider.connection.requestAirdrop(
+      buyer.publicKey,
+      10 * anchor.web3.LAMPORTS_PER_SOL
+    );
+    await provider.connection.confirmTransaction(signature);
+
+    if ((await provider.connection.getAccountInfo(royaltyConfigPda)) === null) {
+      await royaltyProgram.methods
+        .initializeRoyaltyConfig(1000, 250)
+        .accounts({ authority: seller, mint: await mintNft() })
+        .rpc();
+    }
+  });
+
+  it("relists a mint after canceling its listing", async () => {
+    const mint = await mintNft();
+    const listing = listingPda(mint, seller);
+
+    await listNft(mint, seller, anchor.web3.LAMPORTS_PER_SOL);
+    await listingProgram.methods
+      .cancelListing()
+      .accountsPartial({
+        listing,
+        seller,
+        listingTokenAccount: ata(mint, listing),
+        sellerTokenAccount: ata(mint, seller),
+      })
+      .rpc();
+
+    // Canceling closes the listing and its escrow token account
+    assert.isNull(await provider.connection.getAccountInfo(listing));
+    assert.isNull(await provider.connection.getAccountInfo(ata(mint, listing)));
+    assert.equal(await tokenBalance(ata(mint, seller)), "1");
+
+    await listNft(mint, seller, 2 * anchor.web3.LAMPORTS_PER_SOL);
+
+    const relisted = await listingProgram.account.listingState.fetch(listing);
+    assert.ok(relisted.seller.equals(seller));
+    assert.ok(relisted.price.eq(new anchor.BN(2 * anchor.web3.LAMPORTS_PER_SOL)));
+    assert.isTrue(relisted.isActive);
+    assert.equal(await tokenBalance(ata(mint, listing)), "1");
+  });
+
+  it("lets the buyer relist a mint it bought", async () => {
+    const mint = await mintNft();
+    const listing = listingPda(mint, seller);
+    const marketplace = await marketplaceProgram.account.marketplaceState.fetch(
+      marketplacePda
+    );
+
+    await listNft(mint, seller, anchor.web3.LAMPORTS_PER_SOL);
+    await listingProgram.methods
+      .buyNft()
+      .accountsPartial({
+        listing,
+        buyer: buyer.publicKey,
+        seller,
+        listingTokenAccount: ata(mint, listing),
+        buyerTokenAccount: ata(mint, buyer.publicKey),
+        mint,
+        metadata: metadataPda(mint),
+        royaltyConfig: royaltyConfigPda,
+        marketplace: marketplacePda,
+        treasury: marketplace.treasury,
+        buyerPaymentAccount: null,
+        sellerPaymentAccount: null,
+        treasuryPaymentAccount: null,
+      })
+      // The seller is the NFT's only creator and receives its royalty
+      .remainingAccounts([{ pubkey: seller, isSigner: false, isWritable: true }])
+      .signers([buyer])
+      .rpc();
+
+    // The sold listing is kept as a record of the sale, its token account is closed
+    const sold = await listingProgram.account.listingState.fetch(listing);
+    assert.isFalse(sold.isActive);
+    assert.ok(sold.buyer.equals(buyer.publicKey));
+    assert.isNull(await provider.connection.getAccountInfo(ata(mint, listing)));
+    assert.equal(await tokenBalance(ata(mint, buyer.publicKey)), "1");
+
+    const buyerListing = listingPda(mint, buyer.publicKey);
+    await listNft(mint, buyer.publicKey, 3 * anchor.web3.LAMPORTS_PER_SOL, [buyer]);
+
+    const relisted = await listingProgram.account.listingState.fetch(buyerListing);
+    assert.ok(relisted.seller.equals(buyer.publicKey));
+    assert.ok(relisted.mint.equals(mint));
+    assert.isTrue(relisted.isActive);
+    assert.equal(await tokenBalance(ata(mint, buyerListing)), "1");
+  });
+
+  it("recreates an auction after canceling and closing it", async () => {
+    const mint = await mintNft();
+    const auction = auctionPda(mint, seller);
+
+    await createAuction(mint, null);
+
+    // An open auction cannot be closed
+    try {
+      await closeAuction(mint);
+      assert.fail("closed an open auction");
+    } catch (err) {
+      assert.equal(err.error.errorCode.code, "AuctionNotSettled");
+    }
+
+    await auctionProgram.methods
+      .cancelAuction()
+      .accountsPartial({
+        auction,
+        seller,
+        auctionTokenAccount: ata(mint, auction),
+        sellerTokenAccount: ata(mint, seller),
+      })
+      .rpc();
+    await closeAuction(mint);
+
+    // Closing frees the auction PDA along with its bid history and escrow token account
+    assert.isNull(await provider.connection.getAccountInfo(auction));
+    assert.isNull(await provider.connection.getAccountInfo(bidHistoryPda(auction)));
+    assert.isNull(await provider.connection.getAccountInfo(ata(mint, auction)));
+    assert.equal(await tokenBalance(ata(mint, seller)), "1");
+
+    await createAuction(mint, null);
+
+    const recreated = await auctionProgram.account.auctionState.fetch(auction);
+    assert.ok(recreated.seller.equals(seller));
+    assert.isFalse(recreated.isCanceled);
+    assert.isFalse(recreated.isSettled);
+    assert.equal(await tokenBalance(ata(mint, auction)), "1");
+  });
+
+  it("closes an auction settled through buy-now", async () => {
+    const mint = await mintNft();
+    const auction = auctionPda(mint, seller);
+    const marketplace = await marketplaceProgram.account.marketplaceState.fetch(
+      marketplacePda
+    );
+
+    const startTime = await createAuction(mint, 2 * anchor.web3.LAMPORTS_PER_SOL);
+    while ((await chainTime()) < startTime) {
+      await new Promise((resolve) => setTimeout(resolve, 500));
+    }
+
+    await auctionProgram.methods
+      .buyNow()
+      .accountsPartial({
+        auction,
+        buyer: buyer.publicKey,
+        seller,
+        auctionTokenAccount: ata(mint, auction),
+        buyerTokenAccount: ata(mint, buyer.publicKey),
+        mint,
+        metadata: metadataPda(mint),
+        royaltyConfig: royaltyConfigPda,
+        marketplace: marketplacePda,
+        treasury: marketplace.treasury,
+        buyerPaymentAccount: null,
+        sellerPaymentAccount: null,
+        treasuryPaymentAccount: null,
+        previousBidderRefund: null,
+      })
+      // The seller is the NFT's only creator and receives its royalty
+      .remainingAccounts([{ pubkey: seller, isSigner: false, isWritable: true }])
+      .signers([buyer])
+      .rpc();
+
+    const settled = await auctionProgram.account.auctionState.fetch(auction);
+    assert.isTrue(settled.isSettled);
+    assert.ok(settled.winner.equals(buyer.publicKey));
+    assert.equal(await tokenBalance(ata(mint, buyer.publicKey)), "1");
+
+    await closeAuction(mint);
+
+    assert.isNull(await provider.connection.getAccountInfo(auction));
+    assert.isNull(await provider.connection.getAccountInfo(bidHistoryPda(auction)));
+  });
+});
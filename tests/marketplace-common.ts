@@ -0,0 +1,58 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { assert } from "chai";
+import { Marketplace } from "../target/types/marketplace";
+import { Listing } from "../target/types/listing";
+import { Auction } from "../target/types/auction";
+import { Escrow } from "../target/types/escrow";
+
+describe("marketplace-common", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const marketplaceProgram = anchor.workspace.marketplace as Program<Marketplace>;
+  const consumers = {
+    listing: anchor.workspace.listing as Program<Listing>,
+    auction: anchor.workspace.auction as Program<Auction>,
+    escrow: anchor.workspace.escrow as Program<Escrow>,
+  };
+
+  const [marketplacePda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("marketplace")],
+    marketplaceProgram.programId
+  );
+  const [, treasuryBump] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("treasury")],
+    marketplaceProgram.programId
+  );
+
+  before(async () => {
+    const existing = await provider.connection.getAccountInfo(marketplacePda);
+    if (existing === null) {
+      await marketplaceProgram.methods
+        .initializeMarketplace(250, treasuryBump)
+        .accounts({ authority: provider.wallet.publicKey })
+        .rpc();
+    }
+  });
+
+  for (const [name, program] of Object.entries(consumers)) {
+    it(`deserializes the real marketplace account in ${name}`, async () => {
+      const expected = await marketplaceProgram.account.marketplaceState.fetch(
+        marketplacePda
+      );
+      const decoded = await program.account.marketplaceState.fetch(
+        marketplacePda
+      );
+
+      assert.ok(decoded.authority.equals(expected.authority));
+      assert.ok(decoded.treasury.equals(expected.treasury));
+      assert.equal(decoded.feeBasisPoints, expected.feeBasisPoints);
+      assert.equal(decoded.treasuryBump, expected.treasuryBump);
+      assert.equal(decoded.isPaused, expected.isPaused);
+      assert.ok(decoded.totalVolume.eq(expected.totalVolume));
+      assert.ok(decoded.totalSales.eq(expected.totalSales));
+      assert.equal(decoded.bump, expected.bump);
+    });
+  }
+});
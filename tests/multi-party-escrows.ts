@@ -0,0 +1,78 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import {
+  SOL,
+  accountMeta,
+  ata,
+  createEscrow,
+  depositNft,
+  depositRecordPda,
+  depositSol,
+  expectError,
+  fundTokens,
+  fundedWallet,
+  mintNft,
+  programs,
+  releaseEscrow,
+  setupMarketplace,
+  tokenBalance,
+} from "./helpers";
+
+describe("multi-party-escrows", () => {
+  let authority: anchor.web3.Keypair;
+  let first: anchor.web3.Keypair;
+  let second: anchor.web3.Keypair;
+
+  before(async () => {
+    await setupMarketplace();
+    authority = await fundedWallet();
+    first = await fundedWallet();
+    second = await fundedWallet();
+  });
+
+  it("records each depositor's NFTs and SOL and releases all of them", async () => {
+    const mints = [await mintNft(first), await mintNft(first), await mintNft(second)];
+    const escrow = await createEscrow(authority, { listing: {} });
+    await depositNft(escrow, first, mints[0]);
+    await depositNft(escrow, first, mints[1]);
+    await depositNft(escrow, second, mints[2]);
+    await depositSol(escrow, second, SOL);
+    await depositSol(escrow, second, SOL / 2);
+
+    const firstRecord = await programs.escrow.account.depositRecord.fetch(
+      depositRecordPda(escrow, first.publicKey)
+    );
+    assert.deepEqual(
+      firstRecord.mints.map((mint) => mint.toBase58()),
+      [mints[0].toBase58(), mints[1].toBase58()]
+    );
+    assert.ok(firstRecord.solAmount.isZero());
+    const secondRecord = await programs.escrow.account.depositRecord.fetch(
+      depositRecordPda(escrow, second.publicKey)
+    );
+    assert.equal(secondRecord.solAmount.toNumber(), 1.5 * SOL);
+    const state = await programs.escrow.account.escrowState.fetch(escrow);
+    assert.equal(state.nftCount, 3);
+    assert.equal(state.depositorCount, 2);
+
+    for (const mint of mints) {
+      await fundTokens(mint, authority.publicKey);
+    }
+    const release = (extraNfts: anchor.web3.PublicKey[]) =>
+      releaseEscrow(escrow, authority, {
+        nft: { mint: mints[0], recipient: authority.publicKey },
+        remainingAccounts: extraNfts.flatMap((mint) => [
+          accountMeta(ata(mint, escrow)),
+          accountMeta(ata(mint, authority.publicKey)),
+        ]),
+      });
+
+    // Every escrowed NFT has to leave with the release
+    await expectError(release([mints[1]]), "InvalidDepositAccounts");
+    await release(mints.slice(1));
+
+    for (const mint of mints) {
+      assert.equal(await tokenBalance(ata(mint, authority.publicKey)), "1");
+    }
+  });
+});
@@ -0,0 +1,113 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import {
+  SOL,
+  ata,
+  chainTime,
+  exists,
+  expectError,
+  fundedWallet,
+  listNft,
+  listingPda,
+  marketplacePda,
+  metadataPda,
+  mintNft,
+  programs,
+  royaltyConfigPda,
+  setupMarketplace,
+  tokenBalance,
+  waitUntil,
+  wallet,
+} from "./helpers";
+
+describe("nft-offers", () => {
+  let buyer: anchor.web3.Keypair;
+  const amount = (8 * SOL) / 10;
+
+  const offerPda = (mint: anchor.web3.PublicKey) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("offer"), mint.toBuffer(), buyer.publicKey.toBuffer()],
+      programs.listing.programId
+    )[0];
+
+  const makeOffer = (mint: anchor.web3.PublicKey, offerAmount: number, expiry?: number) =>
+    programs.listing.methods
+      .makeOffer(new anchor.BN(offerAmount), expiry === undefined ? null : new anchor.BN(expiry))
+      .accountsPartial({
+        offer: offerPda(mint),
+        buyer: buyer.publicKey,
+        mint,
+        marketplace: marketplacePda,
+      })
+      .signers([buyer])
+      .rpc();
+
+  const acceptOffer = async (mint: anchor.web3.PublicKey, listed: boolean) => {
+    const listing = listingPda(mint, wallet);
+    const { treasury } = await programs.marketplace.account.marketplaceState.fetch(
+      marketplacePda
+    );
+    return programs.listing.methods
+      .acceptOffer()
+      .accountsPartial({
+        offer: offerPda(mint),
+        seller: wallet,
+        buyer: buyer.publicKey,
+        listing: listed ? listing : null,
+        listingTokenAccount: listed ? ata(mint, listing) : null,
+        sellerTokenAccount: ata(mint, wallet),
+        buyerTokenAccount: ata(mint, buyer.publicKey),
+        mint,
+        metadata: metadataPda(mint),
+        royaltyConfig: royaltyConfigPda,
+        marketplace: marketplacePda,
+        treasury,
+      })
+      // The seller is the NFT's only creator and receives its royalty
+      .remainingAccounts([{ pubkey: wallet, isSigner: false, isWritable: true }])
+      .rpc();
+  };
+
+  before(async () => {
+    await setupMarketplace();
+    buyer = await fundedWallet();
+  });
+
+  it("sells a listed NFT into an offer and closes the listing", async () => {
+    const mint = await mintNft();
+    const listing = listingPda(mint, wallet);
+    await listNft(mint, SOL);
+    await makeOffer(mint, amount);
+
+    await acceptOffer(mint, true);
+
+    assert.equal(await tokenBalance(ata(mint, buyer.publicKey)), "1");
+    assert.isFalse(await exists(offerPda(mint)));
+    assert.isFalse(await exists(listing));
+    assert.isFalse(await exists(ata(mint, listing)));
+  });
+
+  it("sells an unlisted NFT into an offer", async () => {
+    const mint = await mintNft();
+    await makeOffer(mint, amount);
+
+    await acceptOffer(mint, false);
+
+    assert.equal(await tokenBalance(ata(mint, buyer.publicKey)), "1");
+    assert.equal(await tokenBalance(ata(mint, wallet)), "0");
+  });
+
+  it("rejects an offer of nothing", async () => {
+    const mint = await mintNft();
+    await expectError(makeOffer(mint, 0), "InvalidPrice");
+  });
+
+  it("rejects accepting an expired offer", async () => {
+    const mint = await mintNft();
+    const expiry = (await chainTime()) + 2;
+    await makeOffer(mint, amount, expiry);
+    await waitUntil(expiry + 1);
+
+    await expectError(acceptOffer(mint, false), "OfferExpired");
+  });
+});
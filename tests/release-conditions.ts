@@ -0,0 +1,130 @@
+import * as anchor from "@coral-xyz/anchor";
+import { IdlTypes } from "@coral-xyz/anchor";
+import { assert } from "chai";
+import { Escrow } from "../target/types/escrow";
+import {
+  SOL,
+  accountMeta,
+  buyNft,
+  chainTime,
+  createEscrow,
+  depositSol,
+  expectError,
+  fundedWallet,
+  lamports,
+  listNft,
+  listingPda,
+  mintNft,
+  programs,
+  releaseEscrow,
+  setupMarketplace,
+  wallet,
+} from "./helpers";
+
+describe("release-conditions", () => {
+  let authority: anchor.web3.Keypair;
+  let depositor: anchor.web3.Keypair;
+  let approvers: anchor.web3.Keypair[];
+
+  const setConditions = (
+    escrow: anchor.web3.PublicKey,
+    logic: IdlTypes<Escrow>["conditionLogic"],
+    conditions: IdlTypes<Escrow>["releaseCondition"][],
+    approverKeys: anchor.web3.PublicKey[] = []
+  ) =>
+    programs.escrow.methods
+      .setReleaseConditions(logic, conditions, approverKeys)
+      .accountsPartial({ escrow, authority: authority.publicKey })
+      .signers([authority])
+      .rpc();
+
+  const approve = (escrow: anchor.web3.PublicKey, approver: anchor.web3.Keypair) =>
+    programs.escrow.methods
+      .approveRelease()
+      .accountsPartial({ escrow, approver: approver.publicKey })
+      .signers([approver])
+      .rpc();
+
+  before(async () => {
+    await setupMarketplace();
+    authority = await fundedWallet();
+    depositor = await fundedWallet();
+    approvers = [await fundedWallet(1), await fundedWallet(1), await fundedWallet(1)];
+  });
+
+  it("releases once two of three named signers approve", async () => {
+    const escrow = await createEscrow(authority, { listing: {} });
+    await setConditions(
+      escrow,
+      { all: {} },
+      [{ approvals: { threshold: 2 } }],
+      approvers.map((approver) => approver.publicKey)
+    );
+    await depositSol(escrow, depositor, SOL);
+
+    await approve(escrow, approvers[0]);
+    await expectError(releaseEscrow(escrow, authority), "ReleaseConditionsNotMet");
+
+    await approve(escrow, approvers[2]);
+    const before = await lamports(authority.publicKey);
+    await releaseEscrow(escrow, authority);
+
+    assert.equal((await lamports(authority.publicKey)) - before, SOL);
+  });
+
+  it("releases when either the listing sells or a far-off date passes", async () => {
+    const mint = await mintNft();
+    await listNft(mint, SOL);
+    const listing = listingPda(mint, wallet);
+    const escrow = await createEscrow(authority, { listing: {} });
+    await setConditions(escrow, { any: {} }, [
+      { listingSettled: { listing } },
+      { afterTimestamp: { timestamp: new anchor.BN((await chainTime()) + 3600) } },
+    ]);
+    await depositSol(escrow, depositor, SOL);
+
+    await expectError(
+      releaseEscrow(escrow, authority, { remainingAccounts: [accountMeta(listing, false)] }),
+      "ReleaseConditionsNotMet"
+    );
+
+    await buyNft(mint, wallet, depositor);
+    await releaseEscrow(escrow, authority, { remainingAccounts: [accountMeta(listing, false)] });
+
+    const state = await programs.escrow.account.escrowState.fetch(escrow);
+    assert.isTrue(state.isReleased);
+  });
+
+  it("still counts a listing as settled after the seller relists it", async () => {
+    const seller = await fundedWallet();
+    const mint = await mintNft(seller);
+    await listNft(mint, SOL, seller);
+    const listing = listingPda(mint, seller.publicKey);
+    const escrow = await createEscrow(authority, { listing: {} });
+    await setConditions(escrow, { all: {} }, [{ listingSettled: { listing } }]);
+    await depositSol(escrow, depositor, SOL);
+
+    await buyNft(mint, seller.publicKey, depositor);
+    // The seller buys the NFT back and lists it again under the same listing account
+    await listNft(mint, SOL, depositor);
+    await buyNft(mint, depositor.publicKey, seller, [seller.publicKey]);
+    await listNft(mint, SOL, seller);
+    const relisted = await programs.listing.account.listingState.fetch(listing);
+    assert.isTrue(relisted.isActive);
+    assert.isNull(relisted.buyer);
+
+    await releaseEscrow(escrow, authority, { remainingAccounts: [accountMeta(listing, false)] });
+
+    const state = await programs.escrow.account.escrowState.fetch(escrow);
+    assert.isTrue(state.isReleased);
+  });
+
+  it("rejects approvals from signers who were not named", async () => {
+    const escrow = await createEscrow(authority, { listing: {} });
+    await setConditions(escrow, { all: {} }, [{ approvals: { threshold: 1 } }], [
+      approvers[0].publicKey,
+    ]);
+
+    await expectError(approve(escrow, approvers[1]), "NotApprover");
+  });
+});
@@ -0,0 +1,140 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import {
+  SOL,
+  expectError,
+  fundedWallet,
+  lamports,
+  metadataPda,
+  mintNft,
+  programs,
+  provider,
+  royaltyConfigPda,
+  setupMarketplace,
+} from "./helpers";
+
+describe("royalty-sol", () => {
+  let creator: anchor.web3.Keypair;
+  let payer: anchor.web3.Keypair;
+  let mint: anchor.web3.PublicKey;
+  const seller = anchor.web3.Keypair.generate().publicKey;
+  const price = SOL;
+
+  const [platformSolTreasury] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("platform_sol_treasury")],
+    programs.royalty.programId
+  );
+  const vaultPda = (owner: anchor.web3.PublicKey) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("payment_vault"), owner.toBuffer()],
+      programs.royalty.programId
+    )[0];
+
+  const distribute = (
+    vault: anchor.web3.PublicKey | null,
+    creators: anchor.web3.PublicKey[] = [creator.publicKey]
+  ) =>
+    programs.royalty.methods
+      .distributePaymentSol(new anchor.BN(price))
+      .accountsPartial({
+        royaltyConfig: royaltyConfigPda,
+        payer: payer.publicKey,
+        vault,
+        seller,
+        platformSolTreasury,
+        mint,
+        metadata: metadataPda(mint),
+      })
+      .remainingAccounts(
+        creators.map((pubkey) => ({ pubkey, isSigner: false, isWritable: true }))
+      )
+      .signers([payer])
+      .rpc();
+
+  before(async () => {
+    await setupMarketplace();
+    creator = await fundedWallet();
+    payer = await fundedWallet();
+    mint = await mintNft(creator);
+
+    await programs.royalty.methods
+      .createPaymentVault()
+      .accountsPartial({
+        vault: vaultPda(payer.publicKey),
+        owner: payer.publicKey,
+        payer: provider.wallet.publicKey,
+      })
+      .signers([payer])
+      .rpc();
+  });
+
+  it("splits a payer-funded sale between treasury, creator and seller", async () => {
+    const { platformFeeBasisPoints } = await programs.royalty.account.royaltyConfig.fetch(
+      royaltyConfigPda
+    );
+    const platformFee = (price * platformFeeBasisPoints) / 10_000;
+    const royalty = (price * 500) / 10_000;
+
+    const treasuryBefore = await lamports(platformSolTreasury);
+    const creatorBefore = await lamports(creator.publicKey);
+    await distribute(null);
+
+    assert.equal((await lamports(platformSolTreasury)) - treasuryBefore, platformFee);
+    assert.equal((await lamports(creator.publicKey)) - creatorBefore, royalty);
+    assert.equal(await lamports(seller), price - platformFee - royalty);
+  });
+
+  it("pays a sale out of the payer's vault", async () => {
+    const vault = vaultPda(payer.publicKey);
+    const fund = new anchor.web3.Transaction().add(
+      anchor.web3.SystemProgram.transfer({
+        fromPubkey: provider.wallet.publicKey,
+        toPubkey: vault,
+        lamports: price,
+      })
+    );
+    await provider.sendAndConfirm(fund);
+
+    const vaultBefore = await lamports(vault);
+    const payerBefore = await lamports(payer.publicKey);
+    const configBefore = await programs.royalty.account.royaltyConfig.fetch(royaltyConfigPda);
+    await distribute(vault);
+
+    assert.equal(vaultBefore - (await lamports(vault)), price);
+    assert.equal(await lamports(payer.publicKey), payerBefore);
+    const configAfter = await programs.royalty.account.royaltyConfig.fetch(royaltyConfigPda);
+    assert.ok(configAfter.totalSolFeesCollected.gt(configBefore.totalSolFeesCollected));
+    assert.ok(configAfter.totalTokenFeesCollected.eq(configBefore.totalTokenFeesCollected));
+  });
+
+  it("rejects a vault sale the vault cannot cover", async () => {
+    // The previous sale drained the vault down to its rent
+    await expectError(distribute(vaultPda(payer.publicKey)), "InsufficientFunds");
+  });
+
+  it("lets the config authority withdraw collected SOL fees", async () => {
+    const withdraw = (authority: anchor.web3.Keypair | null, amount: number) =>
+      programs.royalty.methods
+        .withdrawPlatformSolFees(new anchor.BN(amount))
+        .accountsPartial({
+          royaltyConfig: royaltyConfigPda,
+          authority: authority ? authority.publicKey : provider.wallet.publicKey,
+          platformSolTreasury,
+        })
+        .signers(authority ? [authority] : [])
+        .rpc();
+
+    await expectError(withdraw(payer, 1000), "ConstraintHasOne");
+
+    const treasuryBefore = await lamports(platformSolTreasury);
+    await withdraw(null, 1000);
+    assert.equal(treasuryBefore - (await lamports(platformSolTreasury)), 1000);
+  });
+
+  it("rejects remaining accounts that are not verified creators", async () => {
+    await expectError(
+      distribute(null, [creator.publicKey, anchor.web3.Keypair.generate().publicKey]),
+      "UnknownCreatorAccount"
+    );
+  });
+});
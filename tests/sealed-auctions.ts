@@ -0,0 +1,172 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import { createHash } from "crypto";
+import {
+  SOL,
+  ata,
+  auctionPda,
+  chainTime,
+  claimAuction,
+  expectError,
+  fundedWallet,
+  lamports,
+  marketplacePda,
+  metadataPda,
+  mintNft,
+  programs,
+  setupMarketplace,
+  tokenBalance,
+  useShortAuctions,
+  waitUntil,
+  wallet,
+} from "./helpers";
+
+describe("sealed-auctions", () => {
+  let winner: anchor.web3.Keypair;
+  let runnerUp: anchor.web3.Keypair;
+
+  const sealedBidPda = (auction: anchor.web3.PublicKey, bidder: anchor.web3.PublicKey) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("sealed_bid"), auction.toBuffer(), bidder.toBuffer()],
+      programs.auction.programId
+    )[0];
+
+  const commitment = (bidder: anchor.web3.PublicKey, amount: number, salt: Buffer) =>
+    Array.from(
+      createHash("sha256")
+        .update(bidder.toBuffer())
+        .update(new anchor.BN(amount).toArrayLike(Buffer, "le", 8))
+        .update(salt)
+        .digest()
+    );
+
+  const createSealedAuction = async (mint: anchor.web3.PublicKey) => {
+    const auction = auctionPda(mint, wallet);
+    const startTime = (await chainTime()) + 2;
+    const endTime = startTime + 4;
+    const revealEndTime = endTime + 4;
+    await programs.auction.methods
+      .createSealedAuction(
+        new anchor.BN(startTime),
+        new anchor.BN(endTime),
+        new anchor.BN(revealEndTime),
+        new anchor.BN(SOL),
+        { secondPrice: {} },
+        null
+      )
+      .accountsPartial({
+        auction,
+        seller: wallet,
+        mint,
+        sellerTokenAccount: ata(mint, wallet),
+        auctionTokenAccount: ata(mint, auction),
+        metadata: metadataPda(mint),
+        paymentMint: null,
+        auctionPaymentAccount: null,
+        bidHistory: null,
+        marketplace: marketplacePda,
+      })
+      .rpc();
+    return { auction, startTime, endTime, revealEndTime };
+  };
+
+  const commitBid = (
+    auction: anchor.web3.PublicKey,
+    bidder: anchor.web3.Keypair,
+    amount: number,
+    salt: Buffer,
+    collateral: number
+  ) =>
+    programs.auction.methods
+      .commitBid(commitment(bidder.publicKey, amount, salt), new anchor.BN(collateral))
+      .accountsPartial({
+        auction,
+        sealedBid: sealedBidPda(auction, bidder.publicKey),
+        bidder: bidder.publicKey,
+        bidderPaymentAccount: null,
+        auctionPaymentAccount: null,
+      })
+      .signers([bidder])
+      .rpc();
+
+  const revealBid = (
+    auction: anchor.web3.PublicKey,
+    bidder: anchor.web3.Keypair,
+    amount: number,
+    salt: Buffer
+  ) =>
+    programs.auction.methods
+      .revealBid(new anchor.BN(amount), Array.from(salt))
+      .accountsPartial({
+        auction,
+        sealedBid: sealedBidPda(auction, bidder.publicKey),
+        bidder: bidder.publicKey,
+      })
+      .signers([bidder])
+      .rpc();
+
+  const refundSealedBid = (auction: anchor.web3.PublicKey, bidder: anchor.web3.PublicKey) =>
+    programs.auction.methods
+      .refundSealedBid()
+      .accountsPartial({
+        auction,
+        sealedBid: sealedBidPda(auction, bidder),
+        bidder,
+        bidderPaymentAccount: null,
+        auctionPaymentAccount: null,
+      })
+      .rpc();
+
+  // Refunds `bidder`'s collateral, returning how much it received besides the bid account rent
+  const refundedAmount = async (auction: anchor.web3.PublicKey, bidder: anchor.web3.PublicKey) => {
+    const rent = await lamports(sealedBidPda(auction, bidder));
+    const before = await lamports(bidder);
+    await refundSealedBid(auction, bidder);
+    return (await lamports(bidder)) - before - rent;
+  };
+
+  before(async () => {
+    await setupMarketplace();
+    await useShortAuctions();
+    winner = await fundedWallet();
+    runnerUp = await fundedWallet();
+  });
+
+  it("settles at the second price and refunds collateral from the escrow", async () => {
+    const mint = await mintNft();
+    const winnerSalt = Buffer.alloc(32, 1);
+    const runnerUpSalt = Buffer.alloc(32, 2);
+    const { auction, startTime, endTime, revealEndTime } = await createSealedAuction(mint);
+
+    await waitUntil(startTime);
+    await commitBid(auction, winner, 2.5 * SOL, winnerSalt, 3 * SOL);
+    await commitBid(auction, runnerUp, 1.5 * SOL, runnerUpSalt, 2 * SOL);
+
+    await waitUntil(endTime);
+    // A reveal that does not match the commitment is rejected
+    await expectError(revealBid(auction, winner, 2.5 * SOL, runnerUpSalt), "InvalidReveal");
+    await revealBid(auction, winner, 2.5 * SOL, winnerSalt);
+    await revealBid(auction, runnerUp, 1.5 * SOL, runnerUpSalt);
+
+    await waitUntil(revealEndTime);
+    await claimAuction(mint, wallet, winner.publicKey, [wallet]);
+    assert.equal(await tokenBalance(ata(mint, winner.publicKey)), "1");
+
+    // The winner pays the runner-up's bid, everyone else gets their collateral back
+    assert.equal(await refundedAmount(auction, winner.publicKey), 1.5 * SOL);
+    assert.equal(await refundedAmount(auction, runnerUp.publicKey), 2 * SOL);
+
+    const settled = await programs.auction.account.auctionState.fetch(auction);
+    assert.equal(settled.openSealedBids, 0);
+  });
+
+  it("rejects refunds before the reveal phase ends", async () => {
+    const mint = await mintNft();
+    const salt = Buffer.alloc(32, 3);
+    const { auction, startTime } = await createSealedAuction(mint);
+    await waitUntil(startTime);
+    await commitBid(auction, runnerUp, 1.5 * SOL, salt, 2 * SOL);
+
+    await expectError(refundSealedBid(auction, runnerUp.publicKey), "AuctionNotEnded");
+  });
+});
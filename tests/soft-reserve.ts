@@ -0,0 +1,68 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import {
+  SOL,
+  ata,
+  claimAuction,
+  createAuction,
+  expectError,
+  fundedWallet,
+  mintNft,
+  placeBid,
+  programs,
+  setupMarketplace,
+  tokenBalance,
+  useShortAuctions,
+  waitUntil,
+  wallet,
+} from "./helpers";
+
+describe("soft-reserve", () => {
+  let bidder: anchor.web3.Keypair;
+
+  const acceptHighestBid = (auction: anchor.web3.PublicKey) =>
+    programs.auction.methods
+      .acceptHighestBid()
+      .accountsPartial({ auction, seller: wallet })
+      .rpc();
+
+  before(async () => {
+    await setupMarketplace();
+    await useShortAuctions();
+    bidder = await fundedWallet();
+  });
+
+  it("lets the seller accept a top bid below a soft reserve", async () => {
+    const mint = await mintNft();
+    const { auction, startTime, endTime } = await createAuction(mint, {
+      duration: 4,
+      reservePrice: 2 * SOL,
+      reserveMode: { soft: { gracePeriod: new anchor.BN(60) } },
+    });
+    await waitUntil(startTime);
+    // Soft reserve auctions open at the minimum increment
+    await placeBid(auction, bidder, SOL / 2);
+    await waitUntil(endTime);
+
+    // Settling is held back while the seller can still accept
+    await expectError(
+      claimAuction(mint, wallet, bidder.publicKey, [wallet]),
+      "GracePeriodActive"
+    );
+
+    await acceptHighestBid(auction);
+    await claimAuction(mint, wallet, bidder.publicKey, [wallet]);
+
+    const settled = await programs.auction.account.auctionState.fetch(auction);
+    assert.isTrue(settled.sellerAcceptedBid);
+    assert.ok(settled.winner.equals(bidder.publicKey));
+    assert.equal(await tokenBalance(ata(mint, bidder.publicKey)), "1");
+  });
+
+  it("rejects accepting a bid on a hard reserve auction", async () => {
+    const mint = await mintNft();
+    const { auction } = await createAuction(mint);
+
+    await expectError(acceptHighestBid(auction), "NotSoftReserveAuction");
+  });
+});
@@ -0,0 +1,84 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import {
+  ata,
+  claimAuction,
+  createAuction,
+  createPaymentMint,
+  expectError,
+  fundTokens,
+  fundedWallet,
+  marketplacePda,
+  mintNft,
+  placeBid,
+  programs,
+  removePaymentMint,
+  setupMarketplace,
+  tokenBalance,
+  useShortAuctions,
+  waitUntil,
+  wallet,
+} from "./helpers";
+
+describe("spl-auctions", () => {
+  let bidder: anchor.web3.Keypair;
+  let paymentMint: anchor.web3.PublicKey;
+  let treasury: anchor.web3.PublicKey;
+  const price = 50_000_000;
+
+  before(async () => {
+    await setupMarketplace();
+    await useShortAuctions();
+    bidder = await fundedWallet();
+    paymentMint = await createPaymentMint();
+    ({ treasury } = await programs.marketplace.account.marketplaceState.fetch(marketplacePda));
+
+    await fundTokens(paymentMint, bidder.publicKey, 10 * price);
+    await fundTokens(paymentMint, wallet);
+    await fundTokens(paymentMint, treasury);
+  });
+
+  after(() => removePaymentMint(paymentMint));
+
+  it("escrows token bids and pays the seller in the payment mint", async () => {
+    const mint = await mintNft();
+    const { auction, startTime, endTime } = await createAuction(mint, {
+      duration: 4,
+      reservePrice: price,
+      minBidIncrement: price / 10,
+      paymentMint,
+    });
+    await waitUntil(startTime);
+    await placeBid(auction, bidder, price, { paymentMint });
+    assert.equal(await tokenBalance(ata(paymentMint, auction)), String(price));
+
+    await waitUntil(endTime);
+    const { feeBasisPoints } = await programs.marketplace.account.marketplaceState.fetch(
+      marketplacePda
+    );
+    const fee = (price * feeBasisPoints) / 10_000;
+    const sellerBefore = Number(await tokenBalance(ata(paymentMint, wallet)));
+    const treasuryBefore = Number(await tokenBalance(ata(paymentMint, treasury)));
+
+    // The seller is also the NFT's creator, so its ATA takes the royalty too
+    await claimAuction(mint, wallet, bidder.publicKey, [ata(paymentMint, wallet)], {
+      paymentMint,
+    });
+
+    assert.equal(Number(await tokenBalance(ata(paymentMint, wallet))) - sellerBefore, price - fee);
+    assert.equal(Number(await tokenBalance(ata(paymentMint, treasury))) - treasuryBefore, fee);
+    assert.equal(await tokenBalance(ata(mint, bidder.publicKey)), "1");
+  });
+
+  it("rejects a lamport bid on a token-denominated auction", async () => {
+    const mint = await mintNft();
+    const { auction, startTime } = await createAuction(mint, {
+      reservePrice: price,
+      minBidIncrement: price / 10,
+      paymentMint,
+    });
+    await waitUntil(startTime);
+
+    await expectError(placeBid(auction, bidder, price), "PaymentAccountMissing");
+  });
+});
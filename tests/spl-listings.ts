@@ -0,0 +1,93 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import {
+  SOL,
+  buyNft,
+  createPaymentMint,
+  expectError,
+  fundTokens,
+  fundedWallet,
+  listNft,
+  listingPda,
+  marketplacePda,
+  mintNft,
+  programs,
+  removePaymentMint,
+  setupMarketplace,
+  tokenBalance,
+  wallet,
+} from "./helpers";
+
+describe("spl-listings", () => {
+  let buyer: anchor.web3.Keypair;
+  let paymentMint: anchor.web3.PublicKey;
+  let treasury: anchor.web3.PublicKey;
+  const price = 100_000_000;
+
+  before(async () => {
+    await setupMarketplace();
+    buyer = await fundedWallet();
+    paymentMint = await createPaymentMint();
+    ({ treasury } = await programs.marketplace.account.marketplaceState.fetch(marketplacePda));
+
+    await fundTokens(paymentMint, buyer.publicKey, 10 * price);
+    await fundTokens(paymentMint, wallet);
+    await fundTokens(paymentMint, treasury);
+  });
+
+  after(() => removePaymentMint(paymentMint));
+
+  it("sells a listing priced in an accepted payment mint", async () => {
+    const mint = await mintNft();
+    await listNft(mint, price, undefined, { paymentMint });
+
+    const listing = await programs.listing.account.listingState.fetch(listingPda(mint, wallet));
+    assert.ok(listing.paymentMint.equals(paymentMint));
+
+    const { feeBasisPoints } = await programs.marketplace.account.marketplaceState.fetch(
+      marketplacePda
+    );
+    const fee = (price * feeBasisPoints) / 10_000;
+    const buyerAccount = await fundTokens(paymentMint, buyer.publicKey);
+    const sellerAccount = await fundTokens(paymentMint, wallet);
+    const treasuryAccount = await fundTokens(paymentMint, treasury);
+    const buyerBefore = Number(await tokenBalance(buyerAccount));
+    const sellerBefore = Number(await tokenBalance(sellerAccount));
+    const treasuryBefore = Number(await tokenBalance(treasuryAccount));
+
+    // The seller is also the NFT's creator, so its ATA takes the royalty too
+    await buyNft(mint, wallet, buyer, [wallet], paymentMint);
+
+    assert.equal(buyerBefore - Number(await tokenBalance(buyerAccount)), price);
+    assert.equal(Number(await tokenBalance(sellerAccount)) - sellerBefore, price - fee);
+    assert.equal(Number(await tokenBalance(treasuryAccount)) - treasuryBefore, fee);
+  });
+
+  it("rejects listings priced in a mint the marketplace does not accept", async () => {
+    const mint = await mintNft();
+    await expectError(
+      listNft(mint, price, undefined, { paymentMint: anchor.web3.Keypair.generate().publicKey }),
+      "PaymentMintNotAccepted"
+    );
+  });
+
+  it("rejects buying a token-priced listing with lamports", async () => {
+    const mint = await mintNft();
+    await listNft(mint, price, undefined, { paymentMint });
+
+    await expectError(buyNft(mint, wallet, buyer), "PaymentAccountMissing");
+  });
+
+  it("leaves listings already on the current layout untouched by migrate_listing", async () => {
+    const mint = await mintNft();
+    await listNft(mint, SOL);
+
+    await expectError(
+      programs.listing.methods
+        .migrateListing()
+        .accounts({ listing: listingPda(mint, wallet), payer: wallet })
+        .rpc(),
+      "ListingAlreadyMigrated"
+    );
+  });
+});
@@ -0,0 +1,136 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import {
+  SOL,
+  accountMeta,
+  ata,
+  createEscrow,
+  expectError,
+  fundTokens,
+  fundedWallet,
+  lamports,
+  mintNft,
+  programs,
+  releaseEscrow,
+  setupMarketplace,
+  swapPda,
+  tokenBalance,
+} from "./helpers";
+
+describe("swaps", () => {
+  let initiator: anchor.web3.Keypair;
+  let counterparty: anchor.web3.Keypair;
+
+  const openSwap = async () => {
+    const escrow = await createEscrow(initiator, { swap: {} });
+    await programs.escrow.methods
+      .createSwap(counterparty.publicKey)
+      .accountsPartial({ escrow, swap: swapPda(escrow), authority: initiator.publicKey })
+      .signers([initiator])
+      .rpc();
+    return escrow;
+  };
+
+  const depositNft = (
+    escrow: anchor.web3.PublicKey,
+    party: anchor.web3.Keypair,
+    mint: anchor.web3.PublicKey
+  ) =>
+    programs.escrow.methods
+      .depositSwapNft()
+      .accountsPartial({
+        escrow,
+        swap: swapPda(escrow),
+        party: party.publicKey,
+        mint,
+        partyTokenAccount: ata(mint, party.publicKey),
+        escrowTokenAccount: ata(mint, escrow),
+      })
+      .signers([party])
+      .rpc();
+
+  const accept = (escrow: anchor.web3.PublicKey, party: anchor.web3.Keypair) =>
+    programs.escrow.methods
+      .acceptSwap()
+      .accountsPartial({ escrow, swap: swapPda(escrow), party: party.publicKey })
+      .signers([party])
+      .rpc();
+
+  before(async () => {
+    await setupMarketplace();
+    initiator = await fundedWallet();
+    counterparty = await fundedWallet();
+  });
+
+  it("swaps an NFT for an NFT plus SOL once both parties accept", async () => {
+    const offered = await mintNft(initiator);
+    const wanted = await mintNft(counterparty);
+    const escrow = await openSwap();
+    await depositNft(escrow, initiator, offered);
+    await depositNft(escrow, counterparty, wanted);
+    await programs.escrow.methods
+      .depositSwapSol(new anchor.BN(SOL))
+      .accountsPartial({ escrow, swap: swapPda(escrow), party: counterparty.publicKey })
+      .signers([counterparty])
+      .rpc();
+    await accept(escrow, initiator);
+    await accept(escrow, counterparty);
+
+    // The escrow only sends NFTs to ATAs that already exist
+    await fundTokens(offered, counterparty.publicKey);
+    await fundTokens(wanted, initiator.publicKey);
+    const before = await lamports(initiator.publicKey);
+    await releaseEscrow(escrow, initiator, {
+      swap: swapPda(escrow),
+      remainingAccounts: [
+        accountMeta(initiator.publicKey),
+        accountMeta(counterparty.publicKey),
+        accountMeta(ata(offered, escrow)),
+        accountMeta(ata(offered, counterparty.publicKey)),
+        accountMeta(ata(wanted, escrow)),
+        accountMeta(ata(wanted, initiator.publicKey)),
+      ],
+    });
+
+    assert.equal(await tokenBalance(ata(offered, counterparty.publicKey)), "1");
+    assert.equal(await tokenBalance(ata(wanted, initiator.publicKey)), "1");
+    assert.equal((await lamports(initiator.publicKey)) - before, SOL);
+    const state = await programs.escrow.account.escrowState.fetch(escrow);
+    assert.isTrue(state.isReleased);
+  });
+
+  it("refuses to release before both accept and lets either party cancel", async () => {
+    const offered = await mintNft(initiator);
+    const escrow = await openSwap();
+    await depositNft(escrow, initiator, offered);
+    await accept(escrow, initiator);
+
+    await expectError(
+      releaseEscrow(escrow, initiator, {
+        swap: swapPda(escrow),
+        remainingAccounts: [accountMeta(initiator.publicKey), accountMeta(counterparty.publicKey)],
+      }),
+      "SwapNotAccepted"
+    );
+
+    await programs.escrow.methods
+      .cancelSwap()
+      .accountsPartial({
+        escrow,
+        swap: swapPda(escrow),
+        party: counterparty.publicKey,
+        initiator: initiator.publicKey,
+        counterparty: counterparty.publicKey,
+      })
+      .remainingAccounts([
+        accountMeta(ata(offered, escrow)),
+        accountMeta(ata(offered, initiator.publicKey)),
+      ])
+      .signers([counterparty])
+      .rpc();
+
+    assert.equal(await tokenBalance(ata(offered, initiator.publicKey)), "1");
+    const swap = await programs.escrow.account.swapState.fetch(swapPda(escrow));
+    assert.isTrue(swap.isCanceled);
+  });
+});
@@ -0,0 +1,82 @@
+import * as anchor from "@coral-xyz/anchor";
+import { assert } from "chai";
+import {
+  SOL,
+  buyNft,
+  expectError,
+  fundedWallet,
+  listNft,
+  marketplacePda,
+  mintNft,
+  programs,
+  setupMarketplace,
+  wallet,
+} from "./helpers";
+
+describe("trading-programs", () => {
+  let buyer: anchor.web3.Keypair;
+
+  const stats = () => programs.marketplace.account.marketplaceState.fetch(marketplacePda);
+
+  before(async () => {
+    await setupMarketplace();
+    buyer = await fundedWallet();
+  });
+
+  it("records a listing sale in the marketplace stats", async () => {
+    const mint = await mintNft();
+    await listNft(mint, SOL);
+
+    const before = await stats();
+    await buyNft(mint, wallet, buyer);
+    const after = await stats();
+
+    assert.ok(after.totalSales.eq(before.totalSales.addn(1)));
+    assert.ok(after.totalVolume.eq(before.totalVolume.addn(SOL)));
+  });
+
+  it("rejects stats reported without a registered program's sale authority", async () => {
+    // The wallet signs in place of the listing program's sale authority PDA
+    await expectError(
+      programs.marketplace.methods
+        .updateStats(new anchor.BN(1000 * SOL))
+        .accountsPartial({
+          marketplace: marketplacePda,
+          saleAuthority: wallet,
+          callerProgram: programs.listing.programId,
+        })
+        .rpc(),
+      "ConstraintSeeds"
+    );
+  });
+
+  it("rejects sales from an unregistered trading program", async () => {
+    const mint = await mintNft();
+    await listNft(mint, SOL);
+
+    await programs.marketplace.methods
+      .unregisterTradingProgram(programs.listing.programId)
+      .accounts({ authority: wallet })
+      .rpc();
+    try {
+      await expectError(buyNft(mint, wallet, buyer), "ProgramNotRegistered");
+    } finally {
+      await programs.marketplace.methods
+        .registerTradingProgram(programs.listing.programId)
+        .accounts({ authority: wallet })
+        .rpc();
+    }
+  });
+
+  it("only lets the marketplace authority register trading programs", async () => {
+    const outsider = await fundedWallet(1);
+    await expectError(
+      programs.marketplace.methods
+        .registerTradingProgram(anchor.web3.Keypair.generate().publicKey)
+        .accounts({ authority: outsider.publicKey })
+        .signers([outsider])
+        .rpc(),
+      "ConstraintHasOne"
+    );
+  });
+});
@@ -0,0 +1,110 @@
+import * as anchor from "@coral-xyz/anchor";
+import { IdlTypes } from "@coral-xyz/anchor";
+import { assert } from "chai";
+import { Escrow } from "../target/types/escrow";
+import {
+  SOL,
+  chainTime,
+  createEscrow,
+  expectError,
+  fundedWallet,
+  lamports,
+  programs,
+  setupMarketplace,
+  waitUntil,
+  wallet,
+} from "./helpers";
+
+describe("vesting", () => {
+  let creator: anchor.web3.Keypair;
+  let beneficiary: anchor.web3.Keypair;
+
+  const schedulePda = (escrow: anchor.web3.PublicKey) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("vesting"), escrow.toBuffer()],
+      programs.escrow.programId
+    )[0];
+
+  const createSchedule = async (
+    kind: IdlTypes<Escrow>["vestingKind"],
+    startTime: number,
+    cliffTime: number,
+    endTime: number
+  ) => {
+    const escrow = await createEscrow(creator, { vesting: {} });
+    await programs.escrow.methods
+      .createVestingSchedule(
+        beneficiary.publicKey,
+        new anchor.BN(SOL),
+        kind,
+        new anchor.BN(startTime),
+        new anchor.BN(cliffTime),
+        new anchor.BN(endTime)
+      )
+      .accountsPartial({ escrow, schedule: schedulePda(escrow), authority: creator.publicKey })
+      .signers([creator])
+      .rpc();
+    return escrow;
+  };
+
+  // Anyone can push vested SOL to the beneficiary; here the provider wallet does
+  const claimVested = (escrow: anchor.web3.PublicKey) =>
+    programs.escrow.methods
+      .claimVested()
+      .accountsPartial({
+        escrow,
+        schedule: schedulePda(escrow),
+        beneficiary: beneficiary.publicKey,
+        caller: wallet,
+      })
+      .rpc();
+
+  before(async () => {
+    await setupMarketplace();
+    creator = await fundedWallet();
+    beneficiary = await fundedWallet(1);
+  });
+
+  it("pays a cliff schedule out in full once the cliff passes", async () => {
+    const now = await chainTime();
+    const escrow = await createSchedule({ cliff: {} }, now, now + 3, now + 3600);
+    await waitUntil(now + 3);
+
+    const before = await lamports(beneficiary.publicKey);
+    await claimVested(escrow);
+
+    assert.equal((await lamports(beneficiary.publicKey)) - before, SOL);
+    const state = await programs.escrow.account.escrowState.fetch(escrow);
+    assert.isTrue(state.isReleased);
+    // A fully paid schedule cannot be claimed from again
+    await expectError(claimVested(escrow), "EscrowAlreadyReleased");
+  });
+
+  it("returns the unvested part of a revoked linear schedule to its creator", async () => {
+    const now = await chainTime();
+    const escrow = await createSchedule({ linear: {} }, now - 1800, now - 1800, now + 1800);
+
+    const creatorBefore = await lamports(creator.publicKey);
+    await programs.escrow.methods
+      .revokeVesting()
+      .accountsPartial({ escrow, schedule: schedulePda(escrow), creator: creator.publicKey })
+      .signers([creator])
+      .rpc();
+    const { totalAmount: vested } = await programs.escrow.account.vestingSchedule.fetch(
+      schedulePda(escrow)
+    );
+    assert.equal((await lamports(creator.publicKey)) - creatorBefore, SOL - vested.toNumber());
+
+    // What had vested at revocation stays claimable
+    const beneficiaryBefore = await lamports(beneficiary.publicKey);
+    await claimVested(escrow);
+    assert.equal((await lamports(beneficiary.publicKey)) - beneficiaryBefore, vested.toNumber());
+  });
+
+  it("rejects claiming before the cliff", async () => {
+    const now = await chainTime();
+    const escrow = await createSchedule({ linear: {} }, now, now + 3600, now + 7200);
+
+    await expectError(claimVested(escrow), "NothingVested");
+  });
+});